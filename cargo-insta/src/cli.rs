@@ -1,26 +1,34 @@
 use std::borrow::{Borrow, Cow};
 use std::error::Error;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::process;
 use std::{collections::HashSet, fmt};
 use std::{env, fs};
-use std::{io, process};
 
 use console::{set_colors_enabled, style, Key, Term};
 use insta::_cargo_insta_support::{
-    get_cargo, get_pending_dir, is_ci, path_to_storage, SnapshotPrinter, SnapshotUpdate,
-    TestRunner, ToolConfig, UnreferencedSnapshots,
+    diff_image_path, get_cargo, get_pending_dir, is_ci, path_to_storage, DiffStyle,
+    SnapshotPrinter, SnapshotUpdate, TestRunner, ToolConfig, UnreferencedSnapshots,
+};
+use insta::{
+    internals::{SnapshotContents, TextSnapshotContents},
+    Snapshot,
 };
-use insta::{internals::SnapshotContents, Snapshot};
 use itertools::Itertools;
 use semver::Version;
 use serde::Serialize;
+use similar::ChangeTag;
 use uuid::Uuid;
 
 use crate::cargo::{find_snapshot_roots, Package};
-use crate::container::{Operation, SnapshotContainer};
+use crate::container::{diff_hunks, Operation, PendingSnapshot, ReviewItem, SnapshotContainer};
+use crate::report::{self, ReportTarget};
+use crate::session::ReviewSession;
 use crate::utils::cargo_insta_version;
-use crate::utils::{err_msg, QuietExit};
+use crate::utils::{err_msg, next_diff_style, QuietExit};
 use crate::walk::{find_pending_snapshots, make_snapshot_walker, FindFlags};
+use crate::{tui, web};
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
 
@@ -78,6 +86,8 @@ enum Command {
     PendingSnapshots(PendingSnapshotsCommand),
     /// Shows a specific snapshot
     Show(ShowCommand),
+    /// Writes a static HTML report of all pending snapshots
+    Report(ReportCommand),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -131,6 +141,18 @@ struct ReviewCommand {
     /// External diff tool to use (e.g., "delta --side-by-side").
     #[arg(long, env = "INSTA_DIFF_TOOL")]
     diff_tool: Option<String>,
+    /// Review all pending snapshots in a full-screen terminal interface.
+    #[arg(long)]
+    tui: bool,
+    /// Continue an interrupted review, reapplying the decisions made so far.
+    #[arg(long, conflicts_with_all = ["tui", "web"])]
+    resume: bool,
+    /// Review all pending snapshots in the browser.
+    #[arg(long, conflicts_with = "tui")]
+    web: bool,
+    /// The port of the local review server (picks a free port by default).
+    #[arg(long, requires = "web", default_value = "0")]
+    port: u16,
 }
 
 #[derive(Args, Debug)]
@@ -264,6 +286,10 @@ struct TestCommand {
     /// Disable running doctests when using nextest test runner
     #[arg(long, alias = "dnd")]
     disable_nextest_doctest: bool,
+    /// Write a report of all snapshot assertions, e.g. `junit=report.xml` or
+    /// `json=report.json`.  Can be passed multiple times.
+    #[arg(long, value_name = "FORMAT=PATH")]
+    report: Vec<ReportTarget>,
     #[command(flatten)]
     target_args: TargetArgs,
     #[command(flatten)]
@@ -306,6 +332,28 @@ struct ShowCommand {
     path: PathBuf,
 }
 
+#[derive(Args, Debug)]
+#[command(rename_all = "kebab-case")]
+struct ReportCommand {
+    #[command(flatten)]
+    target_args: TargetArgs,
+    /// The directory to write the HTML report into.
+    #[arg(long, value_name = "DIR")]
+    html: PathBuf,
+}
+
+/// The answer to [`query_snapshot`].
+enum ReviewChoice {
+    Decide(Operation),
+    /// Accept only the selected diff hunks.
+    AcceptHunks(Vec<bool>),
+    /// Undo the previous decision.
+    Undo,
+    /// The new snapshot was edited and should be shown again.
+    Edited(TextSnapshotContents),
+}
+
+/// Asks the user what to do with a snapshot.
 #[allow(clippy::too_many_arguments)]
 fn query_snapshot(
     workspace_root: &Path,
@@ -319,7 +367,9 @@ fn query_snapshot(
     snapshot_file: Option<&Path>,
     show_info: &mut bool,
     show_diff: &mut bool,
-) -> Result<Operation, Box<dyn Error>> {
+    diff_style: &mut DiffStyle,
+    can_undo: bool,
+) -> Result<ReviewChoice, Box<dyn Error>> {
     // Check if we're running in a TTY environment
     if !term.is_term() {
         return Err(err_msg(
@@ -333,6 +383,8 @@ fn query_snapshot(
         ));
     }
 
+    let mut edit_error = None;
+
     loop {
         term.clear_screen()?;
 
@@ -350,6 +402,7 @@ fn query_snapshot(
         printer.set_line(line);
         printer.set_show_info(*show_info);
         printer.set_show_diff(*show_diff);
+        printer.set_diff_style(*diff_style);
         printer.print();
 
         println!();
@@ -378,6 +431,25 @@ fn query_snapshot(
             style("s").yellow().bold(),
             style("keep both for now").dim()
         );
+
+        let hunk_texts = match (old.and_then(|x| x.as_text()), new.as_text()) {
+            (Some(old), Some(new)) => Some((old.to_string(), new.to_string())),
+            _ => None,
+        };
+        if hunk_texts.is_some() {
+            println!(
+                "  {} partial    {}",
+                style("p").green().bold(),
+                style("accept individual hunks").dim()
+            );
+        }
+        if new.as_text().is_some() {
+            println!(
+                "  {} edit       {}",
+                style("e").cyan().bold(),
+                style("edit the new snapshot in $VISUAL or $EDITOR").dim()
+            );
+        }
         println!(
             "  {} {} info  {}",
             style("i").cyan().bold(),
@@ -390,6 +462,18 @@ fn query_snapshot(
             if *show_diff { "hide" } else { "show" },
             style("toggle snapshot diff").dim()
         );
+        if *show_diff {
+            println!(
+                "  {} {}  {}",
+                style("v").cyan().bold(),
+                match next_diff_style(*diff_style) {
+                    DiffStyle::Unified => "unified  ",
+                    DiffStyle::SideBySide => "split    ",
+                    DiffStyle::Structural => "structure",
+                },
+                style("toggle diff layout").dim()
+            );
+        }
 
         let new_is_binary = new.contents().is_binary();
         let old_is_binary = old.map(|o| o.contents().is_binary()).unwrap_or(false);
@@ -409,21 +493,54 @@ fn query_snapshot(
             );
         }
 
+        if can_undo {
+            println!(
+                "  {} undo       {}",
+                style("u").cyan().bold(),
+                style("go back to the previous snapshot").dim()
+            );
+        }
+
         // Add a subtle hint about uppercase shortcuts at the bottom
         println!();
         println!(
             "  {}",
             style("Tip: Use uppercase A/R/S to apply to all remaining snapshots").dim()
         );
+        if let Some(err) = edit_error.take() {
+            println!();
+            println!("  {}: {}", style("error").red().bold(), err);
+        }
 
         loop {
             match term.read_key()? {
-                Key::Char('a') | Key::Enter => return Ok(Operation::Accept),
-                Key::Char('A') => return Ok(Operation::AcceptAll),
-                Key::Char('r') | Key::Escape => return Ok(Operation::Reject),
-                Key::Char('R') => return Ok(Operation::RejectAll),
-                Key::Char('s') | Key::Char(' ') => return Ok(Operation::Skip),
-                Key::Char('S') => return Ok(Operation::SkipAll),
+                Key::Char('a') | Key::Enter => return Ok(ReviewChoice::Decide(Operation::Accept)),
+                Key::Char('A') => return Ok(ReviewChoice::Decide(Operation::AcceptAll)),
+                Key::Char('r') | Key::Escape => return Ok(ReviewChoice::Decide(Operation::Reject)),
+                Key::Char('R') => return Ok(ReviewChoice::Decide(Operation::RejectAll)),
+                Key::Char('s') | Key::Char(' ') => {
+                    return Ok(ReviewChoice::Decide(Operation::Skip))
+                }
+                Key::Char('S') => return Ok(ReviewChoice::Decide(Operation::SkipAll)),
+                Key::Char('u') if can_undo => return Ok(ReviewChoice::Undo),
+                Key::Char('p') => {
+                    if let Some((ref old_text, ref new_text)) = hunk_texts {
+                        if let Some(selection) = query_hunks(term, old_text, new_text)? {
+                            return Ok(ReviewChoice::AcceptHunks(selection));
+                        }
+                        break;
+                    }
+                }
+                Key::Char('e') => {
+                    if let Some(contents) = new.as_text() {
+                        match edit_snapshot(contents, snapshot_file) {
+                            Ok(Some(edited)) => return Ok(ReviewChoice::Edited(edited)),
+                            Ok(None) => {}
+                            Err(err) => edit_error = Some(err.to_string()),
+                        }
+                        break;
+                    }
+                }
                 Key::Char('i') => {
                     *show_info = !*show_info;
                     break;
@@ -432,17 +549,29 @@ fn query_snapshot(
                     *show_diff = !*show_diff;
                     break;
                 }
+                Key::Char('v') if *show_diff => {
+                    *diff_style = next_diff_style(*diff_style);
+                    break;
+                }
                 Key::Char('o') => {
-                    if let Some(old) = old {
-                        if let Some(path) = old.build_binary_path(snapshot_file.unwrap()) {
+                    // inline snapshots have no file and thus nothing to open
+                    if let Some(snapshot_file) = snapshot_file {
+                        if let Some(old) = old {
+                            if let Some(path) = old.build_binary_path(snapshot_file) {
+                                open::that_detached(path)?;
+                            }
+                        }
+
+                        if let Some(path) =
+                            new.build_binary_path(snapshot_file.with_extension("snap.new"))
+                        {
                             open::that_detached(path)?;
                         }
-                    }
 
-                    if let Some(path) =
-                        new.build_binary_path(snapshot_file.unwrap().with_extension("snap.new"))
-                    {
-                        open::that_detached(path)?;
+                        let diff_image = diff_image_path(snapshot_file);
+                        if diff_image.is_file() {
+                            open::that_detached(diff_image)?;
+                        }
                     }
 
                     // there's no break here because there's no need to re-output anything
@@ -453,6 +582,149 @@ fn query_snapshot(
     }
 }
 
+/// Opens the contents of a snapshot in the user's editor.
+///
+/// Returns the edited contents, or `None` if they were not changed.
+fn edit_snapshot(
+    contents: &TextSnapshotContents,
+    snapshot_file: Option<&Path>,
+) -> Result<Option<TextSnapshotContents>, Box<dyn Error>> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|x| !x.trim().is_empty())
+        .ok_or_else(|| err_msg("neither $VISUAL nor $EDITOR is set"))?;
+    edit_snapshot_with(&editor, contents, snapshot_file)
+}
+
+/// Opens the contents of a snapshot with the given editor command.
+fn edit_snapshot_with(
+    editor: &str,
+    contents: &TextSnapshotContents,
+    snapshot_file: Option<&Path>,
+) -> Result<Option<TextSnapshotContents>, Box<dyn Error>> {
+    // Use the snapshot name for the file so editors can pick up the file type.
+    let prefix = snapshot_file
+        .and_then(|x| x.file_stem())
+        .and_then(|x| x.to_str())
+        .map(|x| format!("{x}."))
+        .unwrap_or_else(|| "snapshot.".to_string());
+    let mut file = tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(".snap")
+        .tempfile()?;
+    let original = contents.to_string();
+    writeln!(file, "{original}")?;
+    file.flush()?;
+
+    // Split the editor to support arguments (e.g., "code --wait")
+    let mut parts = editor.split_whitespace();
+    let status = process::Command::new(parts.next().unwrap())
+        .args(parts)
+        .arg(file.path())
+        .status()
+        .map_err(|e| err_msg(format!("failed to run editor `{editor}`: {e}")))?;
+    if !status.success() {
+        return Err(err_msg(format!("editor `{editor}` exited with {status}")));
+    }
+
+    let edited = TextSnapshotContents::new(fs::read_to_string(file.path())?, contents.kind);
+    if edited.to_string() == original {
+        Ok(None)
+    } else {
+        Ok(Some(edited))
+    }
+}
+
+/// Asks for every diff hunk of a text snapshot whether it should be accepted.
+///
+/// Returns `None` if the user went back to the snapshot without deciding.
+fn query_hunks(term: &Term, old: &str, new: &str) -> Result<Option<Vec<bool>>, Box<dyn Error>> {
+    let (diff, hunks) = diff_hunks(old, new);
+    let mut accepted = Vec::with_capacity(hunks.len());
+
+    while accepted.len() < hunks.len() {
+        let idx = accepted.len();
+        term.clear_screen()?;
+        println!(
+            "{}{}{}",
+            style("Reviewing hunk [").bold(),
+            style(format!("{}/{}", idx + 1, hunks.len()))
+                .yellow()
+                .bold(),
+            style("]:").bold(),
+        );
+        println!();
+        for op in &hunks[idx] {
+            for change in diff.iter_changes(op) {
+                let line = change.to_string_lossy();
+                let line = line.trim_end_matches(['\r', '\n']);
+                match change.tag() {
+                    ChangeTag::Insert => println!(
+                        "{:>5} {:>5} │{}",
+                        "",
+                        style(change.new_index().unwrap() + 1).cyan().dim().bold(),
+                        style(format!("+{line}")).green()
+                    ),
+                    ChangeTag::Delete => println!(
+                        "{:>5} {:>5} │{}",
+                        style(change.old_index().unwrap() + 1).cyan().dim(),
+                        "",
+                        style(format!("-{line}")).red()
+                    ),
+                    ChangeTag::Equal => println!(
+                        "{:>5} {:>5} │ {}",
+                        style(change.old_index().unwrap() + 1).cyan().dim(),
+                        style(change.new_index().unwrap() + 1).cyan().dim().bold(),
+                        style(line).dim()
+                    ),
+                }
+            }
+        }
+
+        println!();
+        println!(
+            "  {} accept     {}",
+            style("y").green().bold(),
+            style("take the new lines of this hunk").dim()
+        );
+        println!(
+            "  {} reject     {}",
+            style("n").red().bold(),
+            style("keep the old lines of this hunk").dim()
+        );
+        println!(
+            "  {} accept all {}",
+            style("a").green().bold(),
+            style("accept this and all remaining hunks").dim()
+        );
+        println!(
+            "  {} reject all {}",
+            style("d").red().bold(),
+            style("reject this and all remaining hunks").dim()
+        );
+        println!(
+            "  {} back       {}",
+            style("q").cyan().bold(),
+            style("return to the whole snapshot").dim()
+        );
+
+        loop {
+            match term.read_key()? {
+                Key::Char('y') => accepted.push(true),
+                Key::Char('n') => accepted.push(false),
+                Key::Char('a') => accepted.resize(hunks.len(), true),
+                Key::Char('d') => accepted.resize(hunks.len(), false),
+                Key::Char('q') | Key::Escape => return Ok(None),
+                _ => continue,
+            }
+            break;
+        }
+    }
+
+    Ok(Some(accepted))
+}
+
 fn handle_color(color: Option<ColorWhen>) {
     match color {
         Some(ColorWhen::Always) => {
@@ -469,6 +741,8 @@ fn handle_color(color: Option<ColorWhen>) {
 struct LocationInfo<'a> {
     tool_config: ToolConfig,
     workspace_root: PathBuf,
+    /// The cargo target directory of the workspace
+    target_dir: PathBuf,
     /// Packages to test
     packages: Vec<Package>,
     exts: Vec<&'a str>,
@@ -553,6 +827,7 @@ fn handle_target_args<'a>(
 
     Ok(LocationInfo {
         workspace_root,
+        target_dir: metadata.target_directory.as_std_path().to_path_buf(),
         packages,
         exts: target_args
         .extensions
@@ -690,18 +965,157 @@ fn snapshot_matches_filter(target_file: &Path, line: Option<u32>, filter: &str)
     target_file.ends_with(path_part)
 }
 
+/// Number of decisions that can be undone during an interactive review.
+///
+/// Containers whose decisions are out of reach of undo are written right away
+/// (like without undo), so an interrupted review only loses the most recent
+/// decisions.  These stay in the session and can be restored with `--resume`.
+const UNDO_LIMIT: usize = 20;
+
+/// A snapshot selected for review and the decision made for it.
+struct ReviewEntry {
+    key: String,
+    /// Index of the container holding the snapshot.
+    container: usize,
+    /// Index of the snapshot within its container.
+    snapshot: usize,
+    decision: Option<(Operation, Option<Vec<bool>>)>,
+    /// The new snapshot as it was before it was edited during the review.
+    original: Option<Snapshot>,
+}
+
+impl ReviewEntry {
+    /// Replaces the new snapshot with edited contents.
+    fn edit(&mut self, snapshot_ref: &mut PendingSnapshot, edited: TextSnapshotContents) {
+        let edited = snapshot_ref.new.with_contents(edited.into());
+        let previous = std::mem::replace(&mut snapshot_ref.new, edited);
+        self.original.get_or_insert(previous);
+    }
+
+    /// Reverts the decision and any edits made to the snapshot.
+    fn undo(&mut self, snapshot_ref: &mut PendingSnapshot) {
+        self.decision = None;
+        if let Some(original) = self.original.take() {
+            snapshot_ref.new = original;
+        }
+    }
+}
+
+/// The snapshots of a review grouped by the decision made for them.
+#[derive(Default)]
+struct ReviewSummary {
+    accepted: Vec<String>,
+    rejected: Vec<String>,
+    skipped: Vec<String>,
+}
+
+impl ReviewSummary {
+    /// Applies a decision to its snapshot and records it.
+    fn apply(
+        &mut self,
+        snapshot_ref: &mut PendingSnapshot,
+        decision: Option<(Operation, Option<Vec<bool>>)>,
+    ) {
+        match decision {
+            Some((_, Some(selection))) => {
+                snapshot_ref.accept_hunks(&selection);
+                let summary = format!(
+                    "{} ({}/{} hunks)",
+                    snapshot_ref.summary(),
+                    selection.iter().filter(|&&x| x).count(),
+                    selection.len()
+                );
+                match snapshot_ref.op {
+                    Operation::Reject | Operation::RejectAll => self.rejected.push(summary),
+                    _ => self.accepted.push(summary),
+                }
+            }
+            Some((Operation::Accept | Operation::AcceptAll, None)) => {
+                snapshot_ref.op = Operation::Accept;
+                self.accepted.push(snapshot_ref.summary());
+            }
+            Some((Operation::Reject | Operation::RejectAll, None)) => {
+                snapshot_ref.op = Operation::Reject;
+                self.rejected.push(snapshot_ref.summary());
+            }
+            Some((Operation::Skip | Operation::SkipAll, None)) | None => {
+                self.skipped.push(snapshot_ref.summary());
+            }
+        }
+    }
+}
+
+/// Applies the decisions of the entries before `end` and writes every
+/// container that has no entries after that.
+fn commit_reviewed(
+    snapshot_containers: &mut [(SnapshotContainer, &Package)],
+    entries: &mut [ReviewEntry],
+    end: usize,
+    committed: &mut [bool],
+    summary: &mut ReviewSummary,
+) -> Result<(), Box<dyn Error>> {
+    let mut start = 0;
+    while start < end {
+        let container = entries[start].container;
+        let len = entries[start..]
+            .iter()
+            .take_while(|x| x.container == container)
+            .count();
+        if start + len > end {
+            break;
+        }
+        if !committed[container] {
+            let snapshot_container = &mut snapshot_containers[container].0;
+            for entry in &mut entries[start..start + len] {
+                summary.apply(
+                    snapshot_container.snapshot_mut(entry.snapshot),
+                    entry.decision.take(),
+                );
+            }
+            snapshot_container.commit()?;
+            committed[container] = true;
+        }
+        start += len;
+    }
+    Ok(())
+}
+
+/// Asks whether an interrupted review session should be resumed.
+fn query_resume(term: &Term) -> Result<bool, Box<dyn Error>> {
+    if !term.is_term() {
+        eprintln!(
+            "{}: discarding an interrupted review session, use `cargo insta review --resume` \
+             to continue it",
+            style("warning").yellow().bold()
+        );
+        return Ok(false);
+    }
+    println!(
+        "An interrupted review session was found.  Resume it? {}",
+        style("[y/n]").dim()
+    );
+    loop {
+        match term.read_key()? {
+            Key::Char('y' | 'Y') | Key::Enter => return Ok(true),
+            Key::Char('n' | 'N') | Key::Escape => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
 /// Processes snapshot files for reviewing, accepting, or rejecting.
 fn review_snapshots(
     quiet: bool,
     snapshot_filter: Option<&[String]>,
     loc: &LocationInfo<'_>,
     op: Option<Operation>,
+    resume: bool,
 ) -> Result<(), Box<dyn Error>> {
     let term = Term::stdout();
 
     let (mut snapshot_containers, roots) = load_snapshot_containers(loc)?;
 
-    let snapshot_count = snapshot_containers.iter().map(|x| x.0.len()).sum();
+    let snapshot_count: usize = snapshot_containers.iter().map(|x| x.0.len()).sum();
 
     if snapshot_count == 0 {
         if !quiet {
@@ -721,12 +1135,10 @@ fn review_snapshots(
         return Ok(());
     }
 
-    let mut accepted = vec![];
-    let mut rejected = vec![];
-    let mut skipped = vec![];
-    let mut num = 0;
+    let mut summary = ReviewSummary::default();
     let mut show_info = true;
     let mut show_diff = true;
+    let mut diff_style = loc.tool_config.diff_style();
     let mut apply_to_all: Option<Operation> = None;
 
     // Non-interactive mode: if we have a filter and no TTY, just show diffs.
@@ -735,114 +1147,206 @@ fn review_snapshots(
         && !term.is_term()
         && (op.is_none() || matches!(op, Some(Operation::Reject)));
 
-    for (snapshot_container, package) in snapshot_containers.iter_mut() {
+    // Decisions of an interactive review are recorded so that an interrupted
+    // review can be resumed.
+    let mut session = if op.is_none() && !non_interactive_display {
+        let path = ReviewSession::path_for(&loc.target_dir);
+        Some(if resume || (path.is_file() && query_resume(&term)?) {
+            ReviewSession::resume(path)?
+        } else {
+            ReviewSession::new(path)
+        })
+    } else {
+        None
+    };
+
+    let mut entries = vec![];
+    for (container, (snapshot_container, _)) in snapshot_containers.iter_mut().enumerate() {
         let target_file = snapshot_container.target_file().to_path_buf();
-        let snapshot_file = snapshot_container.snapshot_file().map(|x| x.to_path_buf());
-        for snapshot_ref in snapshot_container.iter_snapshots() {
+        for (snapshot, snapshot_ref) in snapshot_container.iter_snapshots().enumerate() {
             // if a filter is provided, check if the snapshot reference is included
             if let Some(filter) = snapshot_filter {
                 if !filter
                     .iter()
                     .any(|f| snapshot_matches_filter(&target_file, snapshot_ref.line, f))
                 {
-                    skipped.push(snapshot_ref.summary());
+                    summary.skipped.push(snapshot_ref.summary());
                     continue;
                 }
             }
+            let key = format_snapshot_key(&loc.workspace_root, &target_file, snapshot_ref.line);
+            entries.push(ReviewEntry {
+                key,
+                container,
+                snapshot,
+                decision: None,
+                original: None,
+            });
+        }
+    }
 
-            num += 1;
+    // Indexes of the decisions that can be undone, most recent last.
+    let mut history: Vec<usize> = vec![];
+    let mut committed = vec![false; snapshot_containers.len()];
+    let mut idx = 0;
+    while idx < entries.len() {
+        let ReviewEntry {
+            ref key,
+            container,
+            snapshot,
+            ..
+        } = entries[idx];
+        let (ref mut snapshot_container, package) = snapshot_containers[container];
+        let snapshot_file = snapshot_container.snapshot_file().map(|x| x.to_path_buf());
+        let snapshot_ref = snapshot_container.snapshot_mut(snapshot);
 
-            // In non-interactive display mode, show the snapshot diff
-            if non_interactive_display {
-                println!(
-                    "{}{}:",
-                    style("Snapshot: ").bold(),
-                    style(&snapshot_ref.summary()).yellow()
-                );
-                println!("  Package: {}@{}", package.name.as_str(), &package.version);
-                println!();
+        // In non-interactive display mode, show the snapshot diff
+        if non_interactive_display {
+            println!(
+                "{}{}:",
+                style("Snapshot: ").bold(),
+                style(&snapshot_ref.summary()).yellow()
+            );
+            println!("  Package: {}@{}", package.name.as_str(), &package.version);
+            println!();
 
-                let mut printer = SnapshotPrinter::new(
-                    &loc.workspace_root,
-                    snapshot_ref.old.as_ref(),
-                    &snapshot_ref.new,
-                );
-                printer.set_snapshot_file(snapshot_file.as_deref());
-                printer.set_line(snapshot_ref.line);
-                printer.set_show_info(true);
-                printer.set_show_diff(true);
-                printer.print();
+            let mut printer = SnapshotPrinter::new(
+                &loc.workspace_root,
+                snapshot_ref.old.as_ref(),
+                &snapshot_ref.new,
+            );
+            printer.set_snapshot_file(snapshot_file.as_deref());
+            printer.set_line(snapshot_ref.line);
+            printer.set_show_info(true);
+            printer.set_show_diff(true);
+            printer.set_diff_style(diff_style);
+            printer.print();
 
-                println!();
+            println!();
 
-                // If we're in review mode (no op), just show instructions and skip
-                if op.is_none() {
-                    let key =
-                        format_snapshot_key(&loc.workspace_root, &target_file, snapshot_ref.line);
-                    println!("To accept: cargo insta accept --snapshot '{}'", key);
-                    println!("To reject: cargo insta reject --snapshot '{}'", key);
-                    println!();
+            // If we're in review mode (no op), just show instructions and skip
+            if op.is_none() {
+                println!("To accept: cargo insta accept --snapshot '{}'", key);
+                println!("To reject: cargo insta reject --snapshot '{}'", key);
+                println!();
 
-                    skipped.push(snapshot_ref.summary());
-                    continue;
-                }
-                // Otherwise fall through to apply the operation (reject)
-                // Note: Only reject mode reaches here because review mode returns early above
+                entries[idx].decision = Some((Operation::Skip, None));
+                idx += 1;
+                continue;
             }
+            // Otherwise fall through to apply the operation (reject)
+            // Note: Only reject mode reaches here because review mode returns early above
+        }
 
-            let op = match (op, apply_to_all) {
-                (Some(op), _) => op, // Use provided op if any (from CLI)
-                (_, Some(op)) => op, // Use apply_to_all if set from previous choice
-                _ => {
-                    // Otherwise prompt for user choice
-                    let choice = query_snapshot(
-                        &loc.workspace_root,
-                        &term,
-                        &snapshot_ref.new,
-                        snapshot_ref.old.as_ref(),
-                        package,
-                        snapshot_ref.line,
-                        num,
-                        snapshot_count,
-                        snapshot_file.as_deref(),
-                        &mut show_info,
-                        &mut show_diff,
-                    )?;
-
-                    // For "All" operations, set the apply_to_all flag and convert to single operation
-                    match choice {
-                        Operation::AcceptAll => {
-                            apply_to_all = Some(Operation::Accept);
-                            Operation::Accept
-                        }
-                        Operation::RejectAll => {
-                            apply_to_all = Some(Operation::Reject);
-                            Operation::Reject
-                        }
-                        Operation::SkipAll => {
-                            apply_to_all = Some(Operation::Skip);
-                            Operation::Skip
+        let recorded = session
+            .as_ref()
+            .and_then(|x| x.lookup(key, &snapshot_ref.new));
+        let decision = match (op, apply_to_all, recorded) {
+            (Some(op), _, _) => (op, None), // Use provided op if any (from CLI)
+            (_, Some(op), _) => (op, None), // Use apply_to_all if set from previous choice
+            (_, _, Some(recorded)) => recorded, // Use the decision of a resumed session
+            _ => {
+                // Otherwise prompt for user choice
+                let choice = query_snapshot(
+                    &loc.workspace_root,
+                    &term,
+                    &snapshot_ref.new,
+                    snapshot_ref.old.as_ref(),
+                    package,
+                    snapshot_ref.line,
+                    idx + 1,
+                    entries.len(),
+                    snapshot_file.as_deref(),
+                    &mut show_info,
+                    &mut show_diff,
+                    &mut diff_style,
+                    !history.is_empty(),
+                )?;
+
+                let (choice, accepted_hunks) = match choice {
+                    ReviewChoice::Decide(op) => (op, None),
+                    ReviewChoice::AcceptHunks(selection) => (Operation::Accept, Some(selection)),
+                    // Go back to the previous decision
+                    ReviewChoice::Undo => {
+                        if let Some(prev) = history.pop() {
+                            let entry = &mut entries[prev];
+                            entry.undo(
+                                snapshot_containers[entry.container]
+                                    .0
+                                    .snapshot_mut(entry.snapshot),
+                            );
+                            if let Some(ref mut session) = session {
+                                session.forget(&entries[prev].key)?;
+                            }
+                            idx = prev;
                         }
-                        op => op,
+                        continue;
                     }
-                }
-            };
+                    // Show the edited snapshot again so it can be accepted
+                    ReviewChoice::Edited(edited) => {
+                        entries[idx].edit(snapshot_ref, edited);
+                        continue;
+                    }
+                };
 
-            match op {
-                Operation::Accept | Operation::AcceptAll => {
-                    snapshot_ref.op = Operation::Accept;
-                    accepted.push(snapshot_ref.summary());
-                }
-                Operation::Reject | Operation::RejectAll => {
-                    snapshot_ref.op = Operation::Reject;
-                    rejected.push(snapshot_ref.summary());
-                }
-                Operation::Skip | Operation::SkipAll => {
-                    skipped.push(snapshot_ref.summary());
-                }
+                // For "All" operations, set the apply_to_all flag and convert to single operation
+                let choice = match choice {
+                    Operation::AcceptAll => {
+                        apply_to_all = Some(Operation::Accept);
+                        Operation::Accept
+                    }
+                    Operation::RejectAll => {
+                        apply_to_all = Some(Operation::Reject);
+                        Operation::Reject
+                    }
+                    Operation::SkipAll => {
+                        apply_to_all = Some(Operation::Skip);
+                        Operation::Skip
+                    }
+                    op => op,
+                };
+                (choice, accepted_hunks)
             }
+        };
+
+        if let Some(ref mut session) = session {
+            session.record(key, &snapshot_ref.new, decision.0, decision.1.as_deref())?;
         }
-        snapshot_container.commit()?;
+        entries[idx].decision = Some(decision);
+        history.push(idx);
+        if history.len() > UNDO_LIMIT {
+            history.remove(0);
+        }
+        idx += 1;
+
+        // everything before the oldest decision that can be undone is final
+        let end = history.first().copied().unwrap_or(idx);
+        commit_reviewed(
+            &mut snapshot_containers,
+            &mut entries,
+            end,
+            &mut committed,
+            &mut summary,
+        )?;
+    }
+
+    let end = entries.len();
+    commit_reviewed(
+        &mut snapshot_containers,
+        &mut entries,
+        end,
+        &mut committed,
+        &mut summary,
+    )?;
+    // containers without reviewed snapshots still need to be written, for
+    // instance to drop stale inline snapshots
+    for ((snapshot_container, _), committed) in snapshot_containers.iter_mut().zip(committed) {
+        if !committed {
+            snapshot_container.commit()?;
+        }
+    }
+    if let Some(session) = session {
+        session.finish();
     }
 
     if op.is_none() && apply_to_all.is_none() {
@@ -850,30 +1354,150 @@ fn review_snapshots(
     }
 
     if !quiet {
-        println!("{}", style("insta review finished").bold());
-        if !accepted.is_empty() {
-            println!("{}:", style("accepted").green());
-            for item in accepted {
-                println!("  {item}");
+        print_review_summary(&summary.accepted, &summary.rejected, &summary.skipped);
+    }
+
+    Ok(())
+}
+
+/// A review frontend that shows all pending snapshots at once.
+#[derive(Debug, Clone, Copy)]
+enum ReviewFrontend {
+    /// The full-screen terminal interface.
+    Tui,
+    /// The browser based interface served on the given port.
+    Web { port: u16 },
+}
+
+/// Collects the pending snapshots of all containers for the review frontends.
+fn collect_review_items<'a>(
+    snapshot_containers: &'a mut [(SnapshotContainer, &Package)],
+    snapshot_filter: Option<&[String]>,
+    loc: &LocationInfo<'_>,
+) -> Vec<ReviewItem<'a>> {
+    let mut items = vec![];
+    for (snapshot_container, package) in snapshot_containers.iter_mut() {
+        let target_file = snapshot_container.target_file().to_path_buf();
+        let snapshot_file = snapshot_container.snapshot_file().map(|x| x.to_path_buf());
+        for snapshot_ref in snapshot_container.iter_snapshots() {
+            if let Some(filter) = snapshot_filter {
+                if !filter
+                    .iter()
+                    .any(|f| snapshot_matches_filter(&target_file, snapshot_ref.line, f))
+                {
+                    continue;
+                }
             }
+            items.push(ReviewItem {
+                package: format!("{}@{}", package.name.as_str(), &package.version),
+                file: format_snapshot_key(&loc.workspace_root, &target_file, None),
+                snapshot_file: snapshot_file.clone(),
+                snapshot: snapshot_ref,
+                decision: None,
+            });
         }
-        if !rejected.is_empty() {
-            println!("{}:", style("rejected").red());
-            for item in rejected {
-                println!("  {item}");
+    }
+    items
+}
+
+/// Reviews snapshots in the full-screen terminal or the browser interface.
+///
+/// Unlike [`review_snapshots`] no decision is applied until the user finishes
+/// the review, at which point all containers are committed at once.
+fn review_snapshots_with(
+    quiet: bool,
+    snapshot_filter: Option<&[String]>,
+    loc: &LocationInfo<'_>,
+    frontend: ReviewFrontend,
+) -> Result<(), Box<dyn Error>> {
+    let term = Term::stdout();
+    if matches!(frontend, ReviewFrontend::Tui) && !term.is_term() {
+        return Err(err_msg("`cargo insta review --tui` requires a terminal"));
+    }
+
+    let (mut snapshot_containers, roots) = load_snapshot_containers(loc)?;
+    let mut items = collect_review_items(&mut snapshot_containers, snapshot_filter, loc);
+
+    if items.is_empty() {
+        if !quiet {
+            println!("{}: no snapshots to review", style("done").bold());
+            if loc.tool_config.review_warn_undiscovered() {
+                show_undiscovered_hint(
+                    loc.find_flags,
+                    &snapshot_containers
+                        .iter()
+                        .map(|x| x.0.clone())
+                        .collect_vec(),
+                    &roots,
+                    &loc.exts,
+                );
             }
         }
-        if !skipped.is_empty() {
-            println!("{}:", style("skipped").yellow());
-            for item in skipped {
-                println!("  {item}");
+        return Ok(());
+    }
+
+    match frontend {
+        ReviewFrontend::Tui => tui::run(
+            &loc.workspace_root,
+            &term,
+            loc.tool_config.diff_style(),
+            &mut items,
+        )?,
+        ReviewFrontend::Web { port } => web::run(&loc.workspace_root, port, &mut items)?,
+    }
+
+    let mut accepted = vec![];
+    let mut rejected = vec![];
+    let mut skipped = vec![];
+    for item in items {
+        match item.decision {
+            Some(Operation::Accept | Operation::AcceptAll) => {
+                item.snapshot.op = Operation::Accept;
+                accepted.push(item.snapshot.summary());
+            }
+            Some(Operation::Reject | Operation::RejectAll) => {
+                item.snapshot.op = Operation::Reject;
+                rejected.push(item.snapshot.summary());
+            }
+            Some(Operation::Skip | Operation::SkipAll) | None => {
+                skipped.push(item.snapshot.summary());
             }
         }
     }
 
+    for (snapshot_container, _) in snapshot_containers.iter_mut() {
+        snapshot_container.commit()?;
+    }
+
+    if !quiet {
+        print_review_summary(&accepted, &rejected, &skipped);
+    }
+
     Ok(())
 }
 
+fn print_review_summary(accepted: &[String], rejected: &[String], skipped: &[String]) {
+    println!("{}", style("insta review finished").bold());
+    if !accepted.is_empty() {
+        println!("{}:", style("accepted").green());
+        for item in accepted {
+            println!("  {item}");
+        }
+    }
+    if !rejected.is_empty() {
+        println!("{}:", style("rejected").red());
+        for item in rejected {
+            println!("  {item}");
+        }
+    }
+    if !skipped.is_empty() {
+        println!("{}:", style("skipped").yellow());
+        for item in skipped {
+            println!("  {item}");
+        }
+    }
+}
+
 /// Check if any of the packages have doctests
 fn has_doctests(packages: &[Package]) -> bool {
     for package in packages {
@@ -996,6 +1620,16 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
     let warnings_file = env::temp_dir().join(format!("insta-warnings-{}", Uuid::new_v4()));
     proc.env("INSTA_WARNINGS_FILE", &warnings_file);
 
+    // Set up the assertions file from which run reports are built.
+    let assertions_file = if cmd.report.is_empty() {
+        None
+    } else {
+        Some(env::temp_dir().join(format!("insta-assertions-{}", Uuid::new_v4())))
+    };
+    if let Some(ref assertions_file) = assertions_file {
+        proc.env("INSTA_ASSERTIONS_FILE", assertions_file);
+    }
+
     if let Some(workspace_root) = &cmd.target_args.workspace_root {
         proc.current_dir(workspace_root);
     }
@@ -1033,8 +1667,11 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
             snapshot_ref_file.as_deref(),
             &loc,
         )?;
-        // Use the same warnings file for doctests
+        // Use the same warnings and assertions files for doctests
         proc.env("INSTA_WARNINGS_FILE", &warnings_file);
+        if let Some(ref assertions_file) = assertions_file {
+            proc.env("INSTA_ASSERTIONS_FILE", assertions_file);
+        }
         success = success && proc.status()?.success();
     }
 
@@ -1051,6 +1688,22 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
         fs::remove_file(&warnings_file).ok();
     }
 
+    // Write the requested run reports, also if tests failed
+    if let Some(assertions_file) = assertions_file {
+        let records = report::load_records(&assertions_file, &loc.workspace_root);
+        fs::remove_file(&assertions_file).ok();
+        for target in &cmd.report {
+            report::write_report(target, &records)?;
+            eprintln!(
+                "{}: wrote report of {} snapshot assertion{} to {}",
+                style("info").bold(),
+                records.len(),
+                if records.len() != 1 { "s" } else { "" },
+                target.path.display()
+            );
+        }
+    }
+
     if !success && cmd.review {
         eprintln!(
             "{} non snapshot tests failed, skipping review",
@@ -1082,6 +1735,7 @@ fn test_run(mut cmd: TestCommand, color: ColorWhen) -> Result<(), Box<dyn Error>
             } else {
                 None
             },
+            false,
         )?
     } else {
         let (snapshot_containers, roots) = load_snapshot_containers(&loc)?;
@@ -1493,6 +2147,21 @@ fn show_cmd(cmd: ShowCommand) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn report_cmd(cmd: ReportCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args, &[])?;
+    let (mut snapshot_containers, _) = load_snapshot_containers(&loc)?;
+    let items = collect_review_items(&mut snapshot_containers, None, &loc);
+    web::write_report(&cmd.html, &loc.workspace_root, &items)?;
+    println!(
+        "{}: wrote report of {} pending snapshot{} to {}",
+        style("done").bold(),
+        items.len(),
+        if items.len() != 1 { "s" } else { "" },
+        cmd.html.join("index.html").display()
+    );
+    Ok(())
+}
+
 fn pending_snapshots_cmd(cmd: PendingSnapshotsCommand) -> Result<(), Box<dyn Error>> {
     #[derive(Serialize, Debug)]
     #[serde(rename_all = "snake_case", tag = "type")]
@@ -1667,11 +2336,27 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
             if let Some(ref diff_tool) = cmd.diff_tool {
                 env::set_var("INSTA_DIFF_TOOL", diff_tool);
             }
+            let frontend = if cmd.web {
+                Some(ReviewFrontend::Web { port: cmd.port })
+            } else if cmd.tui {
+                Some(ReviewFrontend::Tui)
+            } else {
+                None
+            };
+            if let Some(frontend) = frontend {
+                return review_snapshots_with(
+                    cmd.process.quiet,
+                    cmd.process.snapshot_filter.as_deref(),
+                    &handle_target_args(&cmd.process.target_args, &[])?,
+                    frontend,
+                );
+            }
             review_snapshots(
                 cmd.process.quiet,
                 cmd.process.snapshot_filter.as_deref(),
                 &handle_target_args(&cmd.process.target_args, &[])?,
                 None,
+                cmd.resume,
             )
         }
         Command::Accept(ref cmd) | Command::Reject(ref cmd) => review_snapshots(
@@ -1683,9 +2368,11 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
                 Command::Reject(_) => Some(Operation::Reject),
                 _ => unreachable!(),
             },
+            false,
         ),
         Command::Test(cmd) => test_run(cmd, opts.color.unwrap_or(ColorWhen::Auto)),
         Command::Show(cmd) => show_cmd(cmd),
+        Command::Report(cmd) => report_cmd(cmd),
         Command::PendingSnapshots(cmd) => pending_snapshots_cmd(cmd),
     }
 }
@@ -1694,6 +2381,8 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
 mod tests {
     use super::*;
 
+    use crate::container::TextSnapshotKind;
+
     #[test]
     fn get_cargo_nextest_command_from_env_variables() {
         env::set_var("INSTA_CARGO_NEXTEST_BIN", "/a/custom/path/to/cargo-nextest");
@@ -1788,6 +2477,53 @@ mod tests {
         assert!(!snapshot_matches_filter(target, Some(17), "lib.rs:foo"));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_snapshot() {
+        let contents = TextSnapshotContents::new("old value".into(), TextSnapshotKind::File);
+        let edited = edit_snapshot_with(
+            "sed -i s/old/edited/",
+            &contents,
+            Some(Path::new("foo__bar.snap")),
+        )
+        .unwrap();
+        assert_eq!(edited.unwrap().to_string(), "edited value");
+
+        // an editor that leaves the file alone doesn't change the snapshot
+        assert!(edit_snapshot_with("true", &contents, None)
+            .unwrap()
+            .is_none());
+
+        assert!(edit_snapshot_with("false", &contents, None).is_err());
+    }
+
+    #[test]
+    fn test_undo_restores_edit() {
+        let mut snapshot = PendingSnapshot::for_test("bar", Some("old"), "new");
+        let mut entry = ReviewEntry {
+            key: "foo__bar.snap".into(),
+            container: 0,
+            snapshot: 0,
+            decision: None,
+            original: None,
+        };
+
+        entry.edit(
+            &mut snapshot,
+            TextSnapshotContents::new("edited".into(), TextSnapshotKind::File),
+        );
+        entry.edit(
+            &mut snapshot,
+            TextSnapshotContents::new("edited again".into(), TextSnapshotKind::File),
+        );
+        entry.decision = Some((Operation::Accept, None));
+        assert_eq!(snapshot.new.as_text().unwrap().to_string(), "edited again");
+
+        entry.undo(&mut snapshot);
+        assert!(entry.decision.is_none());
+        assert_eq!(snapshot.new.as_text().unwrap().to_string(), "new");
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_snapshot_matches_filter_windows_separators() {
@@ -2,12 +2,16 @@ use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use insta::_cargo_insta_support::{ContentError, PendingInlineSnapshot};
+use insta::_cargo_insta_support::{diff_image_path, ContentError, PendingInlineSnapshot};
 pub(crate) use insta::TextSnapshotKind;
-use insta::{internals::SnapshotContents, Snapshot};
+use insta::{internals::SnapshotContents, internals::TextSnapshotContents, Snapshot};
+use similar::{Algorithm, DiffOp, DiffTag, TextDiff};
 
 use crate::inline::FilePatcher;
 
+/// Number of context lines shown around (and joining) diff hunks.
+const HUNK_CONTEXT: usize = 4;
+
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum Operation {
     Accept,
@@ -43,6 +47,126 @@ impl PendingSnapshot {
         }
         rv
     }
+
+    /// Returns the old and new text if the snapshot can be reviewed hunk by hunk.
+    ///
+    /// This is only possible if both the old and the new snapshot are text
+    /// snapshots.
+    pub(crate) fn hunk_texts(&self) -> Option<(String, String)> {
+        match (self.old.as_ref()?.contents(), self.new.contents()) {
+            (SnapshotContents::Text(old), SnapshotContents::Text(new)) => {
+                Some((old.to_string(), new.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Accepts only the hunks marked in `accepted`.
+    ///
+    /// The new snapshot is replaced with a merge of both snapshots which keeps
+    /// the old lines of every rejected hunk.  Accepting all or no hunks is the
+    /// same as accepting or rejecting the whole snapshot.
+    pub(crate) fn accept_hunks(&mut self, accepted: &[bool]) {
+        if accepted.iter().all(|&x| x) {
+            self.op = Operation::Accept;
+        } else if !accepted.iter().any(|&x| x) {
+            self.op = Operation::Reject;
+        } else if let Some((old, new)) = self.hunk_texts() {
+            let kind = match self.new.contents() {
+                SnapshotContents::Text(contents) => contents.kind,
+                SnapshotContents::Binary(_) => unreachable!(),
+            };
+            let merged = merge_hunks(&old, &new, accepted);
+            self.new = self
+                .new
+                .with_contents(TextSnapshotContents::new(merged, kind).into());
+            self.op = Operation::Accept;
+        }
+    }
+}
+
+#[cfg(test)]
+impl PendingSnapshot {
+    /// Creates a pending file snapshot named `name` for tests.
+    pub(crate) fn for_test(name: &str, old: Option<&str>, new: &str) -> PendingSnapshot {
+        let dir = tempfile::tempdir().unwrap();
+        let load = |contents: &str| {
+            let path = dir.path().join(format!("test__{name}.snap"));
+            fs::write(
+                &path,
+                format!("---\nsource: tests/test.rs\n---\n{contents}\n"),
+            )
+            .unwrap();
+            Snapshot::from_file(&path).unwrap()
+        };
+        PendingSnapshot {
+            id: 0,
+            old: old.map(load),
+            new: load(new),
+            op: Operation::Skip,
+            line: None,
+        }
+    }
+}
+
+/// A pending snapshot as presented by the full-screen review frontends.
+pub(crate) struct ReviewItem<'a> {
+    /// `name@version` of the package the snapshot belongs to.
+    pub(crate) package: String,
+    /// Workspace relative path of the file holding the snapshot.
+    pub(crate) file: String,
+    pub(crate) snapshot_file: Option<PathBuf>,
+    pub(crate) snapshot: &'a mut PendingSnapshot,
+    /// The decision made in the interface, `None` if undecided.
+    pub(crate) decision: Option<Operation>,
+}
+
+impl ReviewItem<'_> {
+    /// Returns a short name for the snapshot within its file.
+    pub(crate) fn label(&self) -> String {
+        match (self.snapshot.new.snapshot_name(), self.snapshot.line) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(line)) => format!("line {line}"),
+            (None, None) => "<snapshot>".to_string(),
+        }
+    }
+}
+
+/// Computes the line diff between two snapshot texts and groups it into hunks.
+///
+/// No deadline is used for the diff so that the hunks are identical when they
+/// are shown to the user and when they are merged afterwards.
+pub(crate) fn diff_hunks<'a>(
+    old: &'a str,
+    new: &'a str,
+) -> (TextDiff<'a, 'a, 'a, str>, Vec<Vec<DiffOp>>) {
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .diff_lines(old, new);
+    let hunks = diff.grouped_ops(HUNK_CONTEXT);
+    (diff, hunks)
+}
+
+/// Merges two snapshot texts, taking the new lines only for accepted hunks.
+pub(crate) fn merge_hunks(old: &str, new: &str, accepted: &[bool]) -> String {
+    let (diff, hunks) = diff_hunks(old, new);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let mut rv = String::new();
+    for op in diff.ops() {
+        let take_new = op.tag() != DiffTag::Equal
+            && hunks
+                .iter()
+                .position(|hunk| hunk.contains(op))
+                .and_then(|idx| accepted.get(idx).copied())
+                .unwrap_or(false);
+        if take_new {
+            rv.extend(new_lines[op.new_range()].iter().copied());
+        } else {
+            rv.extend(old_lines[op.old_range()].iter().copied());
+        }
+    }
+    rv
 }
 
 /// A snapshot and its immediate context, which loads & saves the snapshot. It
@@ -162,6 +286,10 @@ impl SnapshotContainer {
         self.snapshots.len()
     }
 
+    pub(crate) fn snapshot_mut(&mut self, idx: usize) -> &mut PendingSnapshot {
+        &mut self.snapshots[idx]
+    }
+
     pub(crate) fn iter_snapshots(&mut self) -> impl Iterator<Item = &'_ mut PendingSnapshot> {
         self.snapshots.iter_mut()
     }
@@ -232,6 +360,8 @@ impl SnapshotContainer {
                         if let Some(path) = snapshot.new.build_binary_path(&self.pending_path) {
                             try_removing_snapshot(&path);
                         }
+                        // a comparator might have stored a diff image for review
+                        fs::remove_file(diff_image_path(&self.pending_path)).ok();
 
                         // We save at the end because we might write a binary file into the same
                         // path again.
@@ -243,6 +373,7 @@ impl SnapshotContainer {
                         if let Some(path) = snapshot.new.build_binary_path(&self.pending_path) {
                             try_removing_snapshot(&path);
                         }
+                        fs::remove_file(diff_image_path(&self.pending_path)).ok();
                     }
                     Operation::Skip | Operation::SkipAll => {}
                 }
@@ -251,3 +382,44 @@ impl SnapshotContainer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_hunks() {
+        let old = (1..=20).map(|x| format!("line {x}\n")).collect::<String>();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "line eighteen\n");
+
+        assert_eq!(diff_hunks(&old, &new).1.len(), 2);
+        assert_eq!(merge_hunks(&old, &new, &[true, true]), new);
+        assert_eq!(merge_hunks(&old, &new, &[false, false]), old);
+        assert_eq!(
+            merge_hunks(&old, &new, &[true, false]),
+            old.replace("line 2\n", "line two\n")
+        );
+        assert_eq!(
+            merge_hunks(&old, &new, &[false, true]),
+            old.replace("line 18\n", "line eighteen\n")
+        );
+    }
+
+    #[test]
+    fn test_merge_hunks_insert_and_delete() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl";
+        let new = "x\na\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk";
+
+        assert_eq!(diff_hunks(old, new).1.len(), 2);
+        assert_eq!(
+            merge_hunks(old, new, &[true, false]),
+            "x\na\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl"
+        );
+        assert_eq!(
+            merge_hunks(old, new, &[false, true]),
+            "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk"
+        );
+    }
+}
@@ -19,8 +19,12 @@ mod cargo;
 mod cli;
 mod container;
 mod inline;
+mod report;
+mod session;
+mod tui;
 mod utils;
 mod walk;
+mod web;
 
 use console::style;
 
@@ -0,0 +1,324 @@
+//! Run reports for `cargo insta test --report`.
+//!
+//! While the tests run, insta appends a record of every snapshot assertion to
+//! the file named by `INSTA_ASSERTIONS_FILE`.  Once the run finished these
+//! records are turned into `JUnit` XML or JSON reports.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The format of a run report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReportFormat {
+    Junit,
+    Json,
+}
+
+/// A report requested with `--report FORMAT=PATH`.
+#[derive(Debug, Clone)]
+pub(crate) struct ReportTarget {
+    pub(crate) format: ReportFormat,
+    pub(crate) path: PathBuf,
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<ReportTarget, String> {
+        let (format, path) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected FORMAT=PATH, got '{value}'"))?;
+        let format = match format {
+            "junit" => ReportFormat::Junit,
+            "json" => ReportFormat::Json,
+            _ => return Err(format!("unknown report format '{format}' (junit, json)")),
+        };
+        if path.is_empty() {
+            return Err("missing report path".into());
+        }
+        Ok(ReportTarget {
+            format,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// The outcome of a single snapshot assertion.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Outcome {
+    /// The snapshot matched.
+    Pass,
+    /// The snapshot did not match.
+    Fail,
+    /// There was no snapshot yet.
+    New,
+    /// The snapshot was written in place.
+    Updated,
+}
+
+impl Outcome {
+    fn is_failure(self) -> bool {
+        matches!(self, Outcome::Fail | Outcome::New)
+    }
+}
+
+/// The record of a snapshot assertion as written by insta.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct AssertionRecord {
+    function: String,
+    module: String,
+    name: String,
+    file: String,
+    line: u32,
+    outcome: Outcome,
+    duration: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct Summary {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    new: usize,
+    updated: usize,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    summary: Summary,
+    assertions: &'a [AssertionRecord],
+}
+
+/// Loads the assertion records written during a test run.
+///
+/// Snapshot paths are made relative to the workspace root.  Lines that cannot
+/// be parsed (for instance because a test process was killed while writing)
+/// are skipped.
+pub(crate) fn load_records(path: &Path, workspace_root: &Path) -> Vec<AssertionRecord> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return vec![],
+    };
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<AssertionRecord>(line).ok())
+        .map(|mut record| {
+            if let Some(snapshot) = record.snapshot.take() {
+                record.snapshot = Some(
+                    snapshot
+                        .strip_prefix(workspace_root)
+                        .map(|x| x.to_path_buf())
+                        .unwrap_or(snapshot),
+                );
+            }
+            record
+        })
+        .collect()
+}
+
+/// Writes a report of the recorded assertions.
+pub(crate) fn write_report(
+    target: &ReportTarget,
+    records: &[AssertionRecord],
+) -> Result<(), Box<dyn Error>> {
+    let contents = match target.format {
+        ReportFormat::Junit => render_junit(records),
+        ReportFormat::Json => {
+            let mut rv = serde_json::to_string_pretty(&JsonReport {
+                summary: summarize(records.iter()),
+                assertions: records,
+            })?;
+            rv.push('\n');
+            rv
+        }
+    };
+    if let Some(parent) = target.path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(&target.path, contents)?;
+    Ok(())
+}
+
+fn summarize<'a>(records: impl Iterator<Item = &'a AssertionRecord>) -> Summary {
+    let mut summary = Summary::default();
+    for record in records {
+        summary.total += 1;
+        match record.outcome {
+            Outcome::Pass => summary.passed += 1,
+            Outcome::Fail => summary.failed += 1,
+            Outcome::New => summary.new += 1,
+            Outcome::Updated => summary.updated += 1,
+        }
+    }
+    summary
+}
+
+/// Renders the records as `JUnit` XML with one test suite per module.
+fn render_junit(records: &[AssertionRecord]) -> String {
+    let mut suites = BTreeMap::<&str, Vec<&AssertionRecord>>::new();
+    for record in records {
+        suites.entry(&record.module).or_default().push(record);
+    }
+
+    let failures =
+        |records: &[&AssertionRecord]| records.iter().filter(|x| x.outcome.is_failure()).count();
+    let time = |records: &[&AssertionRecord]| records.iter().map(|x| x.duration).sum::<f64>();
+
+    let all = records.iter().collect::<Vec<_>>();
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        out,
+        r#"<testsuites name="insta" tests="{}" failures="{}" time="{:.6}">"#,
+        all.len(),
+        failures(&all),
+        time(&all)
+    )
+    .unwrap();
+    for (module, records) in &suites {
+        writeln!(
+            out,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.6}">"#,
+            escape_xml(module),
+            records.len(),
+            failures(records),
+            time(records)
+        )
+        .unwrap();
+        for record in records {
+            write_testcase(&mut out, record);
+        }
+        writeln!(out, "  </testsuite>").unwrap();
+    }
+    writeln!(out, "</testsuites>").unwrap();
+    out
+}
+
+fn write_testcase(out: &mut String, record: &AssertionRecord) {
+    writeln!(
+        out,
+        r#"    <testcase name="{}" classname="{}" file="{}" line="{}" time="{:.6}">"#,
+        escape_xml(&record.name),
+        escape_xml(&record.function),
+        escape_xml(&record.file),
+        record.line,
+        record.duration
+    )
+    .unwrap();
+    writeln!(out, "      <properties>").unwrap();
+    writeln!(
+        out,
+        r#"        <property name="insta.outcome" value="{}"/>"#,
+        match record.outcome {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "fail",
+            Outcome::New => "new",
+            Outcome::Updated => "updated",
+        }
+    )
+    .unwrap();
+    if let Some(ref snapshot) = record.snapshot {
+        writeln!(
+            out,
+            r#"        <property name="insta.snapshot" value="{}"/>"#,
+            escape_xml(&snapshot.to_string_lossy())
+        )
+        .unwrap();
+    }
+    writeln!(out, "      </properties>").unwrap();
+    let failure = match record.outcome {
+        Outcome::Fail => Some((
+            "snapshot-mismatch",
+            format!("snapshot assertion for '{}' failed", record.name),
+        )),
+        Outcome::New => Some(("new-snapshot", format!("new snapshot '{}'", record.name))),
+        Outcome::Pass | Outcome::Updated => None,
+    };
+    if let Some((kind, message)) = failure {
+        writeln!(
+            out,
+            r#"      <failure type="{}" message="{}">{}</failure>"#,
+            kind,
+            escape_xml(&message),
+            escape_xml(record.diff.as_deref().unwrap_or(""))
+        )
+        .unwrap();
+    }
+    writeln!(out, "    </testcase>").unwrap();
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => rv.push_str("&amp;"),
+            '<' => rv.push_str("&lt;"),
+            '>' => rv.push_str("&gt;"),
+            '"' => rv.push_str("&quot;"),
+            '\'' => rv.push_str("&apos;"),
+            // control characters other than whitespace are not allowed in XML
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(name: &str, outcome: Outcome) -> AssertionRecord {
+        AssertionRecord {
+            function: format!("demo::tests::{name}"),
+            module: "demo::tests".into(),
+            name: name.into(),
+            file: "src/lib.rs".into(),
+            line: 7,
+            outcome,
+            duration: 0.5,
+            snapshot: Some(PathBuf::from(format!("src/snapshots/{name}.snap"))),
+            diff: match outcome {
+                Outcome::Fail => Some("-a\n+<b>".into()),
+                _ => None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_report_target() {
+        let target = "junit=out/report.xml".parse::<ReportTarget>().unwrap();
+        assert_eq!(target.format, ReportFormat::Junit);
+        assert_eq!(target.path, PathBuf::from("out/report.xml"));
+        assert!("xml=report.xml".parse::<ReportTarget>().is_err());
+        assert!("report.xml".parse::<ReportTarget>().is_err());
+        assert!("json=".parse::<ReportTarget>().is_err());
+    }
+
+    #[test]
+    fn test_render_junit() {
+        let xml = render_junit(&[record("a", Outcome::Pass), record("b", Outcome::Fail)]);
+        assert!(xml.contains(r#"<testsuites name="insta" tests="2" failures="1" time="1.000000">"#));
+        assert!(xml.contains(
+            r#"<testcase name="b" classname="demo::tests::b" file="src/lib.rs" line="7""#
+        ));
+        assert!(xml.contains(r#"<property name="insta.snapshot" value="src/snapshots/b.snap"/>"#));
+        assert!(xml.contains(
+            r#"<failure type="snapshot-mismatch" message="snapshot assertion for &apos;b&apos; failed">-a
++&lt;b&gt;</failure>"#
+        ));
+        assert_eq!(xml.matches("<failure").count(), 1);
+    }
+}
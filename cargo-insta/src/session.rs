@@ -0,0 +1,218 @@
+//! Persisted review sessions.
+//!
+//! Every decision made during an interactive `cargo insta review` is recorded
+//! in a session file in the workspace's target directory.  If the review is
+//! interrupted, `cargo insta review --resume` replays the recorded decisions
+//! for snapshots that are still pending and unchanged.  A plain review asks
+//! whether to resume before it replaces an existing session.
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use insta::{internals::SnapshotContents, Snapshot};
+use serde::{Deserialize, Serialize};
+
+use crate::container::Operation;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Decision {
+    Accept,
+    Reject,
+    Skip,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RecordedDecision {
+    snapshot: String,
+    fingerprint: String,
+    decision: Decision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hunks: Option<Vec<bool>>,
+}
+
+/// The decisions of an interactive review.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub(crate) struct ReviewSession {
+    #[serde(skip)]
+    path: PathBuf,
+    decisions: Vec<RecordedDecision>,
+}
+
+impl ReviewSession {
+    /// Returns the path of the session file for a target directory.
+    pub(crate) fn path_for(target_dir: &Path) -> PathBuf {
+        target_dir.join("insta").join("review-session.json")
+    }
+
+    /// Starts a new session which is stored at `path`.
+    ///
+    /// An existing session at `path` is replaced with the first decision.
+    pub(crate) fn new(path: PathBuf) -> ReviewSession {
+        ReviewSession {
+            path,
+            decisions: vec![],
+        }
+    }
+
+    /// Loads a previously interrupted session, or starts a new one if there
+    /// is none.
+    pub(crate) fn resume(path: PathBuf) -> Result<ReviewSession, Box<dyn Error>> {
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                let mut session: ReviewSession = serde_json::from_str(&contents)
+                    .map_err(|err| format!("failed to load review session {path:?}: {err}"))?;
+                session.path = path;
+                Ok(session)
+            }
+            Err(_) => Ok(ReviewSession::new(path)),
+        }
+    }
+
+    /// Looks up the recorded decision for a snapshot.
+    ///
+    /// Decisions are only returned if the new snapshot is unchanged since the
+    /// decision was made.
+    pub(crate) fn lookup(
+        &self,
+        key: &str,
+        new: &Snapshot,
+    ) -> Option<(Operation, Option<Vec<bool>>)> {
+        let fingerprint = fingerprint(new);
+        self.decisions
+            .iter()
+            .find(|x| x.snapshot == key && x.fingerprint == fingerprint)
+            .map(|x| {
+                let op = match x.decision {
+                    Decision::Accept => Operation::Accept,
+                    Decision::Reject => Operation::Reject,
+                    Decision::Skip => Operation::Skip,
+                };
+                (op, x.hunks.clone())
+            })
+    }
+
+    /// Records a decision and persists the session.
+    pub(crate) fn record(
+        &mut self,
+        key: &str,
+        new: &Snapshot,
+        op: Operation,
+        hunks: Option<&[bool]>,
+    ) -> Result<(), Box<dyn Error>> {
+        let decision = match op {
+            Operation::Accept | Operation::AcceptAll => Decision::Accept,
+            Operation::Reject | Operation::RejectAll => Decision::Reject,
+            Operation::Skip | Operation::SkipAll => Decision::Skip,
+        };
+        self.decisions.retain(|x| x.snapshot != key);
+        self.decisions.push(RecordedDecision {
+            snapshot: key.to_string(),
+            fingerprint: fingerprint(new),
+            decision,
+            hunks: hunks.map(|x| x.to_vec()),
+        });
+        self.save()
+    }
+
+    /// Forgets the decision for a snapshot and persists the session.
+    pub(crate) fn forget(&mut self, key: &str) -> Result<(), Box<dyn Error>> {
+        self.decisions.retain(|x| x.snapshot != key);
+        self.save()
+    }
+
+    /// Removes the session file once the review finished.
+    pub(crate) fn finish(self) {
+        fs::remove_file(&self.path).ok();
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Identifies the contents of a new snapshot, so that stale decisions are not
+/// applied to snapshots that changed since.
+fn fingerprint(snapshot: &Snapshot) -> String {
+    let mut hasher = DefaultHasher::new();
+    match snapshot.contents() {
+        SnapshotContents::Text(contents) => contents.to_string().hash(&mut hasher),
+        SnapshotContents::Binary(contents) => contents.as_deref().hash(&mut hasher),
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::container::PendingSnapshot;
+
+    fn snapshot(contents: &str) -> Snapshot {
+        PendingSnapshot::for_test("session", None, contents).new
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = ReviewSession::path_for(dir.path());
+
+        // resuming without a session file starts a new session
+        let mut session = ReviewSession::resume(path.clone()).unwrap();
+        assert!(session.decisions.is_empty());
+
+        let (a, b) = (snapshot("a"), snapshot("b"));
+        session
+            .record("a.snap", &a, Operation::AcceptAll, None)
+            .unwrap();
+        session
+            .record("b.rs:3", &b, Operation::Accept, Some(&[true, false]))
+            .unwrap();
+        session.record("c.snap", &b, Operation::Skip, None).unwrap();
+        session.forget("c.snap").unwrap();
+
+        let session = ReviewSession::resume(path.clone()).unwrap();
+        assert!(matches!(
+            session.lookup("a.snap", &a),
+            Some((Operation::Accept, None))
+        ));
+        assert!(matches!(
+            session.lookup("b.rs:3", &b),
+            Some((Operation::Accept, Some(ref hunks))) if hunks == &[true, false]
+        ));
+        assert!(session.lookup("c.snap", &b).is_none());
+
+        session.finish();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_session_stale_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = ReviewSession::path_for(dir.path());
+
+        let mut session = ReviewSession::new(path.clone());
+        session
+            .record("a.snap", &snapshot("old"), Operation::Reject, None)
+            .unwrap();
+        // deciding again replaces the previous decision
+        session
+            .record("a.snap", &snapshot("new"), Operation::Skip, None)
+            .unwrap();
+
+        let session = ReviewSession::resume(path).unwrap();
+        assert_eq!(session.decisions.len(), 1);
+        // a changed snapshot does not get the decision of its old contents
+        assert!(session.lookup("a.snap", &snapshot("old")).is_none());
+        assert!(matches!(
+            session.lookup("a.snap", &snapshot("new")),
+            Some((Operation::Skip, None))
+        ));
+    }
+}
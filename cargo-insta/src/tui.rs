@@ -0,0 +1,581 @@
+//! Full-screen terminal interface for `cargo insta review --tui`.
+//!
+//! The interface shows every pending snapshot in a sidebar (grouped by package
+//! and file) next to a scrollable diff pane.  Decisions are only recorded while
+//! the interface is open; they are applied by the caller once the user quits,
+//! which means decisions can be changed freely until then.
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+use console::{measure_text_width, pad_str, style, truncate_str, Alignment, Key, Term};
+use insta::_cargo_insta_support::{DiffStyle, SnapshotPrinter};
+
+use crate::container::{Operation, ReviewItem};
+use crate::utils::{next_diff_style, QuietExit};
+
+/// Returns whether a snapshot matches a (case insensitive) search query.
+fn matches_query(item: &ReviewItem<'_>, query: &str) -> bool {
+    let query = query.to_lowercase();
+    item.label().to_lowercase().contains(&query)
+        || item.file.to_lowercase().contains(&query)
+        || item.package.to_lowercase().contains(&query)
+}
+
+/// A row in the sidebar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Row {
+    Package(String),
+    File(String),
+    Snapshot(usize),
+}
+
+fn build_rows(items: &[ReviewItem<'_>]) -> Vec<Row> {
+    let mut rows = vec![];
+    let mut last_package = None;
+    let mut last_file = None;
+    for (idx, item) in items.iter().enumerate() {
+        if last_package != Some(&item.package) {
+            rows.push(Row::Package(item.package.clone()));
+            last_package = Some(&item.package);
+            last_file = None;
+        }
+        if last_file != Some(&item.file) {
+            rows.push(Row::File(item.file.clone()));
+            last_file = Some(&item.file);
+        }
+        rows.push(Row::Snapshot(idx));
+    }
+    rows
+}
+
+enum Mode {
+    Normal,
+    Search(String),
+}
+
+/// What the interface does after a key press.
+#[derive(Debug, PartialEq, Eq)]
+enum Flow {
+    Continue,
+    /// The review is finished and the decisions are applied.
+    Finish,
+    /// The review is aborted without applying anything.
+    Abort,
+}
+
+/// The state of the review interface, independent of the terminal.
+struct ReviewState<'a, 'b> {
+    items: &'b mut [ReviewItem<'a>],
+    rows: Vec<Row>,
+    selected: usize,
+    sidebar_offset: usize,
+    diff_offset: usize,
+    show_info: bool,
+    show_diff: bool,
+    diff_style: DiffStyle,
+    query: Option<String>,
+    mode: Mode,
+    message: Option<String>,
+}
+
+impl<'a, 'b> ReviewState<'a, 'b> {
+    fn new(items: &'b mut [ReviewItem<'a>], diff_style: DiffStyle) -> ReviewState<'a, 'b> {
+        let rows = build_rows(items);
+        ReviewState {
+            items,
+            rows,
+            selected: 0,
+            sidebar_offset: 0,
+            diff_offset: 0,
+            show_info: true,
+            show_diff: true,
+            diff_style,
+            query: None,
+            mode: Mode::Normal,
+            message: None,
+        }
+    }
+
+    fn select(&mut self, idx: usize) {
+        if idx < self.items.len() && idx != self.selected {
+            self.selected = idx;
+            self.diff_offset = 0;
+        }
+    }
+
+    fn select_next(&mut self) {
+        self.select(self.selected + 1);
+    }
+
+    fn select_prev(&mut self) {
+        if let Some(idx) = self.selected.checked_sub(1) {
+            self.select(idx);
+        }
+    }
+
+    /// Moves to the next undecided snapshot after the current one, wrapping
+    /// around.  Stays put if everything has been decided.
+    fn select_next_undecided(&mut self) {
+        let n = self.items.len();
+        if let Some(idx) = (1..n)
+            .map(|offset| (self.selected + offset) % n)
+            .find(|&idx| self.items[idx].decision.is_none())
+        {
+            self.select(idx);
+        }
+    }
+
+    fn decide(&mut self, op: Operation) {
+        self.items[self.selected].decision = Some(op);
+        self.select_next_undecided();
+    }
+
+    fn decide_remaining(&mut self, op: Operation) {
+        for item in self.items.iter_mut() {
+            if item.decision.is_none() {
+                item.decision = Some(op);
+            }
+        }
+    }
+
+    /// Selects the next (or previous) snapshot matching the current search.
+    fn find(&mut self, forwards: bool, include_current: bool) {
+        let query = match self.query {
+            Some(ref query) => query,
+            None => return,
+        };
+        let n = self.items.len();
+        let start = if include_current { 0 } else { 1 };
+        let found = (start..n + start)
+            .map(|offset| {
+                if forwards {
+                    (self.selected + offset) % n
+                } else {
+                    (self.selected + n - offset % n) % n
+                }
+            })
+            .find(|&idx| matches_query(&self.items[idx], query));
+        match found {
+            Some(idx) => {
+                self.select(idx);
+                self.message = None;
+            }
+            None => self.message = Some(format!("no snapshot matches `{query}`")),
+        }
+    }
+
+    /// Handles a key press.  `body_height` is the number of diff lines on
+    /// screen which determines how far the page keys scroll.
+    fn handle_key(&mut self, key: Key, body_height: usize) -> Flow {
+        if let Mode::Search(ref mut query) = self.mode {
+            match key {
+                Key::Enter => {
+                    let query = std::mem::take(query);
+                    self.mode = Mode::Normal;
+                    self.query = if query.is_empty() { None } else { Some(query) };
+                    self.find(true, true);
+                }
+                Key::Escape => self.mode = Mode::Normal,
+                Key::Backspace => {
+                    query.pop();
+                }
+                Key::Char(c) if !c.is_control() => query.push(c),
+                Key::CtrlC => return Flow::Abort,
+                _ => {}
+            }
+            return Flow::Continue;
+        }
+
+        self.message = None;
+        match key {
+            Key::ArrowDown | Key::Char('j') => self.select_next(),
+            Key::ArrowUp | Key::Char('k') => self.select_prev(),
+            Key::Home => self.select(0),
+            Key::End => self.select(self.items.len().saturating_sub(1)),
+            Key::PageDown | Key::Char(' ') => self.diff_offset += body_height / 2,
+            Key::PageUp => self.diff_offset = self.diff_offset.saturating_sub(body_height / 2),
+            Key::ArrowRight | Key::Char('J') => self.diff_offset += 1,
+            Key::ArrowLeft | Key::Char('K') => {
+                self.diff_offset = self.diff_offset.saturating_sub(1)
+            }
+            Key::Char('a') | Key::Enter => self.decide(Operation::Accept),
+            Key::Char('r') => self.decide(Operation::Reject),
+            Key::Char('s') => self.decide(Operation::Skip),
+            Key::Char('A') => self.decide_remaining(Operation::Accept),
+            Key::Char('R') => self.decide_remaining(Operation::Reject),
+            Key::Char('S') => self.decide_remaining(Operation::Skip),
+            Key::Char('i') => self.show_info = !self.show_info,
+            Key::Char('d') => self.show_diff = !self.show_diff,
+            Key::Char('v') => self.diff_style = next_diff_style(self.diff_style),
+            Key::Char('/') => self.mode = Mode::Search(String::new()),
+            Key::Char('n') => self.find(true, false),
+            Key::Char('N') => self.find(false, false),
+            Key::Char('q') => return Flow::Finish,
+            Key::CtrlC => return Flow::Abort,
+            _ => {}
+        }
+        Flow::Continue
+    }
+
+    fn counts(&self) -> (usize, usize, usize) {
+        let mut rv = (0, 0, 0);
+        for item in self.items.iter() {
+            match item.decision {
+                Some(Operation::Accept | Operation::AcceptAll) => rv.0 += 1,
+                Some(Operation::Reject | Operation::RejectAll) => rv.1 += 1,
+                Some(Operation::Skip | Operation::SkipAll) => rv.2 += 1,
+                None => {}
+            }
+        }
+        rv
+    }
+}
+
+/// Restores the terminal when the interface is left, including on errors.
+struct ScreenGuard<'t> {
+    term: &'t Term,
+}
+
+impl<'t> ScreenGuard<'t> {
+    fn enter(term: &'t Term) -> io::Result<ScreenGuard<'t>> {
+        // switch to the alternate screen so the scrollback is left untouched
+        term.write_str("\x1b[?1049h")?;
+        term.hide_cursor()?;
+        Ok(ScreenGuard { term })
+    }
+}
+
+impl Drop for ScreenGuard<'_> {
+    fn drop(&mut self) {
+        self.term.show_cursor().ok();
+        self.term.write_str("\x1b[?1049l").ok();
+    }
+}
+
+/// Runs the interactive review interface.
+///
+/// Returns once the user finishes the review with `q`.  The decisions are
+/// stored in [`ReviewItem::decision`]; undecided snapshots are left as `None`.
+pub(crate) fn run(
+    workspace_root: &Path,
+    term: &Term,
+    diff_style: DiffStyle,
+    items: &mut [ReviewItem<'_>],
+) -> Result<(), Box<dyn Error>> {
+    let _guard = ScreenGuard::enter(term)?;
+    let mut state = ReviewState::new(items, diff_style);
+    let mut rendered_key = None;
+    let mut lines = vec![];
+
+    loop {
+        let (height, width) = term.size();
+        let (height, width) = (height as usize, width as usize);
+        let sidebar_width = (width / 3).clamp(20, 48).min(width / 2);
+        let pane_width = width.saturating_sub(sidebar_width + 3);
+        // header line, blank line and status line
+        let body_height = height.saturating_sub(3).max(1);
+
+        // rendering can be expensive (the info section runs rustfmt) so only
+        // re-render when something affecting the output changed
+        let key = (
+            state.selected,
+            state.show_info,
+            state.show_diff,
+            state.diff_style,
+            pane_width,
+        );
+        if rendered_key != Some(key) {
+            lines = render_snapshot(workspace_root, &state, pane_width);
+            rendered_key = Some(key);
+        }
+
+        state.diff_offset = state
+            .diff_offset
+            .min(lines.len().saturating_sub(body_height));
+        let selected_row = state
+            .rows
+            .iter()
+            .position(|row| *row == Row::Snapshot(state.selected))
+            .unwrap_or(0);
+        if selected_row < state.sidebar_offset {
+            state.sidebar_offset = selected_row.saturating_sub(1);
+        } else if selected_row >= state.sidebar_offset + body_height {
+            state.sidebar_offset = selected_row + 1 - body_height;
+        }
+
+        let frame = draw(
+            &state,
+            &lines,
+            width,
+            sidebar_width,
+            pane_width,
+            body_height,
+        );
+        term.move_cursor_to(0, 0)?;
+        term.write_str(&frame)?;
+        term.flush()?;
+
+        let key = term.read_key_raw()?;
+        match state.handle_key(key, body_height) {
+            Flow::Continue => {}
+            Flow::Finish => return Ok(()),
+            Flow::Abort => return Err(QuietExit(1).into()),
+        }
+    }
+}
+
+fn render_snapshot(
+    workspace_root: &Path,
+    state: &ReviewState<'_, '_>,
+    width: usize,
+) -> Vec<String> {
+    let item = &state.items[state.selected];
+    let mut printer = SnapshotPrinter::new(
+        workspace_root,
+        item.snapshot.old.as_ref(),
+        &item.snapshot.new,
+    );
+    printer.set_snapshot_file(item.snapshot_file.as_deref());
+    printer.set_line(item.snapshot.line);
+    printer.set_show_info(state.show_info);
+    printer.set_show_diff(state.show_diff);
+    printer.set_diff_style(state.diff_style);
+    printer.set_width(Some(width));
+    printer
+        .render()
+        .lines()
+        .map(|line| line.replace('\t', "    "))
+        .collect()
+}
+
+fn decision_marker(decision: Option<Operation>) -> String {
+    match decision {
+        Some(Operation::Accept | Operation::AcceptAll) => style("✔").green().to_string(),
+        Some(Operation::Reject | Operation::RejectAll) => style("✘").red().to_string(),
+        Some(Operation::Skip | Operation::SkipAll) => style("»").yellow().to_string(),
+        None => style("·").dim().to_string(),
+    }
+}
+
+fn draw(
+    state: &ReviewState<'_, '_>,
+    lines: &[String],
+    width: usize,
+    sidebar_width: usize,
+    pane_width: usize,
+    body_height: usize,
+) -> String {
+    let mut frame = vec![];
+    let (accepted, rejected, skipped) = state.counts();
+    frame.push(format!(
+        "{} {} {}",
+        style("insta review").bold(),
+        style(format!("[{}/{}]", state.selected + 1, state.items.len()))
+            .yellow()
+            .bold(),
+        style(format!(
+            "{accepted} accepted, {rejected} rejected, {skipped} skipped"
+        ))
+        .dim(),
+    ));
+    frame.push(String::new());
+
+    for line_idx in 0..body_height {
+        let sidebar = match state.rows.get(state.sidebar_offset + line_idx) {
+            Some(Row::Package(name)) => style(name.as_str()).bold().to_string(),
+            Some(Row::File(file)) => format!(" {}", style(file.as_str()).cyan()),
+            Some(Row::Snapshot(idx)) => {
+                let item = &state.items[*idx];
+                let label = format!("   {} {}", decision_marker(item.decision), item.label());
+                if *idx == state.selected {
+                    style(label).reverse().to_string()
+                } else {
+                    label
+                }
+            }
+            None => String::new(),
+        };
+        let content = lines
+            .get(state.diff_offset + line_idx)
+            .map(|line| truncate_str(line, pane_width, "…"))
+            .unwrap_or_default();
+        frame.push(format!(
+            "{} {} {}",
+            pad_str(&sidebar, sidebar_width, Alignment::Left, Some("…")),
+            style("│").dim(),
+            content
+        ));
+    }
+
+    let status = match state.mode {
+        Mode::Search(ref query) => format!("/{query}"),
+        Mode::Normal => match state.message {
+            Some(ref message) => style(message).yellow().to_string(),
+            None => style(
+                "a accept  r reject  s skip  A/R/S all remaining  ↑↓ select  \
+                 PgUp/PgDn scroll  i info  d diff  v layout  / search  n/N next/prev  q finish",
+            )
+            .dim()
+            .to_string(),
+        },
+    };
+    frame.push(truncate_str(&status, width, "…").into_owned());
+
+    // clear the remainder of every line so that leftovers of the previous frame
+    // disappear without clearing the entire screen (which would flicker)
+    let mut rv = String::new();
+    for (idx, line) in frame.iter().enumerate() {
+        if idx > 0 {
+            rv.push_str("\r\n");
+        }
+        if measure_text_width(line) > width {
+            rv.push_str(&truncate_str(line, width, ""));
+        } else {
+            rv.push_str(line);
+        }
+        rv.push_str("\x1b[K");
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::container::PendingSnapshot;
+
+    fn make_items<'a>(snapshots: &'a mut [PendingSnapshot]) -> Vec<ReviewItem<'a>> {
+        snapshots
+            .iter_mut()
+            .enumerate()
+            .map(|(idx, snapshot)| ReviewItem {
+                package: "demo@0.1.0".into(),
+                file: format!("tests/test_{}.rs", idx / 2),
+                snapshot_file: None,
+                snapshot,
+                decision: None,
+            })
+            .collect()
+    }
+
+    fn make_snapshots() -> Vec<PendingSnapshot> {
+        ["alpha", "beta", "gamma", "delta"]
+            .iter()
+            .map(|name| PendingSnapshot::for_test(name, Some("old"), "new"))
+            .collect()
+    }
+
+    #[test]
+    fn test_build_rows() {
+        let mut snapshots = make_snapshots();
+        let items = make_items(&mut snapshots);
+        assert_eq!(
+            build_rows(&items),
+            vec![
+                Row::Package("demo@0.1.0".into()),
+                Row::File("tests/test_0.rs".into()),
+                Row::Snapshot(0),
+                Row::Snapshot(1),
+                Row::File("tests/test_1.rs".into()),
+                Row::Snapshot(2),
+                Row::Snapshot(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut snapshots = make_snapshots();
+        let mut items = make_items(&mut snapshots);
+        let mut state = ReviewState::new(&mut items, DiffStyle::default());
+
+        // plain navigation stops at both ends
+        state.select_prev();
+        assert_eq!(state.selected, 0);
+        state.handle_key(Key::End, 10);
+        assert_eq!(state.selected, 3);
+        state.select_next();
+        assert_eq!(state.selected, 3);
+        state.handle_key(Key::Char('k'), 10);
+        assert_eq!(state.selected, 2);
+
+        // selecting another snapshot resets the scroll position
+        state.handle_key(Key::PageDown, 10);
+        assert_eq!(state.diff_offset, 5);
+        state.handle_key(Key::ArrowDown, 10);
+        assert_eq!(state.diff_offset, 0);
+
+        // the next undecided snapshot wraps around
+        state.items[0].decision = Some(Operation::Accept);
+        state.select_next_undecided();
+        assert_eq!(state.selected, 1);
+
+        // navigating without any snapshots does nothing
+        let mut no_items = Vec::new();
+        let mut state = ReviewState::new(&mut no_items, DiffStyle::default());
+        state.handle_key(Key::End, 10);
+        state.handle_key(Key::Home, 10);
+        state.handle_key(Key::ArrowDown, 10);
+        assert_eq!(state.selected, 0);
+    }
+
+    #[test]
+    fn test_decisions() {
+        let mut snapshots = make_snapshots();
+        let mut items = make_items(&mut snapshots);
+        let mut state = ReviewState::new(&mut items, DiffStyle::default());
+
+        state.select(2);
+        assert_eq!(state.handle_key(Key::Char('r'), 10), Flow::Continue);
+        assert!(matches!(state.items[2].decision, Some(Operation::Reject)));
+        assert_eq!(state.selected, 3);
+        state.handle_key(Key::Char('a'), 10);
+        assert!(matches!(state.items[3].decision, Some(Operation::Accept)));
+        // wrapped around to the first undecided snapshot
+        assert_eq!(state.selected, 0);
+
+        state.handle_key(Key::Char('S'), 10);
+        assert!(matches!(state.items[0].decision, Some(Operation::Skip)));
+        assert!(matches!(state.items[1].decision, Some(Operation::Skip)));
+        assert!(matches!(state.items[2].decision, Some(Operation::Reject)));
+        assert_eq!(state.counts(), (1, 1, 2));
+
+        assert_eq!(state.handle_key(Key::Char('q'), 10), Flow::Finish);
+        assert_eq!(state.handle_key(Key::CtrlC, 10), Flow::Abort);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut snapshots = make_snapshots();
+        let mut items = make_items(&mut snapshots);
+        let mut state = ReviewState::new(&mut items, DiffStyle::default());
+
+        for key in [Key::Char('/'), Key::Char('T'), Key::Char('a'), Key::Enter] {
+            state.handle_key(key, 10);
+        }
+        assert_eq!(state.query.as_deref(), Some("Ta"));
+        // "beta" is the first match, case insensitive
+        assert_eq!(state.selected, 1);
+        state.handle_key(Key::Char('n'), 10);
+        assert_eq!(state.selected, 3);
+        // searching forwards wraps around
+        state.handle_key(Key::Char('n'), 10);
+        assert_eq!(state.selected, 1);
+        state.handle_key(Key::Char('N'), 10);
+        assert_eq!(state.selected, 3);
+        assert_eq!(state.message, None);
+
+        for key in [Key::Char('/'), Key::Char('x'), Key::Enter] {
+            state.handle_key(key, 10);
+        }
+        assert_eq!(state.selected, 3);
+        assert_eq!(state.message.as_deref(), Some("no snapshot matches `x`"));
+
+        // escape leaves the search without changing the query
+        for key in [Key::Char('/'), Key::Char('z'), Key::Escape] {
+            state.handle_key(key, 10);
+        }
+        assert_eq!(state.query.as_deref(), Some("x"));
+    }
+}
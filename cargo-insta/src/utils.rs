@@ -1,6 +1,8 @@
 use std::fmt;
 use std::{env, error::Error};
 
+use insta::_cargo_insta_support::DiffStyle;
+
 /// Close without message but exit code.
 #[derive(Debug)]
 pub(crate) struct QuietExit(pub(crate) i32);
@@ -33,3 +35,13 @@ pub(crate) fn err_msg<S: Into<String>>(s: S) -> Box<dyn Error> {
 pub(crate) fn cargo_insta_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
+
+/// Returns the diff layout that follows `style` when cycling through them
+/// during review.
+pub(crate) fn next_diff_style(style: DiffStyle) -> DiffStyle {
+    match style {
+        DiffStyle::Unified => DiffStyle::SideBySide,
+        DiffStyle::SideBySide => DiffStyle::Structural,
+        DiffStyle::Structural => DiffStyle::Unified,
+    }
+}
@@ -0,0 +1,38 @@
+function post(path, params) {
+  params.token = TOKEN;
+  return fetch(path, { method: "POST", body: new URLSearchParams(params) });
+}
+
+function updateCounts() {
+  const sections = document.querySelectorAll(".snapshot");
+  const count = (op) =>
+    document.querySelectorAll(`.snapshot[data-decision="${op}"]`).length;
+  document.getElementById("counts").textContent =
+    `${sections.length} snapshots: ${count("accept")} accepted, ` +
+    `${count("reject")} rejected, ${count("skip")} skipped`;
+}
+
+document.querySelectorAll(".snapshot .actions button").forEach((button) => {
+  button.addEventListener("click", async () => {
+    const section = button.closest(".snapshot");
+    const op = button.dataset.op;
+    const response = await post("/decide", { id: section.dataset.id, op });
+    if (response.ok) {
+      section.dataset.decision = op;
+      // the server finishes the review once these stop after the page was closed
+setInterval(() => fetch("/ping"), 20000);
+
+updateCounts();
+    }
+  });
+});
+
+document.getElementById("finish").addEventListener("click", async () => {
+  const response = await post("/finish", {});
+  document.body.innerHTML = await response.text();
+});
+
+// the server finishes the review once these stop after the page was closed
+setInterval(() => fetch("/ping"), 20000);
+
+updateCounts();
@@ -0,0 +1,753 @@
+//! Browser based review for `cargo insta review --web`.
+//!
+//! A tiny HTTP server is started on localhost which serves a single page with
+//! all pending snapshots.  Decisions are posted back to the server and applied
+//! by the caller once the review is finished in the browser.
+//!
+//! The same rendering is used for the static report written by
+//! `cargo insta report --html`.
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use console::{style, Term};
+use insta::{internals::SnapshotContents, Snapshot};
+use similar::DiffTag;
+use uuid::Uuid;
+
+use crate::container::{diff_hunks, Operation, ReviewItem};
+
+/// Requests larger than this are rejected; decisions are tiny.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// The review is finished if the page sent no request for this long.  The
+/// page sends a heartbeat (see `web.js`), so this only happens once it was
+/// closed.  Browsers throttle timers of background tabs to about one per
+/// minute, which is why this is generous.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(180);
+
+struct Request {
+    method: String,
+    path: String,
+    host: Option<String>,
+    body: String,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    sandboxed: bool,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            content_type,
+            sandboxed: false,
+            body: body.into(),
+        }
+    }
+
+    /// Keeps scripts in snapshot files (e.g. SVG) from running with the
+    /// origin of the review page.
+    fn sandboxed(mut self) -> Self {
+        self.sandboxed = true;
+        self
+    }
+
+    fn not_found() -> Self {
+        Response::new("404 Not Found", "text/plain", "not found")
+    }
+
+    fn write_to(&self, stream: &mut TcpStream) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             X-Content-Type-Options: nosniff\r\n{}\
+             Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            if self.sandboxed {
+                "Content-Security-Policy: sandbox\r\n"
+            } else {
+                ""
+            }
+        )?;
+        stream.write_all(&self.body)?;
+        stream.flush()
+    }
+}
+
+/// Runs the review server until the review is finished in the browser.
+///
+/// The decisions are stored in [`ReviewItem::decision`].  If the page is
+/// closed without finishing the review, the server stops after
+/// [`IDLE_TIMEOUT`] and the decisions made so far are applied.
+pub(crate) fn run(
+    workspace_root: &Path,
+    port: u16,
+    items: &mut [ReviewItem<'_>],
+) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let addr = listener.local_addr()?;
+    let url = format!("http://{addr}/");
+    // every state changing request has to carry this token so that other
+    // pages open in the browser cannot make decisions
+    let token = Uuid::new_v4().simple().to_string();
+
+    println!(
+        "{}: reviewing {} snapshot(s) at {}",
+        style("insta review").bold(),
+        items.len(),
+        style(&url).cyan().underlined()
+    );
+    println!(
+        "{}",
+        style("Finish the review in the browser to apply it.").dim()
+    );
+    if Term::stdout().is_term() {
+        open::that_detached(&url).ok();
+    }
+
+    // the listener is polled so that the idle timeout can be noticed
+    listener.set_nonblocking(true)?;
+    let mut last_request = None;
+    loop {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                if last_request.map_or(false, |x: Instant| x.elapsed() > IDLE_TIMEOUT) {
+                    println!(
+                        "{}: the review page was closed, applying the decisions made so far",
+                        style("insta review").bold()
+                    );
+                    break;
+                }
+                thread::sleep(Duration::from_millis(50));
+                continue;
+            }
+            Err(_) => continue,
+        };
+        // browsers open speculative connections which never send anything;
+        // don't let them block the (single threaded) server
+        if let Err(err) = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(Duration::from_secs(2))))
+        {
+            eprintln!(
+                "{}: failed to set up review connection: {err}",
+                style("warning").yellow().bold()
+            );
+            continue;
+        }
+        let request = match read_request(&mut stream) {
+            Ok(Some(request)) => request,
+            Ok(None) | Err(_) => continue,
+        };
+        last_request = Some(Instant::now());
+
+        let mut finished = false;
+        let response = match (request.method.as_str(), request.path.as_str()) {
+            // other sites could otherwise read the snapshots by rebinding
+            // their domain to 127.0.0.1
+            _ if !is_allowed_host(request.host.as_deref(), addr.port()) => {
+                Response::new("403 Forbidden", "text/plain", "forbidden")
+            }
+            ("GET", "/") => Response::new(
+                "200 OK",
+                "text/html; charset=utf-8",
+                render_page(workspace_root, items, &token),
+            ),
+            ("GET", path) if path.starts_with("/binary/") => serve_binary(items, path),
+            ("GET", "/ping") => Response::new("204 No Content", "text/plain", ""),
+            ("POST", "/decide") => {
+                let form = parse_form(&request.body);
+                let id = form_value(&form, "id").and_then(|x| x.parse::<usize>().ok());
+                let op = match form_value(&form, "op") {
+                    Some("accept") => Some(Operation::Accept),
+                    Some("reject") => Some(Operation::Reject),
+                    Some("skip") => Some(Operation::Skip),
+                    _ => None,
+                };
+                match (form_value(&form, "token"), id, op) {
+                    (Some(t), Some(id), Some(op)) if t == token && id < items.len() => {
+                        items[id].decision = Some(op);
+                        Response::new("204 No Content", "text/plain", "")
+                    }
+                    _ => Response::new("400 Bad Request", "text/plain", "bad request"),
+                }
+            }
+            ("POST", "/finish") => {
+                if form_value(&parse_form(&request.body), "token") == Some(token.as_str()) {
+                    finished = true;
+                    Response::new(
+                        "200 OK",
+                        "text/html; charset=utf-8",
+                        "<!doctype html><meta charset=\"utf-8\"><title>insta review</title>\
+                         <p>The review is finished, you can close this page.</p>",
+                    )
+                } else {
+                    Response::new("400 Bad Request", "text/plain", "bad request")
+                }
+            }
+            _ => Response::not_found(),
+        };
+        response.write_to(&mut stream).ok();
+
+        if finished {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_request(stream: &mut TcpStream) -> io::Result<Option<Request>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Ok(None),
+    };
+
+    let mut content_length = 0;
+    let mut host = None;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Ok(None);
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(Request {
+        method,
+        path,
+        host,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }))
+}
+
+/// Checks that the request was made for the review server itself.
+fn is_allowed_host(host: Option<&str>, port: u16) -> bool {
+    match host.and_then(|x| x.rsplit_once(':')) {
+        Some((name, p)) => {
+            (name == "127.0.0.1" || name.eq_ignore_ascii_case("localhost")) && p.parse() == Ok(port)
+        }
+        None => false,
+    }
+}
+
+/// Parses an `application/x-www-form-urlencoded` body.
+fn parse_form(body: &str) -> Vec<(String, String)> {
+    body.split('&')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+fn form_value<'a>(form: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    form.iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut rv = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'+' => rv.push(b' '),
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                rv.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b => rv.push(b),
+        }
+    }
+    String::from_utf8(rv).ok()
+}
+
+/// Serves `/binary/<id>/<old|new>`.
+fn serve_binary(items: &[ReviewItem<'_>], path: &str) -> Response {
+    let mut parts = path.trim_start_matches("/binary/").split('/');
+    let item = match parts.next().and_then(|x| x.parse::<usize>().ok()) {
+        Some(id) if id < items.len() => &items[id],
+        _ => return Response::not_found(),
+    };
+    let snapshot = match parts.next() {
+        Some("old") => item.snapshot.old.as_ref(),
+        Some("new") => Some(&item.snapshot.new),
+        _ => None,
+    };
+    match snapshot.map(|x| (x.contents(), x.metadata().binary_extension())) {
+        Some((SnapshotContents::Binary(Some(data)), extension)) => Response::new(
+            "200 OK",
+            content_type_for_extension(extension.unwrap_or("")),
+            data.as_slice(),
+        )
+        .sandboxed(),
+        _ => Response::not_found(),
+    }
+}
+
+fn content_type_for_extension(extension: &str) -> &'static str {
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "txt" => "text/plain; charset=utf-8",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+fn is_image_extension(extension: &str) -> bool {
+    content_type_for_extension(extension).starts_with("image/")
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => rv.push_str("&amp;"),
+            '<' => rv.push_str("&lt;"),
+            '>' => rv.push_str("&gt;"),
+            '"' => rv.push_str("&quot;"),
+            '\'' => rv.push_str("&#39;"),
+            c => rv.push(c),
+        }
+    }
+    rv
+}
+
+fn op_name(op: Option<Operation>) -> &'static str {
+    match op {
+        Some(Operation::Accept | Operation::AcceptAll) => "accept",
+        Some(Operation::Reject | Operation::RejectAll) => "reject",
+        Some(Operation::Skip | Operation::SkipAll) => "skip",
+        None => "",
+    }
+}
+
+fn render_page(workspace_root: &Path, items: &[ReviewItem<'_>], token: &str) -> String {
+    let mut html = String::new();
+    html.push_str(concat!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\">",
+        "<title>insta review</title><style>",
+        include_str!("web.css"),
+        "</style></head><body>\n"
+    ));
+    writeln!(
+        html,
+        "<header><h1>insta review</h1><span id=\"counts\"></span>\
+         <button id=\"finish\">Finish review</button></header><main>"
+    )
+    .unwrap();
+
+    for (id, item) in items.iter().enumerate() {
+        writeln!(
+            html,
+            "<section class=\"snapshot\" data-id=\"{id}\" data-decision=\"{}\">\
+             <h2><span class=\"package\">{}</span> <span class=\"file\">{}</span> \
+             <span class=\"name\">{}</span></h2>",
+            op_name(item.decision),
+            escape_html(&item.package),
+            escape_html(&item.file),
+            escape_html(&item.label()),
+        )
+        .unwrap();
+        render_metadata(&mut html, workspace_root, item);
+        html.push_str(
+            "<div class=\"actions\"><button data-op=\"accept\">Accept</button>\
+             <button data-op=\"reject\">Reject</button>\
+             <button data-op=\"skip\">Skip</button></div>\n",
+        );
+        render_snapshot_diff(
+            &mut html,
+            &|side| format!("/binary/{id}/{side}"),
+            item.snapshot.old.as_ref(),
+            &item.snapshot.new,
+        );
+        html.push_str("</section>\n");
+    }
+
+    writeln!(
+        html,
+        "</main><script>const TOKEN = \"{}\";\n{}</script></body></html>",
+        escape_html(token),
+        include_str!("web.js")
+    )
+    .unwrap();
+    html
+}
+
+fn render_metadata(html: &mut String, workspace_root: &Path, item: &ReviewItem<'_>) {
+    let metadata = item.snapshot.new.metadata();
+    html.push_str("<dl class=\"metadata\">");
+    if let Some(source) = metadata.get_relative_source(workspace_root) {
+        let line = item.snapshot.line.or(metadata.assertion_line());
+        write!(
+            html,
+            "<dt>Source</dt><dd>{}{}</dd>",
+            escape_html(&source.display().to_string()),
+            line.map(|x| format!(":{x}")).unwrap_or_default()
+        )
+        .unwrap();
+    }
+    if let Some(expression) = metadata.expression() {
+        write!(
+            html,
+            "<dt>Expression</dt><dd><code>{}</code></dd>",
+            escape_html(expression)
+        )
+        .unwrap();
+    }
+    if let Some(description) = metadata.description() {
+        write!(
+            html,
+            "<dt>Description</dt><dd>{}</dd>",
+            escape_html(description)
+        )
+        .unwrap();
+    }
+    if let Some(input_file) = metadata.input_file() {
+        write!(
+            html,
+            "<dt>Input file</dt><dd>{}</dd>",
+            escape_html(input_file)
+        )
+        .unwrap();
+    }
+    if let Some(explanation) = metadata.explanation() {
+        write!(
+            html,
+            "<dt>Mismatch</dt><dd>{}</dd>",
+            escape_html(explanation)
+        )
+        .unwrap();
+    }
+    if let Some(info) = metadata.private_info() {
+        if let Ok(info) = serde_json::to_string_pretty(info) {
+            write!(
+                html,
+                "<dt>Info</dt><dd><pre>{}</pre></dd>",
+                escape_html(&info)
+            )
+            .unwrap();
+        }
+    }
+    html.push_str("</dl>\n");
+}
+
+/// Renders the diff of a snapshot.  `binary_url` returns the URL under which
+/// the `old` or `new` side of a binary snapshot is available.
+fn render_snapshot_diff(
+    html: &mut String,
+    binary_url: &dyn Fn(&str) -> String,
+    old: Option<&Snapshot>,
+    new: &Snapshot,
+) {
+    // binary snapshots (or a change between binary and text) are shown next
+    // to each other rather than diffed
+    if new.contents().is_binary() || old.map_or(false, |x| x.contents().is_binary()) {
+        html.push_str("<div class=\"binary\">");
+        render_binary_side(html, binary_url, "old", old);
+        render_binary_side(html, binary_url, "new", Some(new));
+        html.push_str("</div>\n");
+        return;
+    }
+
+    let old_text = old.and_then(|x| x.as_text()).map(|x| x.to_string());
+    let new_text = new.as_text().map(|x| x.to_string());
+    html.push_str(&render_side_by_side(
+        old_text.as_deref().unwrap_or(""),
+        new_text.as_deref().unwrap_or(""),
+    ));
+}
+
+fn render_binary_side(
+    html: &mut String,
+    binary_url: &dyn Fn(&str) -> String,
+    side: &str,
+    snapshot: Option<&Snapshot>,
+) {
+    write!(
+        html,
+        "<figure class=\"{side}\"><figcaption>{side}</figcaption>"
+    )
+    .unwrap();
+    match snapshot {
+        None => html.push_str("<p class=\"empty\">no snapshot</p>"),
+        Some(snapshot) => match snapshot.contents() {
+            SnapshotContents::Text(text) => {
+                write!(html, "<pre>{}</pre>", escape_html(&text.to_string())).unwrap();
+            }
+            SnapshotContents::Binary(None) => {
+                html.push_str("<p class=\"empty\">binary file is missing</p>");
+            }
+            SnapshotContents::Binary(Some(data)) => {
+                let extension = snapshot.metadata().binary_extension().unwrap_or("");
+                if is_image_extension(extension) {
+                    write!(
+                        html,
+                        "<img src=\"{}\" alt=\"{side} snapshot\">",
+                        escape_html(&binary_url(side))
+                    )
+                    .unwrap();
+                } else {
+                    write!(
+                        html,
+                        "<p><a href=\"{}\">{side}.{}</a> ({} bytes)</p>",
+                        escape_html(&binary_url(side)),
+                        escape_html(extension),
+                        data.len()
+                    )
+                    .unwrap();
+                }
+            }
+        },
+    }
+    html.push_str("</figure>");
+}
+
+/// Writes a static report of all pending snapshots for
+/// `cargo insta report --html`.
+///
+/// The report consists of an index page and one page per snapshot.  Binary
+/// snapshots are copied next to the pages, so the directory can be archived
+/// and viewed without a server.
+pub(crate) fn write_report(
+    out_dir: &Path,
+    workspace_root: &Path,
+    items: &[ReviewItem<'_>],
+) -> Result<(), Box<dyn Error>> {
+    let pages_dir = out_dir.join("snapshots");
+    fs::create_dir_all(&pages_dir)?;
+    fs::write(out_dir.join("index.html"), render_report_index(items))?;
+
+    for (id, item) in items.iter().enumerate() {
+        for (side, snapshot) in [
+            ("old", item.snapshot.old.as_ref()),
+            ("new", Some(&item.snapshot.new)),
+        ] {
+            if let Some(snapshot) = snapshot {
+                if let SnapshotContents::Binary(Some(data)) = snapshot.contents() {
+                    let extension = snapshot.metadata().binary_extension().unwrap_or("bin");
+                    fs::write(
+                        pages_dir.join(report_binary_name(id, side, extension)),
+                        data.as_slice(),
+                    )?;
+                }
+            }
+        }
+        fs::write(
+            pages_dir.join(format!("{id}.html")),
+            render_report_page(workspace_root, items, id),
+        )?;
+    }
+    Ok(())
+}
+
+fn report_binary_name(id: usize, side: &str, extension: &str) -> String {
+    format!("{id}.{side}.{extension}")
+}
+
+fn report_head(html: &mut String, title: &str) {
+    write!(
+        html,
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>\n",
+        escape_html(title),
+        include_str!("web.css")
+    )
+    .unwrap();
+}
+
+fn render_report_index(items: &[ReviewItem<'_>]) -> String {
+    let mut html = String::new();
+    report_head(&mut html, "insta report");
+    writeln!(
+        html,
+        "<header><h1>insta report</h1><span id=\"counts\">{} pending snapshot{}</span></header><main>",
+        items.len(),
+        if items.len() != 1 { "s" } else { "" }
+    )
+    .unwrap();
+    html.push_str("<table class=\"index\">\n<tr><th>Package</th><th>File</th><th>Snapshot</th><th>Status</th></tr>\n");
+    for (id, item) in items.iter().enumerate() {
+        writeln!(
+            html,
+            "<tr><td class=\"package\">{}</td><td class=\"file\">{}</td>\
+             <td><a href=\"snapshots/{id}.html\">{}</a></td><td>{}</td></tr>",
+            escape_html(&item.package),
+            escape_html(&item.file),
+            escape_html(&item.label()),
+            if item.snapshot.old.is_some() {
+                "changed"
+            } else {
+                "new"
+            }
+        )
+        .unwrap();
+    }
+    html.push_str("</table>\n</main></body></html>\n");
+    html
+}
+
+fn render_report_page(workspace_root: &Path, items: &[ReviewItem<'_>], id: usize) -> String {
+    let item = &items[id];
+    let mut html = String::new();
+    report_head(&mut html, &format!("{} - insta report", item.label()));
+
+    html.push_str("<header><h1>insta report</h1><nav><a href=\"../index.html\">index</a>");
+    if id > 0 {
+        write!(html, " <a href=\"{}.html\">previous</a>", id - 1).unwrap();
+    }
+    if id + 1 < items.len() {
+        write!(html, " <a href=\"{}.html\">next</a>", id + 1).unwrap();
+    }
+    writeln!(
+        html,
+        "</nav><span id=\"counts\">{} of {}</span></header><main>",
+        id + 1,
+        items.len()
+    )
+    .unwrap();
+
+    writeln!(
+        html,
+        "<section class=\"snapshot\"><h2><span class=\"package\">{}</span> \
+         <span class=\"file\">{}</span> <span class=\"name\">{}</span></h2>",
+        escape_html(&item.package),
+        escape_html(&item.file),
+        escape_html(&item.label()),
+    )
+    .unwrap();
+    render_metadata(&mut html, workspace_root, item);
+    render_snapshot_diff(
+        &mut html,
+        &|side| {
+            let snapshot = match side {
+                "old" => item.snapshot.old.as_ref(),
+                _ => Some(&item.snapshot.new),
+            };
+            let extension = snapshot
+                .and_then(|x| x.metadata().binary_extension())
+                .unwrap_or("bin");
+            report_binary_name(id, side, extension)
+        },
+        item.snapshot.old.as_ref(),
+        &item.snapshot.new,
+    );
+    html.push_str("</section>\n</main></body></html>\n");
+    html
+}
+
+/// Renders a side-by-side diff of two texts as an HTML table.
+pub(crate) fn render_side_by_side(old: &str, new: &str) -> String {
+    let (diff, hunks) = diff_hunks(old, new);
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+    let cell = |lines: &[&str], idx: Option<usize>, class: &str| match idx {
+        Some(idx) => format!(
+            "<td class=\"ln\">{}</td><td class=\"{class}\">{}</td>",
+            idx + 1,
+            escape_html(lines[idx].trim_end_matches(['\r', '\n']))
+        ),
+        None => "<td class=\"ln\"></td><td class=\"none\"></td>".to_string(),
+    };
+
+    let mut html = String::from("<table class=\"diff\">\n");
+    if hunks.is_empty() {
+        html.push_str("<tr><td class=\"same\" colspan=\"4\">snapshots are matching</td></tr>\n");
+    }
+    for (hunk_idx, hunk) in hunks.iter().enumerate() {
+        if hunk_idx > 0 {
+            html.push_str("<tr class=\"sep\"><td colspan=\"4\">⋯</td></tr>\n");
+        }
+        for op in hunk {
+            let old_range = op.old_range();
+            let new_range = op.new_range();
+            let (old_class, new_class) = match op.tag() {
+                DiffTag::Equal => ("eq", "eq"),
+                DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => ("del", "ins"),
+            };
+            for row in 0..old_range.len().max(new_range.len()) {
+                let old_idx = Some(old_range.start + row).filter(|x| old_range.contains(x));
+                let new_idx = Some(new_range.start + row).filter(|x| new_range.contains(x));
+                writeln!(
+                    html,
+                    "<tr>{}{}</tr>",
+                    cell(old_lines, old_idx, old_class),
+                    cell(new_lines, new_idx, new_class)
+                )
+                .unwrap();
+            }
+        }
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_form() {
+        let form = parse_form("token=abc&id=3&op=accept&msg=a+b%26c");
+        assert_eq!(form_value(&form, "id"), Some("3"));
+        assert_eq!(form_value(&form, "op"), Some("accept"));
+        assert_eq!(form_value(&form, "msg"), Some("a b&c"));
+        assert_eq!(form_value(&form, "missing"), None);
+    }
+
+    #[test]
+    fn test_is_allowed_host() {
+        assert!(is_allowed_host(Some("127.0.0.1:8080"), 8080));
+        assert!(is_allowed_host(Some("localhost:8080"), 8080));
+        assert!(!is_allowed_host(Some("127.0.0.1:8081"), 8080));
+        assert!(!is_allowed_host(Some("127.0.0.1"), 8080));
+        assert!(!is_allowed_host(Some("evil.example:8080"), 8080));
+        assert!(!is_allowed_host(None, 8080));
+    }
+
+    #[test]
+    fn test_render_side_by_side() {
+        let html = render_side_by_side("a\nb\n<c>\n", "a\nB\n<c>\nd\n");
+        assert!(html
+            .contains("<td class=\"del\">b</td><td class=\"ln\">2</td><td class=\"ins\">B</td>"));
+        assert!(html.contains("<td class=\"eq\">&lt;c&gt;</td>"));
+        assert!(html.contains("<td class=\"ln\"></td><td class=\"none\"></td><td class=\"ln\">4</td><td class=\"ins\">d</td>"));
+    }
+}
@@ -1,5 +1,8 @@
 //! Functional tests for custom [`Comparator`] implementations.
 
+use std::fs;
+use std::process::Stdio;
+
 use crate::TestFiles;
 
 /// Test that a custom comparator can override default matching behavior.
@@ -355,3 +358,241 @@ mod tests {
         String::from_utf8_lossy(&output.stderr)
     );
 }
+
+/// Test that accepting a snapshot of the template comparator keeps the
+/// placeholders in the lines that still match.
+#[test]
+fn test_template_comparator_accept() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_template_comparator_accept")
+        .add_file(
+            "src/snapshots/test_template_comparator_accept__tests__log.snap",
+            r#"---
+source: src/lib.rs
+expression: value
+---
+started at [..]
+...
+request [ID] ok
+finished in [..]
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[cfg(test)]
+mod tests {
+    use insta::comparator::TemplateComparator;
+    use insta::{with_settings, assert_snapshot};
+
+    #[test]
+    fn test_log() {
+        let value = "started at 12:03\nloading\nrequest 17 failed\nfinished in 31ms";
+        with_settings!({comparator => Box::new(TemplateComparator)}, {
+            assert_snapshot!("log", value);
+        });
+    }
+}
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept", "--", "--nocapture"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "Test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let snapshot = std::fs::read_to_string(
+        test_project
+            .workspace_dir
+            .join("src/snapshots/test_template_comparator_accept__tests__log.snap"),
+    )
+    .unwrap();
+    assert!(
+        snapshot.ends_with("---\nstarted at [..]\n...\nrequest 17 failed\nfinished in [..]\n"),
+        "{snapshot}"
+    );
+}
+
+/// Test that comparators can be selected by snapshot path in `insta.yaml`.
+#[test]
+fn test_comparators_from_config() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_comparators_from_config")
+        .add_file(
+            "insta.yaml",
+            r#"
+comparators:
+  - glob: "src/snapshots/*__json_*.snap"
+    kind: json-semantic
+  - glob: "**/*__float_*.snap"
+    kind: float
+    abs: 1e-6
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_comparators_from_config__tests__json_user.snap",
+            r#"---
+source: src/lib.rs
+expression: value
+---
+{
+  "name": "a",
+  "id": 1
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_comparators_from_config__tests__float_sum.snap",
+            r#"---
+source: src/lib.rs
+expression: value
+---
+sum: 0.3
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_comparators_from_config__tests__other.snap",
+            r#"---
+source: src/lib.rs
+expression: value
+---
+sum: 0.3
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r##"
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_json() {
+        assert_snapshot!("json_user", r#"{"id": 1, "name": "a"}"#);
+    }
+
+    #[test]
+    fn test_float() {
+        assert_snapshot!("float_sum", format!("sum: {}", 0.1 + 0.2));
+    }
+
+    #[test]
+    fn test_other() {
+        assert_snapshot!("other", format!("sum: {}", 0.1 + 0.2));
+    }
+}
+"##
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success());
+    assert!(stdout.contains("test tests::test_json ... ok"), "{stdout}");
+    assert!(stdout.contains("test tests::test_float ... ok"), "{stdout}");
+    assert!(
+        stdout.contains("test tests::test_other ... FAILED"),
+        "{stdout}"
+    );
+}
+
+/// Test that the image comparator tolerates small pixel differences and
+/// stores a diff image next to the pending snapshot until it is reviewed.
+#[test]
+fn test_image_comparator_diff_image() {
+    let lib_rs = |delta: u8| {
+        format!(
+            r#"
+#[test]
+fn test_image() {{
+    // a 4x4 gradient where the first pixel is off by {delta}
+    let mut ppm = b"P6 4 4 255\n".to_vec();
+    for idx in 0..16u8 {{
+        let value = idx * 16 + if idx == 0 {{ {delta} }} else {{ 0 }};
+        ppm.extend_from_slice(&[value, value, value]);
+    }}
+    let mut settings = insta::Settings::clone_current();
+    settings.set_comparator(Box::new(insta::comparator::ImageComparator::new(8, 0.0)));
+    settings.bind(|| {{
+        insta::assert_binary_snapshot!("image.ppm", ppm);
+    }});
+}}
+"#
+        )
+    };
+    let test_project = TestFiles::new()
+        .add_file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "test_image_comparator_diff_image"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+insta = { path = '$PROJECT_PATH', features = ["image"] }
+"#
+            .to_string(),
+        )
+        .add_file("src/lib.rs", lib_rs(0))
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--accept"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // within the threshold
+    test_project.update_file("src/lib.rs", lib_rs(5));
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    test_project.update_file("src/lib.rs", lib_rs(100));
+    let output = test_project.insta_cmd().args(["test"]).output().unwrap();
+    assert!(!output.status.success());
+    let pending = fs::read_to_string(
+        test_project
+            .workspace_dir
+            .join("src/snapshots/test_image_comparator_diff_image__image.snap.new"),
+    )
+    .unwrap();
+    assert!(
+        pending.contains("1 of 16 pixels (6.25%) differ by more than 8 per channel"),
+        "{pending}"
+    );
+    let diff_image = test_project
+        .workspace_dir
+        .join("src/snapshots/test_image_comparator_diff_image__image.snap.new.diff.png");
+    assert!(diff_image.is_file());
+
+    let output = test_project.insta_cmd().args(["reject"]).output().unwrap();
+    assert!(output.status.success());
+    assert!(!diff_image.exists());
+}
@@ -0,0 +1,77 @@
+use std::process::Stdio;
+
+use crate::TestFiles;
+
+/// Test that redaction presets can be enabled for all snapshots in `insta.yaml`.
+#[test]
+fn test_redaction_presets_from_config() {
+    let test_project = TestFiles::new()
+        .add_file(
+            "Cargo.toml",
+            r#"
+[package]
+name = "test_redaction_presets_from_config"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+doctest = false
+
+[dependencies]
+insta = { path = '$PROJECT_PATH', features = ["yaml", "redactions"] }
+serde = { version = "1.0", features = ["derive"] }
+"#
+            .to_string(),
+        )
+        .add_file(
+            "insta.yaml",
+            r#"
+redactions:
+  presets: ["uuid", "timestamp"]
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/lib.rs",
+            r##"
+#[cfg(test)]
+mod tests {
+    use insta::assert_yaml_snapshot;
+
+    #[derive(serde::Serialize)]
+    struct Event {
+        id: String,
+        message: String,
+    }
+
+    #[test]
+    fn test_event() {
+        let event = Event {
+            id: "67e55044-10b1-426f-9247-bb680e5fe0c8".into(),
+            message: "created at 2024-01-01T10:00:00Z".into(),
+        };
+        assert_yaml_snapshot!(event, @r#"
+        id: "[uuid]"
+        message: "created at [timestamp]"
+        "#);
+    }
+}
+"##
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args(["test", "--check"])
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,144 @@
+//! Functional tests for `cargo insta test --report` and `cargo insta report`.
+
+use std::fs;
+
+use crate::TestFiles;
+
+/// Test that every snapshot assertion ends up in the JUnit and JSON reports.
+#[test]
+fn test_report_junit_and_json() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_report_junit_and_json")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_passing() {
+    insta::assert_snapshot!("passing", "hello");
+}
+
+#[test]
+fn test_failing() {
+    insta::assert_snapshot!("failing", "new value");
+}
+
+#[test]
+fn test_new() {
+    insta::assert_snapshot!("new", "unseen");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_report_junit_and_json__passing.snap",
+            r#"---
+source: src/lib.rs
+expression: "\"hello\""
+---
+hello
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_report_junit_and_json__failing.snap",
+            r#"---
+source: src/lib.rs
+expression: "\"new value\""
+---
+old value
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project
+        .insta_cmd()
+        .args([
+            "test",
+            "--report",
+            "junit=reports/insta.xml",
+            "--report",
+            "json=reports/insta.json",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let junit = fs::read_to_string(test_project.workspace_dir.join("reports/insta.xml")).unwrap();
+    assert!(junit.contains(r#"<testsuites name="insta" tests="3" failures="2""#));
+    assert!(junit.contains(r#"<testcase name="failing" classname="test_report_junit_and_json::test_failing" file="src/lib.rs" line="9""#));
+    assert!(
+        junit.contains(r#"<failure type="new-snapshot" message="new snapshot &apos;new&apos;">"#)
+    );
+    assert!(junit.contains("-old value\n+new value</failure>"));
+    assert!(junit.contains(
+        r#"<property name="insta.snapshot" value="src/snapshots/test_report_junit_and_json__passing.snap"/>"#
+    ));
+
+    let json: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(test_project.workspace_dir.join("reports/insta.json")).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        json["summary"],
+        serde_json::json!({"total": 3, "passed": 1, "failed": 1, "new": 1, "updated": 0})
+    );
+}
+
+/// Test that `cargo insta report --html` renders every pending snapshot.
+#[test]
+fn test_report_html() {
+    let test_project = TestFiles::new()
+        .add_cargo_toml("test_report_html")
+        .add_file(
+            "src/lib.rs",
+            r#"
+#[test]
+fn test_changed() {
+    insta::assert_snapshot!("changed", "new <value>");
+}
+
+#[test]
+fn test_new() {
+    insta::assert_snapshot!("unseen", "hello");
+}
+"#
+            .to_string(),
+        )
+        .add_file(
+            "src/snapshots/test_report_html__changed.snap",
+            r#"---
+source: src/lib.rs
+expression: "\"new <value>\""
+---
+old <value>
+"#
+            .to_string(),
+        )
+        .create_project();
+
+    let output = test_project.insta_cmd().args(["test"]).output().unwrap();
+    assert!(!output.status.success());
+
+    let output = test_project
+        .insta_cmd()
+        .args(["report", "--html", "out"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let out = test_project.workspace_dir.join("out");
+    let index = fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("2 pending snapshots"));
+    assert!(index.contains(r#"<a href="snapshots/0.html">"#));
+    assert!(index.contains(r#"<a href="snapshots/1.html">"#));
+
+    let pages = [
+        fs::read_to_string(out.join("snapshots/0.html")).unwrap(),
+        fs::read_to_string(out.join("snapshots/1.html")).unwrap(),
+    ];
+    let changed = pages.iter().find(|x| x.contains("changed")).unwrap();
+    assert!(changed.contains(r#"<td class="del">old &lt;value&gt;</td>"#));
+    assert!(changed.contains(r#"<td class="ins">new &lt;value&gt;</td>"#));
+    assert!(changed.contains("<dt>Expression</dt>"));
+}
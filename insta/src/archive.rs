@@ -0,0 +1,439 @@
+//! Comparison of archive snapshots by their entries.
+//!
+//! Archives are read into a list of their files so that metadata like
+//! modification times and permissions, which make generated archives differ
+//! on every run, do not cause mismatches.  Zip files (stored or deflated
+//! entries), tar files and gzip compressed tar files are supported.
+use std::fmt::Write;
+use std::io::Read;
+
+use crate::comparator::{Comparator, DefaultComparator};
+use crate::snapshot::{Snapshot, SnapshotContents};
+
+/// Compares archive snapshots by the names and contents of their entries.
+///
+/// Binary snapshots with a `zip`, `tar`, `tar.gz` or `tgz` extension are
+/// read and match if they contain the same files with the same contents.
+/// The order of the entries, modification times, permissions and
+/// compression settings are ignored, as are directory entries. Snapshots
+/// which cannot be read are compared like with the [`DefaultComparator`].
+///
+/// Independent of the comparator, failed assertions of archive snapshots
+/// show a diff of the entry listing and of the textual entries.
+///
+/// This requires the `archive` feature.
+///
+/// ```no_run
+/// use insta::comparator::ArchiveComparator;
+///
+/// # let bundle_zip: Vec<u8> = vec![];
+/// let mut settings = insta::Settings::clone_current();
+/// settings.set_comparator(Box::new(ArchiveComparator));
+/// settings.bind(|| {
+///     insta::assert_binary_snapshot!("bundle.zip", bundle_zip);
+/// });
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "archive")))]
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveComparator;
+
+impl Comparator for ArchiveComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        match read_snapshots(reference, test) {
+            Some((a, b)) => a == b,
+            None => DefaultComparator.matches(reference, test),
+        }
+    }
+
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let (a, b) = read_snapshots(reference, test)?;
+        let mut differences =
+            a.iter()
+                .filter(|entry| !b.iter().any(|x| x.name == entry.name))
+                .map(|entry| format!("{} was removed", entry.name))
+                .chain(b.iter().filter_map(
+                    |entry| match a.iter().find(|x| x.name == entry.name) {
+                        None => Some(format!("{} was added", entry.name)),
+                        Some(old) if old.data != entry.data => {
+                            Some(format!("{} has different contents", entry.name))
+                        }
+                        Some(_) => None,
+                    },
+                ));
+        let first = differences.next()?;
+        Some(match differences.count() {
+            0 => format!("archive entry {first}"),
+            more => format!("archive entry {first} (and {more} more)"),
+        })
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
+/// A file in an archive.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Entry {
+    name: String,
+    data: Vec<u8>,
+}
+
+/// Reads both snapshots if they are archives of the same format.
+fn read_snapshots(reference: &Snapshot, test: &Snapshot) -> Option<(Vec<Entry>, Vec<Entry>)> {
+    let extension = reference.metadata().binary_extension()?;
+    if test.metadata().binary_extension() != Some(extension) {
+        return None;
+    }
+    let read = |snapshot: &Snapshot| match snapshot.contents() {
+        SnapshotContents::Binary(Some(data)) => read_archive(extension, data),
+        _ => None,
+    };
+    Some((read(reference)?, read(test)?))
+}
+
+/// Reads the files of an archive by the extension of its snapshot.
+///
+/// The entries are sorted by name.  Returns `None` if the extension is not
+/// an archive format or if the archive cannot be read.
+pub(crate) fn read_archive(extension: &str, data: &[u8]) -> Option<Vec<Entry>> {
+    let mut entries = match extension.to_ascii_lowercase().as_str() {
+        "zip" => read_zip(data)?,
+        "tar" => read_tar(data)?,
+        "tar.gz" | "tgz" => {
+            let mut tar = Vec::new();
+            flate2::read::MultiGzDecoder::new(data)
+                .read_to_end(&mut tar)
+                .ok()?;
+            read_tar(&tar)?
+        }
+        _ => return None,
+    };
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Some(entries)
+}
+
+/// Renders the entries of an archive for a diff.
+///
+/// Every entry is listed with its size and checksum, followed by the
+/// contents of the entries that are text.
+pub(crate) fn render_entries(entries: &[Entry]) -> String {
+    let mut rv = String::new();
+    for entry in entries {
+        writeln!(
+            rv,
+            "{}  {} bytes  crc32:{:08x}",
+            entry.name,
+            entry.data.len(),
+            crc32fast::hash(&entry.data)
+        )
+        .unwrap();
+    }
+    for entry in entries {
+        if let Some(text) = as_text(&entry.data) {
+            writeln!(rv, "\n=== {} ===", entry.name).unwrap();
+            rv.push_str(text);
+            if !text.is_empty() && !text.ends_with('\n') {
+                rv.push('\n');
+            }
+        }
+    }
+    rv
+}
+
+/// Returns the contents of an entry if it looks like text.
+fn as_text(data: &[u8]) -> Option<&str> {
+    std::str::from_utf8(data)
+        .ok()
+        .filter(|text| !text.contains('\0'))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<usize> {
+    data.get(offset..offset + 2)
+        .map(|x| u16::from_le_bytes([x[0], x[1]]) as usize)
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<usize> {
+    data.get(offset..offset + 4)
+        .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]) as usize)
+}
+
+/// Reads the files of a zip archive through its central directory.
+fn read_zip(data: &[u8]) -> Option<Vec<Entry>> {
+    // the end of central directory record is followed by a comment of at
+    // most 64KiB
+    let search_start = data.len().saturating_sub(22 + 0xffff);
+    let eocd = (search_start..=data.len().checked_sub(22)?)
+        .rev()
+        .find(|&offset| data[offset..offset + 4] == *b"PK\x05\x06")?;
+    let count = u16_at(data, eocd + 10)?;
+    let mut offset = u32_at(data, eocd + 16)?;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if data.get(offset..offset + 4)? != b"PK\x01\x02" {
+            return None;
+        }
+        let method = u16_at(data, offset + 10)?;
+        let compressed_size = u32_at(data, offset + 20)?;
+        let size = u32_at(data, offset + 24)?;
+        let name_len = u16_at(data, offset + 28)?;
+        let extra_len = u16_at(data, offset + 30)?;
+        let comment_len = u16_at(data, offset + 32)?;
+        let local_header = u32_at(data, offset + 42)?;
+        let name = String::from_utf8_lossy(data.get(offset + 46..offset + 46 + name_len)?);
+        offset += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if data.get(local_header..local_header + 4)? != b"PK\x03\x04" {
+            return None;
+        }
+        let start =
+            local_header + 30 + u16_at(data, local_header + 26)? + u16_at(data, local_header + 28)?;
+        let compressed = data.get(start..start + compressed_size)?;
+        let data = match method {
+            0 => compressed.to_vec(),
+            8 => {
+                let mut rv = Vec::with_capacity(size);
+                flate2::read::DeflateDecoder::new(compressed)
+                    .read_to_end(&mut rv)
+                    .ok()?;
+                rv
+            }
+            _ => return None,
+        };
+        entries.push(Entry {
+            name: name.into_owned(),
+            data,
+        });
+    }
+    Some(entries)
+}
+
+/// Reads the regular files of a tar archive.
+///
+/// Long names of GNU and PAX archives are supported, links and special files
+/// are skipped.
+fn read_tar(data: &[u8]) -> Option<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    let mut long_name = None;
+    while let Some(header) = data.get(offset..offset + 512) {
+        // the archive ends with empty blocks
+        if header.iter().all(|&x| x == 0) {
+            break;
+        }
+        let field = |range: std::ops::Range<usize>| {
+            let field = &header[range];
+            let end = field.iter().position(|&x| x == 0).unwrap_or(field.len());
+            String::from_utf8_lossy(&field[..end]).into_owned()
+        };
+        let size = usize::from_str_radix(field(124..136).trim(), 8).ok()?;
+        let contents = data.get(offset + 512..offset + 512 + size)?;
+        offset += 512 + (size + 511) / 512 * 512;
+
+        match header[156] {
+            // GNU long name for the next entry
+            b'L' => {
+                long_name = Some(
+                    String::from_utf8_lossy(contents)
+                        .trim_end_matches('\0')
+                        .into(),
+                );
+            }
+            // PAX extended header with records of the form "<len> <key>=<value>\n"
+            b'x' => {
+                long_name = String::from_utf8_lossy(contents)
+                    .lines()
+                    .find_map(|record| {
+                        record
+                            .split_once(' ')
+                            .and_then(|(_, record)| record.strip_prefix("path="))
+                            .map(Into::into)
+                    });
+            }
+            b'0' | b'\0' | b'7' => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let prefix = field(345..500);
+                    if header[257..262] == *b"ustar" && !prefix.is_empty() {
+                        format!("{}/{}", prefix, field(0..100))
+                    } else {
+                        field(0..100)
+                    }
+                });
+                entries.push(Entry {
+                    name,
+                    data: contents.to_vec(),
+                });
+            }
+            _ => long_name = None,
+        }
+    }
+    Some(entries)
+}
+
+#[cfg(test)]
+fn tar_header(name: &str, size: usize, kind: u8, mtime: u32) -> Vec<u8> {
+    let mut header = vec![0; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[124..136].copy_from_slice(format!("{size:011o}\0").as_bytes());
+    header[136..148].copy_from_slice(format!("{mtime:011o}\0").as_bytes());
+    header[156] = kind;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header
+}
+
+#[cfg(test)]
+fn build_tar(files: &[(&str, &[u8])], mtime: u32) -> Vec<u8> {
+    let mut rv = Vec::new();
+    rv.extend(tar_header("dir/", 0, b'5', mtime));
+    for (name, data) in files {
+        rv.extend(tar_header(name, data.len(), b'0', mtime));
+        rv.extend_from_slice(data);
+        rv.resize((rv.len() + 511) / 512 * 512, 0);
+    }
+    rv.resize(rv.len() + 1024, 0);
+    rv
+}
+
+#[cfg(test)]
+fn build_zip(files: &[(&str, &[u8])], mtime: u16) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut rv = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in files {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut header = Vec::new();
+        header.extend_from_slice(&8u16.to_le_bytes());
+        header.extend_from_slice(&mtime.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+        header.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+
+        central.extend_from_slice(b"PK\x01\x02\x14\x00\x14\x00\x00\x00");
+        central.extend_from_slice(&header);
+        central.extend_from_slice(&[0; 6]);
+        central.extend_from_slice(&0o100644u32.wrapping_shl(16).to_le_bytes());
+        central.extend_from_slice(&(rv.len() as u32).to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        rv.extend_from_slice(b"PK\x03\x04\x14\x00\x00\x00");
+        rv.extend_from_slice(&header);
+        rv.extend_from_slice(name.as_bytes());
+        rv.extend_from_slice(&compressed);
+    }
+    let central_offset = rv.len();
+    rv.extend_from_slice(&central);
+    rv.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00");
+    rv.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    rv.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    rv.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    rv.extend_from_slice(&(central_offset as u32).to_le_bytes());
+    rv.extend_from_slice(&0u16.to_le_bytes());
+    rv
+}
+
+#[cfg(test)]
+fn archive_snapshot(extension: &str, data: Vec<u8>) -> Snapshot {
+    use crate::snapshot::{MetaData, SnapshotKind};
+    use std::rc::Rc;
+
+    Snapshot::from_components(
+        "test".into(),
+        None,
+        MetaData {
+            snapshot_kind: SnapshotKind::Binary {
+                extension: extension.into(),
+            },
+            ..MetaData::default()
+        },
+        SnapshotContents::Binary(Some(Rc::new(data))),
+    )
+}
+
+#[test]
+fn test_read_archives() {
+    let files: &[(&str, &[u8])] = &[("b.txt", b"hello\n"), ("a.bin", b"\x00\x01")];
+    let expected = vec![
+        Entry {
+            name: "a.bin".into(),
+            data: b"\x00\x01".to_vec(),
+        },
+        Entry {
+            name: "b.txt".into(),
+            data: b"hello\n".to_vec(),
+        },
+    ];
+
+    assert_eq!(read_archive("zip", &build_zip(files, 1)), Some(expected));
+    let tar = build_tar(files, 1);
+    assert_eq!(
+        read_archive("tar", &tar).unwrap(),
+        read_archive("zip", &build_zip(files, 2)).unwrap()
+    );
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    std::io::Write::write_all(&mut encoder, &tar).unwrap();
+    assert_eq!(
+        read_archive("tar.gz", &encoder.finish().unwrap()),
+        read_archive("tar", &tar)
+    );
+
+    // GNU long names
+    let long_name = "x".repeat(120);
+    let mut tar = tar_header("././@LongLink", long_name.len(), b'L', 0);
+    tar.extend_from_slice(long_name.as_bytes());
+    tar.resize(1024, 0);
+    tar.extend(tar_header("xxxx", 0, b'0', 0));
+    assert_eq!(read_archive("tar", &tar).unwrap()[0].name, long_name);
+
+    assert_eq!(read_archive("zip", b"not a zip"), None);
+    assert_eq!(read_archive("bin", &build_zip(files, 1)), None);
+}
+
+#[test]
+fn test_archive_comparator() {
+    let files: &[(&str, &[u8])] = &[("b.txt", b"hello\n"), ("a.bin", b"\x00\x01")];
+    let a = archive_snapshot("tar", build_tar(files, 1));
+    let b = archive_snapshot("tar", build_tar(files, 2));
+    assert!(!DefaultComparator.matches(&a, &b));
+    assert!(ArchiveComparator.matches(&a, &b));
+
+    let c = archive_snapshot(
+        "tar",
+        build_tar(&[("b.txt", b"world\n"), ("c.txt", b"")], 1),
+    );
+    assert!(!ArchiveComparator.matches(&a, &c));
+    assert_eq!(
+        ArchiveComparator.explain(&a, &c).as_deref(),
+        Some("archive entry a.bin was removed (and 2 more)")
+    );
+    assert_eq!(
+        render_entries(&read_archive("tar", &build_tar(files, 1)).unwrap()),
+        "a.bin  2 bytes  crc32:36de2269\nb.txt  6 bytes  crc32:363a3020\n\n=== b.txt ===\nhello\n"
+    );
+
+    // diffs show the entries
+    let diff = crate::binary::render_diff(
+        Some(("tar", &build_tar(files, 1))),
+        ("tar", &build_tar(files, 2)),
+    )
+    .unwrap();
+    assert_eq!(diff.old, diff.new.clone().into());
+    assert!(diff.new.starts_with("a.bin  2 bytes"));
+
+    // other snapshots are compared by their bytes
+    let d = archive_snapshot("zip", b"not a zip".to_vec());
+    assert!(ArchiveComparator.matches(&d, &d.clone()));
+    assert_eq!(ArchiveComparator.explain(&d, &d), None);
+}
@@ -0,0 +1,225 @@
+//! Textual renderings of binary snapshots.
+//!
+//! Binary snapshots cannot be diffed line by line, so they are rendered as
+//! text first.  Formats which need a dedicated viewer (like images) are only
+//! linked, text formats are shown as they are, archives are listed by their
+//! entries (with the `archive` feature) and everything else is rendered as a
+//! hexdump.
+use std::fmt::Write;
+
+/// The number of bytes that are rendered into a hexdump.
+const HEXDUMP_LIMIT: usize = 4096;
+
+/// The number of bytes in one line of a hexdump.
+const HEXDUMP_WIDTH: usize = 16;
+
+/// Extensions of formats that are opened in an external viewer instead of
+/// being rendered.
+const VIEWER_EXTENSIONS: &[&str] = &[
+    "avif", "bmp", "flac", "gif", "ico", "jpeg", "jpg", "mov", "mp3", "mp4", "ogg", "pdf", "png",
+    "ppm", "tif", "tiff", "wav", "webm", "webp",
+];
+
+/// Extensions of formats that are rendered as text if they are valid UTF-8.
+const TEXT_EXTENSIONS: &[&str] = &[
+    "css", "csv", "htm", "html", "js", "json", "md", "svg", "toml", "tsv", "txt", "xml", "yaml",
+    "yml",
+];
+
+/// The textual rendering of two binary snapshots for a diff.
+#[derive(Debug, PartialEq)]
+pub(crate) struct BinaryDiff {
+    /// Describes the sizes and the first difference if the hexdump had to be
+    /// cut to [`HEXDUMP_LIMIT`].
+    pub(crate) summary: Option<String>,
+    pub(crate) old: Option<String>,
+    pub(crate) new: String,
+}
+
+/// Renders the old and the new contents of a binary snapshot for a diff.
+///
+/// Both sides are given with the extension of their snapshot.  Returns `None`
+/// if either side is a format that should be opened in a viewer.
+pub(crate) fn render_diff(old: Option<(&str, &[u8])>, new: (&str, &[u8])) -> Option<BinaryDiff> {
+    let is_viewed = |ext: &str| VIEWER_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str());
+    if is_viewed(new.0) || old.map_or(false, |(ext, _)| is_viewed(ext)) {
+        return None;
+    }
+
+    let as_text = |(ext, data): (&str, &[u8])| {
+        if TEXT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) {
+            std::str::from_utf8(data).ok().map(str::to_string)
+        } else {
+            None
+        }
+    };
+    if let Some(new_text) = as_text(new) {
+        match old.map(as_text) {
+            None => {
+                return Some(BinaryDiff {
+                    summary: None,
+                    old: None,
+                    new: new_text,
+                })
+            }
+            Some(Some(old_text)) => {
+                return Some(BinaryDiff {
+                    summary: None,
+                    old: Some(old_text),
+                    new: new_text,
+                })
+            }
+            // a text format changed to something else
+            Some(None) => {}
+        }
+    }
+
+    #[cfg(feature = "archive")]
+    {
+        let as_archive = |(ext, data): (&str, &[u8])| {
+            crate::archive::read_archive(ext, data).map(|x| crate::archive::render_entries(&x))
+        };
+        if let Some(new_entries) = as_archive(new) {
+            match old.map(as_archive) {
+                None => {
+                    return Some(BinaryDiff {
+                        summary: None,
+                        old: None,
+                        new: new_entries,
+                    })
+                }
+                Some(Some(old_entries)) => {
+                    return Some(BinaryDiff {
+                        summary: None,
+                        old: Some(old_entries),
+                        new: new_entries,
+                    })
+                }
+                Some(None) => {}
+            }
+        }
+    }
+
+    Some(render_hexdumps(old.map(|x| x.1), new.1))
+}
+
+/// Renders both sides as hexdumps of the same window of bytes.
+///
+/// If either side is larger than [`HEXDUMP_LIMIT`] the window starts shortly
+/// before the first difference.
+fn render_hexdumps(old: Option<&[u8]>, new: &[u8]) -> BinaryDiff {
+    let old_len = old.map_or(0, |x| x.len());
+    let first_difference = old.and_then(|old| {
+        old.iter()
+            .zip(new.iter())
+            .position(|(a, b)| a != b)
+            .or_else(|| Some(old.len().min(new.len())).filter(|_| old.len() != new.len()))
+    });
+
+    let mut start = 0;
+    let mut summary = None;
+    if old_len.max(new.len()) > HEXDUMP_LIMIT {
+        // show a few lines of context before the first difference
+        if let Some(offset) = first_difference {
+            start = (offset / HEXDUMP_WIDTH).saturating_sub(4) * HEXDUMP_WIDTH;
+        }
+        let end = (start + HEXDUMP_LIMIT).min(old_len.max(new.len()));
+        let sizes = match old {
+            Some(_) if old_len == new.len() => format!("both {} bytes", new.len()),
+            Some(_) => format!("{} bytes -> {} bytes", old_len, new.len()),
+            None => format!("{} bytes", new.len()),
+        };
+        let difference = match (old, first_difference) {
+            (None, _) => String::new(),
+            (Some(_), Some(offset)) => format!(", first difference at offset {offset:#010x}"),
+            (Some(_), None) => ", contents are identical".into(),
+        };
+        summary = Some(format!(
+            "binary snapshot: {sizes}{difference}; showing bytes {start:#x}..{end:#x}"
+        ));
+    }
+
+    BinaryDiff {
+        summary,
+        old: old.map(|old| hexdump(old, start)),
+        new: hexdump(new, start),
+    }
+}
+
+/// Renders up to [`HEXDUMP_LIMIT`] bytes starting at `start` in the format
+/// of `hexdump -C`.
+fn hexdump(data: &[u8], start: usize) -> String {
+    let mut rv = String::new();
+    let end = (start + HEXDUMP_LIMIT).min(data.len());
+    for (idx, line) in data
+        .get(start..end)
+        .unwrap_or_default()
+        .chunks(HEXDUMP_WIDTH)
+        .enumerate()
+    {
+        write!(rv, "{:08x} ", start + idx * HEXDUMP_WIDTH).unwrap();
+        for col in 0..HEXDUMP_WIDTH {
+            if col % 8 == 0 {
+                rv.push(' ');
+            }
+            match line.get(col) {
+                Some(byte) => write!(rv, "{byte:02x} ").unwrap(),
+                None => rv.push_str("   "),
+            }
+        }
+        rv.push_str(" |");
+        rv.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        rv.push_str("|\n");
+    }
+    rv
+}
+
+#[test]
+fn test_hexdump() {
+    assert_eq!(
+        hexdump(b"Hello, World!\n\x00\x01\xffmore", 0),
+        "00000000  48 65 6c 6c 6f 2c 20 57  6f 72 6c 64 21 0a 00 01  |Hello, World!...|\n\
+         00000010  ff 6d 6f 72 65                                    |.more|\n"
+    );
+    assert_eq!(hexdump(b"", 0), "");
+}
+
+#[test]
+fn test_render_diff() {
+    // images are opened in a viewer
+    assert_eq!(render_diff(Some(("bin", b"a")), ("PNG", b"b")), None);
+
+    // text formats are shown as text
+    assert_eq!(
+        render_diff(Some(("json", b"{}")), ("json", b"[]")),
+        Some(BinaryDiff {
+            summary: None,
+            old: Some("{}".into()),
+            new: "[]".into(),
+        })
+    );
+
+    // everything else is rendered as a hexdump
+    let diff = render_diff(Some(("json", b"{}")), ("bin", b"\x00")).unwrap();
+    assert_eq!(diff.old.unwrap(), hexdump(b"{}", 0));
+    assert_eq!(diff.summary, None);
+
+    // large files are cut around the first difference
+    let old = vec![0u8; 10000];
+    let mut new = old.clone();
+    new[5000] = 1;
+    new.push(2);
+    let diff = render_diff(Some(("bin", &old)), ("bin", &new)).unwrap();
+    assert_eq!(
+        diff.summary.as_deref(),
+        Some("binary snapshot: 10000 bytes -> 10001 bytes, first difference at offset 0x00001388; showing bytes 0x1340..0x2340")
+    );
+    assert!(diff.new.starts_with("00001340  00 00"));
+    assert_eq!(diff.new.lines().count(), 256);
+}
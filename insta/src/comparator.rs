@@ -1,8 +1,28 @@
 //! Provides the [`Comparator`] trait, which provides a mechanism for specifying
 //! how [`Snapshot`] data should be compared.
+//!
+//! Besides the [`DefaultComparator`] this module provides comparators which
+//! tolerate differences in numbers ([`FloatToleranceComparator`]) or in the
+//! formatting of JSON and YAML snapshots ([`SemanticComparator`]), and one
+//! which treats the reference as a template ([`TemplateComparator`]).  With
+//! the `image` feature, image snapshots can be compared pixel by pixel with
+//! the `ImageComparator`, and with the `archive` feature zip and tar
+//! snapshots can be compared by their entries with the `ArchiveComparator`.
 
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::content::{self, json, Content};
+#[cfg(feature = "redactions")]
+use crate::redaction::PathItem;
 use crate::snapshot::{Snapshot, SnapshotContents, TextSnapshotKind};
 
+#[cfg(feature = "archive")]
+pub use crate::archive::ArchiveComparator;
+#[cfg(feature = "image")]
+pub use crate::image::ImageComparator;
+
 /// Allows specific behavior to be invoked when [`Snapshot`]s are compared.
 ///
 /// This is intended for when custom `Snapshot` comparison behavior is
@@ -34,6 +54,42 @@ pub trait Comparator: Send + Sync + 'static {
         self.matches(reference, test)
     }
 
+    /// Explains why `reference` and `test` do not match.
+    ///
+    /// Comparators that accept differences in the snapshot contents can use
+    /// this to point out what made the comparison fail, as the diff also
+    /// shows the differences that were accepted. The explanation is shown
+    /// below the diff and in the panic message of a failed assertion. It is
+    /// also stored with the pending snapshot so that `cargo insta review` can
+    /// show it. The default implementation returns `None`.
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let _ = (reference, test);
+        None
+    }
+
+    /// Returns the contents to store when `test` replaces `reference`.
+    ///
+    /// This is called for failed assertions of text snapshots before the new
+    /// snapshot is written. Comparators can use it to carry parts of the
+    /// reference over into the updated snapshot, like the placeholders of a
+    /// [`TemplateComparator`]. The default implementation returns `None`
+    /// which stores `test` unchanged.
+    fn merge(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let _ = (reference, test);
+        None
+    }
+
+    /// Renders a PNG image which shows how `test` differs from `reference`.
+    ///
+    /// This is called for failed assertions of file snapshots when a new
+    /// snapshot is written. The image is stored next to it as
+    /// `<name>.snap.new.diff.png` so that it can be looked at during review.
+    /// The default implementation returns `None`.
+    fn diff_image(&self, reference: &Snapshot, test: &Snapshot) -> Option<Vec<u8>> {
+        let _ = (reference, test);
+        None
+    }
+
     /// Returns a type-erased clone of `self`.
     ///
     /// This is needed so that [`crate::settings::Settings`] (which provides the
@@ -45,7 +101,7 @@ pub trait Comparator: Send + Sync + 'static {
 /// Provides default comparison semantics for [`Snapshot`]s. Binary snapshots
 /// are compared on the basis of their contents (including file extension). Text
 /// snapshots are compared on the basis of their deserialized representation.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct DefaultComparator;
 
 impl Comparator for DefaultComparator {
@@ -79,14 +135,862 @@ impl Comparator for DefaultComparator {
         }
     }
 
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        // a failure with matching contents comes from `INSTA_REQUIRE_FULL_MATCH`
+        if self.matches(reference, test) {
+            Some("the contents match, but the metadata or formatting differs".into())
+        } else {
+            None
+        }
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Compares the metadata of file snapshots for [`Comparator::matches_fully`].
+fn file_metadata_matches(reference: &Snapshot, test: &Snapshot) -> bool {
+    match reference.contents() {
+        SnapshotContents::Text(contents) if contents.kind == TextSnapshotKind::File => {
+            reference.metadata().trim_for_persistence() == test.metadata().trim_for_persistence()
+        }
+        _ => true,
+    }
+}
+
+/// Compares text snapshots while allowing numbers to differ within a
+/// tolerance.
+///
+/// Both snapshots are split into numbers and the text between them. The text
+/// has to match exactly, while two numbers match if they differ by no more
+/// than `abs`, or by no more than `rel` times the larger of the two values.
+/// This is useful for snapshots of floating point results which vary slightly
+/// across platforms.
+///
+/// Numbers that are preceded by a word (like the `8` in `utf8` or the `2` in
+/// `v2`) are treated as text, while units after a number (`1.5ms`) are not
+/// part of it. Binary snapshots are compared like with the
+/// [`DefaultComparator`].
+///
+/// ```
+/// use insta::comparator::FloatToleranceComparator;
+///
+/// let mut settings = insta::Settings::clone_current();
+/// settings.set_comparator(Box::new(FloatToleranceComparator::new(1e-9, 1e-6)));
+/// settings.bind(|| {
+///     insta::assert_snapshot!(format!("{}", 0.1 + 0.2), @"0.3");
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FloatToleranceComparator {
+    /// The absolute difference two numbers may have.
+    pub abs: f64,
+    /// The difference two numbers may have relative to the larger of them.
+    pub rel: f64,
+}
+
+impl FloatToleranceComparator {
+    /// Creates a comparator with the given absolute and relative tolerance.
+    pub fn new(abs: f64, rel: f64) -> FloatToleranceComparator {
+        FloatToleranceComparator { abs, rel }
+    }
+
+    fn number_matches(&self, a: f64, b: f64) -> bool {
+        let difference = (a - b).abs();
+        // the equality check lets infinities of the same sign match
+        a == b || difference <= self.abs || difference <= self.rel * a.abs().max(b.abs())
+    }
+
+    /// Finds the first difference that is not within tolerance.
+    fn first_mismatch(&self, reference: &str, test: &str) -> Option<Mismatch> {
+        let reference_tokens = tokenize_numbers(reference);
+        let test_tokens = tokenize_numbers(test);
+        let (mut reference_offset, mut test_offset) = (0, 0);
+        let mut line = 1;
+        for idx in 0..reference_tokens.len().max(test_tokens.len()) {
+            match (reference_tokens.get(idx), test_tokens.get(idx)) {
+                (Some(NumberToken::Number(a_str, a)), Some(NumberToken::Number(b_str, b))) => {
+                    if !self.number_matches(*a, *b) {
+                        return Some(Mismatch::Number {
+                            line,
+                            reference: a_str.to_string(),
+                            test: b_str.to_string(),
+                            difference: (a - b).abs(),
+                        });
+                    }
+                }
+                (Some(NumberToken::Text(a)), Some(NumberToken::Text(b))) if a == b => {}
+                _ => {
+                    // report the line on which the text starts to deviate
+                    let skipped_lines = reference[reference_offset..]
+                        .chars()
+                        .zip(test[test_offset..].chars())
+                        .take_while(|(a, b)| a == b)
+                        .filter(|(a, _)| *a == '\n')
+                        .count();
+                    return Some(Mismatch::Text {
+                        line: line + skipped_lines,
+                    });
+                }
+            }
+            let reference_token = reference_tokens[idx].as_str();
+            line += reference_token.matches('\n').count();
+            reference_offset += reference_token.len();
+            test_offset += test_tokens[idx].as_str().len();
+        }
+        None
+    }
+}
+
+impl Comparator for FloatToleranceComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        match (reference.contents(), test.contents()) {
+            (SnapshotContents::Text(a), SnapshotContents::Text(b)) => self
+                .first_mismatch(&a.to_string(), &b.to_string())
+                .is_none(),
+            _ => DefaultComparator.matches(reference, test),
+        }
+    }
+
+    fn matches_fully(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        file_metadata_matches(reference, test) && self.matches(reference, test)
+    }
+
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let (a, b) = match (reference.contents(), test.contents()) {
+            (SnapshotContents::Text(a), SnapshotContents::Text(b)) => (a, b),
+            _ => return None,
+        };
+        match self.first_mismatch(&a.to_string(), &b.to_string())? {
+            Mismatch::Number {
+                line,
+                reference,
+                test,
+                difference,
+            } => Some(format!(
+                "number on line {line} is not within tolerance: {reference} != {test} \
+                 (difference {difference:e}, abs tolerance {:e}, rel tolerance {:e})",
+                self.abs, self.rel
+            )),
+            Mismatch::Text { line } => Some(format!(
+                "text on line {line} differs (only numbers are compared with tolerance)"
+            )),
+        }
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
+enum Mismatch {
+    Number {
+        line: usize,
+        reference: String,
+        test: String,
+        difference: f64,
+    },
+    Text {
+        line: usize,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+enum NumberToken<'a> {
+    Number(&'a str, f64),
+    Text(&'a str),
+}
+
+impl<'a> NumberToken<'a> {
+    fn as_str(&self) -> &'a str {
+        match *self {
+            NumberToken::Number(s, _) | NumberToken::Text(s) => s,
+        }
+    }
+}
+
+/// Splits text into numbers and the text between them.
+///
+/// A number is an optionally signed decimal with an optional fraction and
+/// exponent which does not follow a word character.
+fn tokenize_numbers(s: &str) -> Vec<NumberToken<'_>> {
+    let bytes = s.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let digits_from = |mut idx: usize| {
+        while idx < bytes.len() && bytes[idx].is_ascii_digit() {
+            idx += 1;
+        }
+        idx
+    };
+
+    let mut rv = Vec::new();
+    let mut text_start = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let start = idx;
+        let mut end = start;
+        if bytes[end] == b'-' || bytes[end] == b'+' {
+            end += 1;
+        }
+        if start > 0 && (is_word(bytes[start - 1]) || bytes[start - 1] == b'.')
+            || end >= bytes.len()
+            || !bytes[end].is_ascii_digit()
+        {
+            idx += 1;
+            continue;
+        }
+        end = digits_from(end);
+        if end + 1 < bytes.len() && bytes[end] == b'.' && bytes[end + 1].is_ascii_digit() {
+            end = digits_from(end + 1);
+        }
+        if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+            let mut exp = end + 1;
+            if exp < bytes.len() && (bytes[exp] == b'-' || bytes[exp] == b'+') {
+                exp += 1;
+            }
+            if exp < bytes.len() && bytes[exp].is_ascii_digit() {
+                end = digits_from(exp);
+            }
+        }
+        let number = &s[start..end];
+        if let Ok(value) = number.parse::<f64>() {
+            if text_start < start {
+                rv.push(NumberToken::Text(&s[text_start..start]));
+            }
+            rv.push(NumberToken::Number(number, value));
+            text_start = end;
+        }
+        idx = end;
+    }
+    if text_start < s.len() {
+        rv.push(NumberToken::Text(&s[text_start..]));
+    }
+    rv
+}
+
+/// The path to a value, mirroring the one of redactions which are not
+/// available without the `redactions` feature.
+#[cfg(not(feature = "redactions"))]
+#[allow(dead_code)]
+enum PathItem {
+    Content(Content),
+    Index(u64, u64),
+}
+
+/// Compares JSON and YAML snapshots as data rather than as text.
+///
+/// Both snapshots are parsed and compared by their values, so whitespace,
+/// indentation and the order of map keys do not matter. This allows keeping
+/// hand-formatted reference snapshots which do not need to be rewritten when
+/// the formatting of the serializer changes. Snapshots that cannot be parsed
+/// into a map or a sequence are compared as text.
+///
+/// Sequences are ordered by default. Sequences that should be compared as
+/// sets are selected with [`unordered`](Self::unordered).
+///
+#[cfg_attr(feature = "redactions", doc = " ```")]
+#[cfg_attr(not(feature = "redactions"), doc = " ```ignore")]
+/// use insta::comparator::SemanticComparator;
+///
+/// let mut settings = insta::Settings::clone_current();
+/// settings.set_comparator(Box::new(SemanticComparator::new().unordered(".tags")));
+/// settings.bind(|| {
+///     insta::assert_snapshot!(r#"{"tags": ["b", "a"], "id": 1}"#, @r#"
+///     {
+///       "id": 1,
+///       "tags": ["a", "b"]
+///     }
+///     "#);
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SemanticComparator {
+    #[cfg(feature = "redactions")]
+    unordered: Vec<crate::redaction::Selector<'static>>,
+}
+
+impl SemanticComparator {
+    /// Creates a comparator where all sequences are ordered.
+    pub fn new() -> SemanticComparator {
+        SemanticComparator::default()
+    }
+
+    /// Compares the sequences at the given path regardless of their order.
+    ///
+    /// The path is a selector like for redactions, for instance
+    /// `.users[].roles` selects the roles of all users and `.` the root.
+    ///
+    /// This requires the `redactions` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the selector cannot be parsed.
+    #[cfg(feature = "redactions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+    pub fn unordered(self, path: &str) -> SemanticComparator {
+        match self.try_unordered(path) {
+            Some(rv) => rv,
+            None => panic!("invalid selector {path:?}"),
+        }
+    }
+
+    /// Like [`unordered`](Self::unordered) but returns `None` if the path
+    /// cannot be parsed.
+    #[cfg(feature = "redactions")]
+    pub(crate) fn try_unordered(mut self, path: &str) -> Option<SemanticComparator> {
+        let selector = crate::redaction::Selector::parse(path).ok()?;
+        self.unordered.push(selector.make_static());
+        Some(self)
+    }
+
+    /// Parses a snapshot into its canonical form.
+    ///
+    /// Only maps and sequences are compared as data, as any text parses as
+    /// a YAML scalar.
+    fn canonicalize(&self, snapshot: &Snapshot) -> Option<Content> {
+        let contents = snapshot.as_text()?.to_string();
+        let name = snapshot.snapshot_name().unwrap_or("snapshot");
+        let parsed = content::parse_serialized(&contents, Path::new(name)).ok()?;
+        match parsed {
+            Content::Map(_) | Content::Seq(_) => {
+                Some(self.canonicalize_content(&parsed, &mut Vec::new()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks if the sequence at the path is compared regardless of order.
+    #[cfg(feature = "redactions")]
+    fn is_unordered(&self, path: &[PathItem]) -> bool {
+        self.unordered.iter().any(|x| x.is_match(path))
+    }
+
+    #[cfg(not(feature = "redactions"))]
+    fn is_unordered(&self, _path: &[PathItem]) -> bool {
+        false
+    }
+
+    /// Sorts all maps by their keys and unordered sequences by their items.
+    ///
+    /// Integers are normalized as JSON and YAML parsers might pick different
+    /// types for the same number.
+    fn canonicalize_content(&self, value: &Content, path: &mut Vec<PathItem>) -> Content {
+        match value {
+            Content::Map(map) => {
+                let mut map = map
+                    .iter()
+                    .map(|(key, value)| {
+                        path.push(PathItem::Content(key.clone()));
+                        let value = self.canonicalize_content(value, path);
+                        path.pop();
+                        (key.clone(), value)
+                    })
+                    .collect::<Vec<_>>();
+                map.sort_by_cached_key(|(key, _)| json::to_string(key));
+                Content::Map(map)
+            }
+            Content::Seq(items) => {
+                let len = items.len() as u64;
+                let mut seq = items
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, item)| {
+                        path.push(PathItem::Index(idx as u64, len));
+                        let item = self.canonicalize_content(item, path);
+                        path.pop();
+                        item
+                    })
+                    .collect::<Vec<_>>();
+                if self.is_unordered(path) {
+                    seq.sort_by_cached_key(json::to_string);
+                }
+                Content::Seq(seq)
+            }
+            other => match other.as_i64() {
+                Some(value) => Content::I64(value),
+                None => match other.as_u64() {
+                    Some(value) => Content::U64(value),
+                    None => other.clone(),
+                },
+            },
+        }
+    }
+}
+
+impl Comparator for SemanticComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        match (self.canonicalize(reference), self.canonicalize(test)) {
+            (Some(a), Some(b)) => a == b,
+            _ => DefaultComparator.matches(reference, test),
+        }
+    }
+
+    fn matches_fully(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        file_metadata_matches(reference, test) && self.matches(reference, test)
+    }
+
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        if reference.as_text().is_none() || test.as_text().is_none() {
+            return None;
+        }
+        let (a, b) = match (self.canonicalize(reference), self.canonicalize(test)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => {
+                return Some(
+                    "snapshots could not be parsed as JSON or YAML maps or sequences and were \
+                     compared as text"
+                        .into(),
+                )
+            }
+        };
+        let changes = content::diff::diff(&a, &b);
+        let first = changes.first()?;
+        let mut rv = format!("value at {} differs", first.path());
+        if changes.len() > 1 {
+            rv.push_str(&format!(" (and {} more)", changes.len() - 1));
+        }
+        Some(rv)
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
+/// Compares text snapshots against a reference which is a template.
+///
+/// The reference snapshot may contain placeholders which match volatile parts
+/// of the output:
+///
+/// * `[..]` matches any text within a line.
+/// * `...` on a line of its own matches any number of lines.
+/// * `[NAME]` (uppercase letters, digits and underscores) matches any
+///   non-empty text within a line.  All occurrences of the same name have to
+///   match the same text.  For this the lines are aligned first, with `...`
+///   matching as few lines as possible.
+///
+/// Everything else has to match exactly. When a failing snapshot is updated,
+/// the lines of the template that still match the new output are kept as they
+/// are, so placeholders in unchanged regions are preserved when the new
+/// snapshot is accepted.
+///
+/// ```
+/// use insta::comparator::TemplateComparator;
+///
+/// let mut settings = insta::Settings::clone_current();
+/// settings.set_comparator(Box::new(TemplateComparator));
+/// settings.bind(|| {
+///     insta::assert_snapshot!("request 4f2a took 31ms\nrequest 4f2a done", @"
+///     request [ID] took [..]
+///     request [ID] done
+///     ");
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TemplateComparator;
+
+impl Comparator for TemplateComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        match (reference.contents(), test.contents()) {
+            (SnapshotContents::Text(a), SnapshotContents::Text(b)) => {
+                let template = a.to_string();
+                let output = b.to_string();
+                let template = parse_template(&template);
+                let output = output.lines().collect::<Vec<_>>();
+                match_template_lines(&template, &output)
+            }
+            _ => DefaultComparator.matches(reference, test),
+        }
+    }
+
+    fn matches_fully(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        file_metadata_matches(reference, test) && self.matches(reference, test)
+    }
+
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let (template, output) = (
+            reference.as_text()?.to_string(),
+            test.as_text()?.to_string(),
+        );
+        let template_lines = template.lines().collect::<Vec<_>>();
+        let output_lines = output.lines().collect::<Vec<_>>();
+        for part in align_template(&parse_template(&template), &output_lines) {
+            if let TemplateAlignment::Gap {
+                template,
+                output,
+                any_lines: false,
+            } = part
+            {
+                return Some(if !template.is_empty() {
+                    format!(
+                        "line {} of the template does not match the output: {}",
+                        template.start + 1,
+                        template_lines[template.start]
+                    )
+                } else {
+                    format!(
+                        "line {} of the output is not covered by the template: {}",
+                        output.start + 1,
+                        output_lines[output.start]
+                    )
+                });
+            }
+        }
+        if self.matches(reference, test) {
+            None
+        } else {
+            Some("named placeholders do not match the same text everywhere".into())
+        }
+    }
+
+    fn merge(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let (template, output) = (
+            reference.as_text()?.to_string(),
+            test.as_text()?.to_string(),
+        );
+        let template_lines = template.lines().collect::<Vec<_>>();
+        let output_lines = output.lines().collect::<Vec<_>>();
+        let mut rv = Vec::new();
+        for part in align_template(&parse_template(&template), &output_lines) {
+            match part {
+                TemplateAlignment::Match { template, .. } => rv.push(template_lines[template]),
+                // an `...` line keeps absorbing whatever the output has in
+                // its place, while the changed lines around it are taken
+                // from the output
+                TemplateAlignment::Gap {
+                    template,
+                    output,
+                    any_lines: true,
+                } => {
+                    let gap = &template_lines[template];
+                    let output = &output_lines[output];
+                    let first = gap.iter().position(|x| x.trim_end() == "...")?;
+                    let last = gap.iter().rposition(|x| x.trim_end() == "...")?;
+                    let before = first.min(output.len());
+                    let after = (gap.len() - last - 1).min(output.len() - before);
+                    rv.extend(&output[..before]);
+                    rv.push(gap[first]);
+                    rv.extend(&output[output.len() - after..]);
+                }
+                TemplateAlignment::Gap { output, .. } => rv.extend(&output_lines[output]),
+            }
+        }
+        Some(rv.join("\n"))
+    }
+
     fn dyn_clone(&self) -> Box<dyn Comparator> {
         Box::new(self.clone())
     }
 }
 
+#[derive(Debug, PartialEq)]
+enum TemplateLine<'a> {
+    /// An `...` line.
+    AnyLines,
+    Line(Vec<TemplateSegment<'a>>),
+}
+
+#[derive(Debug, PartialEq)]
+enum TemplateSegment<'a> {
+    Literal(&'a str),
+    /// `[..]`
+    Any,
+    /// `[NAME]`
+    Named(&'a str),
+}
+
+/// A part of the alignment of template lines with output lines.
+enum TemplateAlignment {
+    /// A template line which matches an output line.
+    Match { template: usize },
+    /// Template and output lines between two matches.
+    Gap {
+        template: Range<usize>,
+        output: Range<usize>,
+        any_lines: bool,
+    },
+}
+
+fn parse_template(template: &str) -> Vec<TemplateLine<'_>> {
+    template
+        .lines()
+        .map(|line| {
+            if line.trim_end() == "..." {
+                return TemplateLine::AnyLines;
+            }
+            let mut segments = Vec::new();
+            let mut literal_start = 0;
+            let mut idx = 0;
+            while let Some(offset) = line[idx..].find('[') {
+                let start = idx + offset;
+                let rest = &line[start..];
+                let placeholder = if rest.starts_with("[..]") {
+                    Some((TemplateSegment::Any, 4))
+                } else {
+                    rest.find(']')
+                        .map(|end| (&rest[1..end], end))
+                        .filter(|(name, _)| is_placeholder_name(name))
+                        .map(|(name, end)| (TemplateSegment::Named(name), end + 1))
+                };
+                match placeholder {
+                    Some((segment, len)) => {
+                        if literal_start < start {
+                            segments.push(TemplateSegment::Literal(&line[literal_start..start]));
+                        }
+                        segments.push(segment);
+                        idx = start + len;
+                        literal_start = idx;
+                    }
+                    None => idx = start + 1,
+                }
+            }
+            if literal_start < line.len() {
+                segments.push(TemplateSegment::Literal(&line[literal_start..]));
+            }
+            TemplateLine::Line(segments)
+        })
+        .collect()
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+type Captures<'t, 'o> = Vec<(&'t str, &'o str)>;
+
+/// Matches template lines against output lines.
+///
+/// First the lines are aligned, with `...` lines matching as few lines as
+/// possible and every placeholder matching any text.  Failed attempts are
+/// remembered by the position in the template and the output so that each
+/// is only tried once.  Then the named placeholders have to capture the
+/// same text on all the lines they were aligned with.
+fn match_template_lines(template: &[TemplateLine<'_>], output: &[&str]) -> bool {
+    fn align(
+        template: &[TemplateLine<'_>],
+        output: &[&str],
+        (ti, oi): (usize, usize),
+        failed: &mut HashSet<(usize, usize)>,
+        path: &mut Vec<(usize, usize)>,
+    ) -> bool {
+        if failed.contains(&(ti, oi)) {
+            return false;
+        }
+        let rv = match template.get(ti) {
+            None => oi == output.len(),
+            Some(TemplateLine::AnyLines) => {
+                (oi..=output.len()).any(|oi| align(template, output, (ti + 1, oi), failed, path))
+            }
+            Some(TemplateLine::Line(segments)) => {
+                if oi < output.len() && match_segments(segments, output[oi], &mut Vec::new()) {
+                    path.push((ti, oi));
+                    let rv = align(template, output, (ti + 1, oi + 1), failed, path);
+                    if !rv {
+                        path.pop();
+                    }
+                    rv
+                } else {
+                    false
+                }
+            }
+        };
+        if !rv {
+            failed.insert((ti, oi));
+        }
+        rv
+    }
+
+    let mut path = Vec::new();
+    if !align(template, output, (0, 0), &mut HashSet::new(), &mut path) {
+        return false;
+    }
+    let mut captures = Vec::new();
+    path.into_iter().all(|(ti, oi)| match template[ti] {
+        TemplateLine::Line(ref segments) => match_segments(segments, output[oi], &mut captures),
+        TemplateLine::AnyLines => true,
+    })
+}
+
+/// Matches the segments of a template line against a line of output.
+///
+/// Placeholders which are not yet captured match the shortest text that
+/// still lets the rest of the line match and are added to `captures`.  A
+/// name that repeats within the line thus has to match the text of its
+/// shortest possible first occurrence.
+fn match_segments<'t, 'o>(
+    segments: &[TemplateSegment<'t>],
+    text: &'o str,
+    captures: &mut Captures<'t, 'o>,
+) -> bool {
+    let captured = |captures: &Captures<'t, 'o>, name: &str| {
+        captures.iter().find(|(n, _)| *n == name).map(|x| x.1)
+    };
+
+    // possible[k][p] tells if segments[k..] can match text[p..], with
+    // placeholders first seen on this line matching any text
+    let mut possible = vec![vec![false; text.len() + 1]; segments.len() + 1];
+    possible[segments.len()][text.len()] = true;
+    for (k, segment) in segments.iter().enumerate().rev() {
+        let literal = match *segment {
+            TemplateSegment::Literal(literal) => Some(literal),
+            TemplateSegment::Named(name) => captured(captures, name),
+            TemplateSegment::Any => None,
+        };
+        let min_len = usize::from(matches!(segment, TemplateSegment::Named(_)));
+        let mut reachable = false;
+        for p in (0..=text.len()).rev() {
+            if !text.is_char_boundary(p) {
+                continue;
+            }
+            possible[k][p] = match literal {
+                Some(literal) => {
+                    text[p..].starts_with(literal) && possible[k + 1][p + literal.len()]
+                }
+                None => {
+                    // whether the rest matches from any boundary after `p`
+                    // (plus the minimum length)
+                    let rv = reachable || (min_len == 0 && possible[k + 1][p]);
+                    reachable = reachable || possible[k + 1][p];
+                    rv
+                }
+            };
+        }
+    }
+    if !possible[0][0] {
+        return false;
+    }
+
+    let mut p = 0;
+    for (k, segment) in segments.iter().enumerate() {
+        let value = match *segment {
+            TemplateSegment::Literal(literal) => Some(literal),
+            TemplateSegment::Named(name) => captured(captures, name),
+            TemplateSegment::Any => None,
+        };
+        let end = match value {
+            Some(value) if text[p..].starts_with(value) => p + value.len(),
+            Some(_) => return false,
+            None => {
+                let min_len = usize::from(matches!(segment, TemplateSegment::Named(_)));
+                match (p + min_len..=text.len())
+                    .find(|&q| text.is_char_boundary(q) && possible[k + 1][q])
+                {
+                    Some(end) => end,
+                    None => return false,
+                }
+            }
+        };
+        if let TemplateSegment::Named(name) = *segment {
+            if captured(captures, name).is_none() {
+                captures.push((name, &text[p..end]));
+            }
+        }
+        p = end;
+    }
+    p == text.len()
+}
+
+/// Aligns template lines with the output lines they match.
+///
+/// Lines are matched individually by the longest common subsequence of
+/// template and output lines.  The lines between two matches form a gap
+/// which is absorbed if the template has an `...` line in it.  Matching
+/// lines at the start and the end are aligned directly and if what remains
+/// is too large it forms a single gap.
+fn align_template(template: &[TemplateLine<'_>], output: &[&str]) -> Vec<TemplateAlignment> {
+    /// The maximum number of line pairs compared for the alignment.
+    const MAX_PAIRS: usize = 1 << 22;
+
+    let line_matches = |template: &TemplateLine<'_>, line: &str| match template {
+        TemplateLine::AnyLines => false,
+        TemplateLine::Line(segments) => match_segments(segments, line, &mut Vec::new()),
+    };
+
+    let prefix = template
+        .iter()
+        .zip(output)
+        .take_while(|(template, line)| line_matches(template, line))
+        .count();
+    let suffix = template[prefix..]
+        .iter()
+        .rev()
+        .zip(output[prefix..].iter().rev())
+        .take_while(|(template, line)| line_matches(template, line))
+        .count();
+    let (template_end, output_end) = (template.len() - suffix, output.len() - suffix);
+    let (rows, cols) = (template_end - prefix, output_end - prefix);
+
+    // matches[i][j] tells if the i-th template line and the j-th output line
+    // in the middle match, lcs[i][j] is the length of the longest common
+    // subsequence of the middle template and output lines from there on
+    let mut matches = vec![vec![false; cols]; rows];
+    let mut lcs = vec![vec![0usize; cols + 1]; rows + 1];
+    if rows.saturating_mul(cols) <= MAX_PAIRS {
+        for i in (0..rows).rev() {
+            for j in (0..cols).rev() {
+                matches[i][j] = line_matches(&template[prefix + i], output[prefix + j]);
+                lcs[i][j] = if matches[i][j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+    }
+
+    let mut rv = Vec::new();
+    let push_gap = |rv: &mut Vec<TemplateAlignment>,
+                    template_range: Range<usize>,
+                    output_range: Range<usize>| {
+        if !template_range.is_empty() || !output_range.is_empty() {
+            rv.push(TemplateAlignment::Gap {
+                any_lines: template[template_range.clone()].contains(&TemplateLine::AnyLines),
+                template: template_range,
+                output: output_range,
+            });
+        }
+    };
+    rv.extend((0..prefix).map(|template| TemplateAlignment::Match { template }));
+    let (mut i, mut j) = (0, 0);
+    let (mut gap_template, mut gap_output) = (0, 0);
+    while i < rows || j < cols {
+        if i < rows && j < cols && matches[i][j] && lcs[i][j] == lcs[i + 1][j + 1] + 1 {
+            push_gap(
+                &mut rv,
+                prefix + gap_template..prefix + i,
+                prefix + gap_output..prefix + j,
+            );
+            rv.push(TemplateAlignment::Match {
+                template: prefix + i,
+            });
+            i += 1;
+            j += 1;
+            gap_template = i;
+            gap_output = j;
+        } else if j < cols && (i == rows || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            j += 1;
+        } else {
+            i += 1;
+        }
+    }
+    push_gap(
+        &mut rv,
+        prefix + gap_template..prefix + i,
+        prefix + gap_output..prefix + j,
+    );
+    rv.extend((template_end..template.len()).map(|template| TemplateAlignment::Match { template }));
+    rv
+}
+
 #[cfg(test)]
 mod test {
-    use super::DefaultComparator;
+    use super::{
+        tokenize_numbers, DefaultComparator, FloatToleranceComparator, NumberToken,
+        SemanticComparator, TemplateComparator,
+    };
 
     use crate::comparator::Comparator;
     use crate::snapshot::{
@@ -143,5 +1047,266 @@ mod test {
         assert!(comparator.matches_fully(&a, &a));
         // Comparing snapshots with differing metadata fails.
         assert!(!comparator.matches_fully(&a, &b));
+        assert_eq!(
+            comparator.explain(&a, &b).as_deref(),
+            Some("the contents match, but the metadata or formatting differs")
+        );
+    }
+
+    fn text_snapshot(contents: &str) -> Snapshot {
+        Snapshot::from_components(
+            String::from("test"),
+            None,
+            MetaData::default(),
+            SnapshotContents::Text(TextSnapshotContents::new(
+                String::from(contents),
+                TextSnapshotKind::File,
+            )),
+        )
+    }
+
+    #[test]
+    fn tokenize_numbers_skips_words() {
+        assert_eq!(
+            tokenize_numbers("v2 took -1.5e3ms, utf8 x=0.25"),
+            vec![
+                NumberToken::Text("v2 took "),
+                NumberToken::Number("-1.5e3", -1500.0),
+                NumberToken::Text("ms, utf8 x="),
+                NumberToken::Number("0.25", 0.25),
+            ]
+        );
+    }
+
+    #[test]
+    fn float_tolerance_comparator_matches() {
+        let comparator = FloatToleranceComparator::new(1e-6, 0.0);
+        let a = text_snapshot("x: 0.30000000000000004\ny: [1, 2.5]");
+        assert!(comparator.matches(&a, &text_snapshot("x: 0.3\ny: [1.0, 2.5000001]")));
+        assert!(!comparator.matches(&a, &text_snapshot("x: 0.31\ny: [1, 2.5]")));
+        assert!(!comparator.matches(&a, &text_snapshot("x: 0.3\nz: [1, 2.5]")));
+        assert!(!comparator.matches(&a, &text_snapshot("x: 0.3\ny: [1, 2.5, 3]")));
+
+        let comparator = FloatToleranceComparator::new(0.0, 1e-3);
+        let a = text_snapshot("1000000 0.001");
+        assert!(comparator.matches(&a, &text_snapshot("1000500 0.0010005")));
+        assert!(!comparator.matches(&a, &text_snapshot("1000500 0.0011")));
+    }
+
+    #[test]
+    fn float_tolerance_comparator_explain() {
+        let comparator = FloatToleranceComparator::new(0.01, 0.0);
+        let a = text_snapshot("a: 1.0\nb: 2.0\nc: 3.0");
+        assert_eq!(
+            comparator.explain(&a, &text_snapshot("a: 1.001\nb: 2.5\nc: 3.0")),
+            Some(
+                "number on line 2 is not within tolerance: 2.0 != 2.5 \
+                 (difference 5e-1, abs tolerance 1e-2, rel tolerance 0e0)"
+                    .into()
+            )
+        );
+        assert_eq!(
+            comparator.explain(&a, &text_snapshot("a: 1.001\nb: 2.0\nd: 3.0")),
+            Some("text on line 3 differs (only numbers are compared with tolerance)".into())
+        );
+        assert_eq!(
+            comparator.explain(&a, &text_snapshot("a: 1.001\nb: 2.0\nc: 3.0")),
+            None
+        );
+    }
+
+    #[test]
+    fn semantic_comparator_matches() {
+        let comparator = SemanticComparator::new();
+        let a = text_snapshot(r#"{"id": 1, "user": {"name": "a", "tags": ["x", "y"]}}"#);
+        assert!(comparator.matches(
+            &a,
+            &text_snapshot("user:\n  tags: [x, y]\n  name: a\nid: 1\n")
+        ));
+        assert!(comparator.matches(
+            &a,
+            &text_snapshot(
+                "{\n  \"user\": {\n    \"tags\": [\"x\", \"y\"],\n    \"name\": \"a\"\n  },\n  \"id\": 1\n}"
+            )
+        ));
+        assert!(!comparator.matches(
+            &a,
+            &text_snapshot(r#"{"id": 1, "user": {"name": "a", "tags": ["y", "x"]}}"#)
+        ));
+        assert!(!comparator.matches(&a, &text_snapshot(r#"{"id": 2, "user": null}"#)));
+
+        // JSON is parsed as JSON, even where it is not valid YAML
+        let json = r#"{"id": 1, "user": {"name": "\/a\ud83d\ude00", "tags": ["x", "y"]}}"#;
+        let b = text_snapshot(r#"{"id": 1, "user": {"name": "/a😀", "tags": ["x", "y"]}}"#);
+        assert!(crate::content::yaml::parse_str(json, std::path::Path::new("")).is_err());
+        assert!(comparator.matches(&b, &text_snapshot(json)));
+
+        // unparsable snapshots are compared as text
+        assert!(comparator.matches(&text_snapshot("{ a"), &text_snapshot("{ a")));
+        assert!(!comparator.matches(&text_snapshot("{ a"), &text_snapshot("{  a")));
+    }
+
+    #[test]
+    #[cfg(feature = "redactions")]
+    fn semantic_comparator_unordered() {
+        let comparator = SemanticComparator::new()
+            .unordered("[].users[].tags")
+            .unordered(".");
+        let a =
+            text_snapshot(r#"[{"users": [{"tags": [1, 2, 3]}, {"tags": [4, 5]}]}, {"users": []}]"#);
+        let b =
+            text_snapshot(r#"[{"users": []}, {"users": [{"tags": [3, 1, 2]}, {"tags": [5, 4]}]}]"#);
+        assert!(comparator.matches(&a, &b));
+        let c =
+            text_snapshot(r#"[{"users": []}, {"users": [{"tags": [5, 4]}, {"tags": [3, 1, 2]}]}]"#);
+        assert!(!comparator.matches(&a, &c));
+        assert!(SemanticComparator::new()
+            .unordered(".*[]")
+            .unordered(".users")
+            .matches(
+                &text_snapshot("users:\n  - [a, b]\n  - [c]\n"),
+                &text_snapshot("users: [[c], [b, a]]")
+            ));
+    }
+
+    #[test]
+    fn semantic_comparator_explain() {
+        let comparator = SemanticComparator::new();
+        assert_eq!(
+            comparator.explain(
+                &text_snapshot("a: 1\nb: [1, 2]\nc: 3"),
+                &text_snapshot(r#"{"c": 3, "b": [1, 3], "a": 2}"#)
+            ),
+            Some("value at .a differs (and 1 more)".into())
+        );
+        assert_eq!(
+            comparator.explain(&text_snapshot("a: 1"), &text_snapshot("{a: 1}")),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "redactions")]
+    fn semantic_comparator_selectors() {
+        assert!(SemanticComparator::default().try_unordered(".").is_some());
+        assert!(SemanticComparator::default()
+            .try_unordered(r#".users[].*[2]["x.y"]"#)
+            .is_some());
+        assert!(SemanticComparator::default()
+            .try_unordered("users")
+            .is_none());
+        assert!(SemanticComparator::default()
+            .try_unordered(".users[x]")
+            .is_none());
+
+        let comparator = SemanticComparator::default().unordered(".**.tags");
+        assert!(comparator.matches(
+            &text_snapshot("a:\n  b:\n    tags: [1, 2]"),
+            &text_snapshot(r#"{"a": {"b": {"tags": [2, 1]}}}"#)
+        ));
+    }
+
+    #[test]
+    fn semantic_comparator_scalars() {
+        let comparator = SemanticComparator::default();
+        // plain text parses as a YAML string but is still compared as text
+        assert!(!comparator.matches(
+            &text_snapshot("hello\nworld"),
+            &text_snapshot("hello world")
+        ));
+        assert!(comparator
+            .explain(&text_snapshot("a"), &text_snapshot("b"))
+            .is_some());
+        // integers compare equal whatever type the parser picked
+        assert!(comparator.matches(
+            &text_snapshot("[1, -2, 9223372036854775807]"),
+            &text_snapshot("- 1\n- -2\n- 9223372036854775807")
+        ));
+        assert!(!comparator.matches(&text_snapshot("[1]"), &text_snapshot("[1.5]")));
+    }
+
+    #[test]
+    fn template_comparator_matches() {
+        let comparator = TemplateComparator;
+        let template = text_snapshot("started at [..]\n...\nid [ID] is [ID]\n[WARN] done");
+        assert!(comparator.matches(
+            &template,
+            &text_snapshot("started at 12:00\nid 7 is 7\nWARN done")
+        ));
+        assert!(comparator.matches(
+            &template,
+            &text_snapshot("started at \na\nb\nid ab-c is ab-c\nX done")
+        ));
+        assert!(!comparator.matches(
+            &template,
+            &text_snapshot("started at 12:00\nid 7 is 8\nWARN done")
+        ));
+        assert!(!comparator.matches(
+            &template,
+            &text_snapshot("started at 12:00\nid 7 is 7\n done")
+        ));
+        assert!(!comparator.matches(&template, &text_snapshot("started\nid 7 is 7\nWARN done")));
+        // brackets that are not placeholders are literal text
+        assert!(comparator.matches(&text_snapshot("[1, 2] [a]"), &text_snapshot("[1, 2] [a]")));
+        assert!(!comparator.matches(&text_snapshot("[a]"), &text_snapshot("b")));
+    }
+
+    #[test]
+    fn template_comparator_large_mismatch() {
+        // without memoization every `...` and placeholder multiplies the
+        // attempts when the output does not match
+        let comparator = TemplateComparator;
+        let template =
+            text_snapshot("...\n[A] [..] [B]\n...\n[..] [C] [..]\n...\n[A] [B] [C]\n...\nthe end");
+        let output = (0..2000)
+            .map(|idx| format!("line {idx} of {idx} with some words"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let output = text_snapshot(&output);
+        assert!(!comparator.matches(&template, &output));
+        assert!(comparator.explain(&template, &output).is_some());
+        assert!(comparator.merge(&template, &output).is_some());
+    }
+
+    #[test]
+    fn template_comparator_merge() {
+        let comparator = TemplateComparator;
+        let template = text_snapshot("header [..]\n...\nstatus: ok\nid: [ID]\nfooter");
+        assert_eq!(
+            comparator.merge(
+                &template,
+                &text_snapshot("header 2024\nnoise\nmore noise\nstatus: failed\nid: 42\nfooter")
+            ),
+            Some("header [..]\n...\nstatus: failed\nid: [ID]\nfooter".into())
+        );
+        assert_eq!(
+            comparator.merge(
+                &template,
+                &text_snapshot("header 2024\nid: 42\nextra\nfooter")
+            ),
+            Some("header [..]\n...\nid: [ID]\nextra\nfooter".into())
+        );
+    }
+
+    #[test]
+    fn template_comparator_explain() {
+        let comparator = TemplateComparator;
+        let template = text_snapshot("a [..]\nb [..]\nc");
+        assert_eq!(
+            comparator.explain(&template, &text_snapshot("a 1\nB 2\nc")),
+            Some("line 2 of the template does not match the output: b [..]".into())
+        );
+        assert_eq!(
+            comparator.explain(&template, &text_snapshot("a 1\nb 2\nc\nd")),
+            Some("line 4 of the output is not covered by the template: d".into())
+        );
+        assert_eq!(
+            comparator.explain(&text_snapshot("[X]\n[X]"), &text_snapshot("a\nb")),
+            Some("named placeholders do not match the same text everywhere".into())
+        );
+        assert_eq!(
+            comparator.explain(&template, &text_snapshot("a 1\nb 2\nc")),
+            None
+        );
     }
 }
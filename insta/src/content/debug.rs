@@ -0,0 +1,606 @@
+//! Parses the output of `{:#?}` into [`Content`] and prints it back.
+//!
+//! This understands what `#[derive(Debug)]` and the standard library emit:
+//! structs, tuple structs, unit structs and enum variants, sequences, tuples,
+//! maps, sets, strings, chars, numbers and booleans.  Other values like
+//! `1.5s`, `127.0.0.1` or `PhantomData<u32>` are kept as opaque atoms which
+//! print back verbatim.  Type names, field names
+//! and map keys keep their original spelling so that printing the parsed
+//! content reproduces the input.
+//!
+//! Names in [`Content`] have to be `'static`, so the parsed names are kept
+//! elsewhere instead: fields become string keys of a map and the names of
+//! types are stored in a table next to the content.  A named type is a
+//! [`Content::NewtypeVariant`] with `NAMED_MARKER` as name, the index of
+//! its name in the table as variant index and its kind as variant name.  As
+//! newtypes are transparent to selectors, `.field` works as for serialized
+//! structs.
+use std::fmt::Write;
+
+use crate::content::Content;
+
+/// The name of the newtype that distinguishes sets (`{1, 2}`) from
+/// sequences (`[1, 2]`).
+const SET_MARKER: &str = "$insta_debug_set";
+
+/// The name of the newtype that wraps named types.
+const NAMED_MARKER: &str = "$insta_debug_named";
+
+/// The kinds of named types, stored as the variant name.
+const STRUCT: &str = "struct";
+const TUPLE: &str = "tuple";
+const NEWTYPE: &str = "newtype";
+const UNIT: &str = "unit";
+
+/// The name of the newtype that wraps the text of an opaque atom.
+const ATOM_MARKER: &str = "$insta_debug_atom";
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    names: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.s[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses comma separated items up to the closing character.
+    fn parse_items<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Option<T>,
+    ) -> Option<Vec<T>> {
+        let mut rv = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Some(rv);
+            }
+            rv.push(item(self)?);
+            if !self.eat(',') {
+                return self.eat(close).then_some(rv);
+            }
+        }
+    }
+
+    /// Wraps a value of a named type.
+    fn named(&mut self, name: &str, kind: &'static str, inner: Content) -> Content {
+        self.names.push(name.to_string());
+        let idx = (self.names.len() - 1) as u32;
+        Content::NewtypeVariant(NAMED_MARKER, idx, kind, Box::new(inner))
+    }
+
+    fn parse_ident(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(idx, c)| !(c == '_' || c.is_alphanumeric() || (idx == 1 && c == '#')))
+            .map_or(rest.len(), |(idx, _)| idx);
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    fn parse_value(&mut self) -> Option<Content> {
+        self.skip_whitespace();
+        match self.peek()? {
+            '"' => self.parse_string().map(Content::String),
+            '\'' => {
+                let s = self.parse_quoted('\'')?;
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(Content::Char(c)),
+                    _ => None,
+                }
+            }
+            '[' => {
+                self.pos += 1;
+                self.parse_items(']', Self::parse_value).map(Content::Seq)
+            }
+            '(' => {
+                self.pos += 1;
+                let items = self.parse_items(')', Self::parse_value)?;
+                Some(if items.is_empty() {
+                    Content::Unit
+                } else {
+                    Content::Tuple(items)
+                })
+            }
+            '{' => {
+                self.pos += 1;
+                if self.eat('}') {
+                    return Some(Content::Map(Vec::new()));
+                }
+                let first = self.parse_value()?;
+                if self.eat(':') {
+                    let value = self.parse_value()?;
+                    let mut entries = vec![(first, value)];
+                    if self.eat(',') {
+                        entries.extend(self.parse_items('}', |p| {
+                            let key = p.parse_value()?;
+                            p.eat(':').then_some(())?;
+                            Some((key, p.parse_value()?))
+                        })?);
+                    } else if !self.eat('}') {
+                        return None;
+                    }
+                    Some(Content::Map(entries))
+                } else {
+                    let mut items = vec![first];
+                    if self.eat(',') {
+                        items.extend(self.parse_items('}', Self::parse_value)?);
+                    } else if !self.eat('}') {
+                        return None;
+                    }
+                    Some(Content::NewtypeStruct(
+                        SET_MARKER,
+                        Box::new(Content::Seq(items)),
+                    ))
+                }
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                match self.parse_number() {
+                    Some(number) if self.at_boundary() => Some(number),
+                    _ => {
+                        self.pos = start;
+                        self.parse_atom()
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                let name = match self.parse_ident() {
+                    Some(name) if self.at_boundary() || self.rest().starts_with('(') => name,
+                    _ => {
+                        self.pos = start;
+                        return self.parse_atom();
+                    }
+                };
+                if self.peek() == Some('(') {
+                    self.pos += 1;
+                    let mut items = self.parse_items(')', Self::parse_value)?;
+                    return Some(match (name, items.len()) {
+                        ("Some", 1) => Content::Some(Box::new(items.remove(0))),
+                        (name, 1) => self.named(name, NEWTYPE, items.remove(0)),
+                        (name, _) => self.named(name, TUPLE, Content::Tuple(items)),
+                    });
+                }
+                let rest = self.rest();
+                if rest.trim_start().starts_with('{') && rest.starts_with(' ') {
+                    self.eat('{');
+                    let fields = self.parse_items('}', |p| {
+                        let field = p.parse_ident()?;
+                        p.eat(':').then_some(())?;
+                        Some((Content::from(field), p.parse_value()?))
+                    })?;
+                    return Some(self.named(name, STRUCT, Content::Map(fields)));
+                }
+                Some(match name {
+                    "true" => Content::Bool(true),
+                    "false" => Content::Bool(false),
+                    "None" => Content::None,
+                    name => self.named(name, UNIT, Content::Unit),
+                })
+            }
+        }
+    }
+
+    /// Checks if a value may end here.
+    fn at_boundary(&self) -> bool {
+        let rest = self.rest();
+        rest.is_empty()
+            || rest.starts_with(|c: char| c.is_whitespace() || matches!(c, ',' | ')' | ']' | '}'))
+            || rest.starts_with(": ")
+    }
+
+    /// Parses anything up to the end of the value as opaque atom.
+    fn parse_atom(&mut self) -> Option<Content> {
+        let rest = self.rest();
+        let mut depth = 0usize;
+        let mut end = rest.len();
+        for (idx, c) in rest.char_indices() {
+            match c {
+                '<' | '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth == 0 => {
+                    end = idx;
+                    break;
+                }
+                '>' | ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                ',' | '\n' if depth == 0 => {
+                    end = idx;
+                    break;
+                }
+                ':' if depth == 0 && rest[idx..].starts_with(": ") => {
+                    end = idx;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let atom = rest[..end].trim_end();
+        if atom.is_empty() {
+            return None;
+        }
+        self.pos += atom.len();
+        Some(Content::NewtypeStruct(
+            ATOM_MARKER,
+            Box::new(Content::from(atom)),
+        ))
+    }
+
+    fn parse_number(&mut self) -> Option<Content> {
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|&(idx, c)| {
+                !(c.is_ascii_digit()
+                    || c == '.'
+                    || c == 'e'
+                    || (c == '-' && (idx == 0 || rest[..idx].ends_with('e'))))
+            })
+            .map_or(rest.len(), |(idx, _)| idx);
+        let num = &rest[..len];
+        self.pos += len;
+        if num.contains(['.', 'e']) {
+            num.parse().ok().map(Content::F64)
+        } else if let Ok(value) = num.parse::<u64>() {
+            Some(Content::U64(value))
+        } else if let Ok(value) = num.parse::<i64>() {
+            Some(Content::I64(value))
+        } else if let Ok(value) = num.parse::<u128>() {
+            Some(Content::U128(value))
+        } else {
+            num.parse().ok().map(Content::I128)
+        }
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        self.parse_quoted('"')
+    }
+
+    /// Parses a string or char literal with the escapes of `escape_debug`.
+    fn parse_quoted(&mut self, quote: char) -> Option<String> {
+        let mut chars = self.rest().char_indices();
+        chars.next();
+        let mut rv = String::new();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.pos += idx + 1;
+                    return Some(rv);
+                }
+                '\\' => rv.push(match chars.next()?.1 {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    'u' => {
+                        chars.next().filter(|x| x.1 == '{')?;
+                        let mut code = String::new();
+                        loop {
+                            match chars.next()?.1 {
+                                '}' => break,
+                                c => code.push(c),
+                            }
+                        }
+                        char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+                    }
+                    c => c,
+                }),
+                c => rv.push(c),
+            }
+        }
+        None
+    }
+}
+
+/// Parses the output of `{:#?}` into content and the table of type names.
+///
+/// Returns `None` if the output contains something that is not understood.
+pub fn parse_str(s: &str) -> Option<(Content, Vec<String>)> {
+    let mut parser = Parser {
+        s,
+        pos: 0,
+        names: Vec::new(),
+    };
+    let rv = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos == s.len() {
+        Some((rv, parser.names))
+    } else {
+        None
+    }
+}
+
+/// Prints content in the format of `{:#?}`.
+pub fn to_string_pretty(value: &Content, names: &[String]) -> String {
+    let mut rv = String::new();
+    Printer {
+        out: &mut rv,
+        names,
+    }
+    .write_value(value, 0);
+    rv
+}
+
+struct Printer<'a> {
+    out: &'a mut String,
+    names: &'a [String],
+}
+
+impl Printer<'_> {
+    fn write_value(&mut self, value: &Content, indent: usize) {
+        match *value {
+            Content::Bool(b) => write!(self.out, "{b:?}").unwrap(),
+            Content::U8(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::U16(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::U32(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::U64(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::U128(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::I8(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::I16(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::I32(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::I64(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::I128(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::F32(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::F64(n) => write!(self.out, "{n:?}").unwrap(),
+            Content::Char(c) => write!(self.out, "{c:?}").unwrap(),
+            Content::String(ref s) => write!(self.out, "{s:?}").unwrap(),
+            Content::Bytes(ref b) => self.write_items(
+                "[",
+                "]",
+                b.iter().map(|x| Content::U8(*x)).collect::<Vec<_>>().iter(),
+                indent,
+            ),
+            Content::None => self.out.push_str("None"),
+            Content::Some(ref inner) => self.write_items("Some(", ")", Some(&**inner), indent),
+            Content::Unit => self.out.push_str("()"),
+            Content::UnitStruct(name) | Content::UnitVariant(_, _, name) => self.out.push_str(name),
+            Content::NewtypeVariant(NAMED_MARKER, idx, kind, ref inner) => {
+                let name = self.names.get(idx as usize).map_or("", |x| x.as_str());
+                self.write_named(name, kind, inner, indent)
+            }
+            Content::NewtypeStruct(ATOM_MARKER, ref inner) if inner.as_str().is_some() => {
+                self.out.push_str(inner.as_str().unwrap_or_default())
+            }
+            Content::NewtypeStruct(SET_MARKER, ref inner) => match **inner {
+                Content::Seq(ref items) if !items.is_empty() => {
+                    self.write_items("{", "}", items.iter(), indent)
+                }
+                ref other => self.write_value(other, indent),
+            },
+            Content::NewtypeStruct(name, ref inner)
+            | Content::NewtypeVariant(_, _, name, ref inner) => {
+                self.write_named(name, NEWTYPE, inner, indent)
+            }
+            Content::Seq(ref items) => self.write_items("[", "]", items.iter(), indent),
+            Content::Tuple(ref items) => self.write_items("(", ")", items.iter(), indent),
+            Content::TupleStruct(name, ref items)
+            | Content::TupleVariant(_, _, name, ref items) => self.write_tuple(name, items, indent),
+            Content::Map(ref entries) => {
+                if entries.is_empty() {
+                    self.out.push_str("{}");
+                    return;
+                }
+                self.out.push_str("{\n");
+                for (key, value) in entries {
+                    self.push_indent(indent + 1);
+                    self.write_value(key, indent + 1);
+                    self.out.push_str(": ");
+                    self.write_value(value, indent + 1);
+                    self.out.push_str(",\n");
+                }
+                self.push_indent(indent);
+                self.out.push('}');
+            }
+            Content::Struct(name, ref fields) | Content::StructVariant(_, _, name, ref fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, value)| (Content::from(*field), value.clone()))
+                    .collect();
+                self.write_named(name, STRUCT, &Content::Map(fields), indent)
+            }
+        }
+    }
+
+    /// Writes a named type.  Should a redaction have replaced the fields of
+    /// a struct or tuple struct the value is printed like a newtype.
+    fn write_named(&mut self, name: &str, kind: &str, inner: &Content, indent: usize) {
+        match (kind, inner) {
+            (UNIT, _) => self.out.push_str(name),
+            (TUPLE, Content::Tuple(items)) => self.write_tuple(name, items, indent),
+            (STRUCT, Content::Map(fields)) => {
+                self.out.push_str(name);
+                if fields.is_empty() {
+                    return;
+                }
+                self.out.push_str(" {\n");
+                for (field, value) in fields {
+                    self.push_indent(indent + 1);
+                    match field.as_str() {
+                        Some(field) => self.out.push_str(field),
+                        None => self.write_value(field, indent + 1),
+                    }
+                    self.out.push_str(": ");
+                    self.write_value(value, indent + 1);
+                    self.out.push_str(",\n");
+                }
+                self.push_indent(indent);
+                self.out.push('}');
+            }
+            _ => self.write_items(&format!("{name}("), ")", Some(inner), indent),
+        }
+    }
+
+    fn write_tuple(&mut self, name: &str, items: &[Content], indent: usize) {
+        if items.is_empty() {
+            self.out.push_str(name);
+        } else {
+            self.write_items(&format!("{name}("), ")", items.iter(), indent);
+        }
+    }
+
+    fn write_items<'c>(
+        &mut self,
+        open: &str,
+        close: &str,
+        items: impl IntoIterator<Item = &'c Content>,
+        indent: usize,
+    ) {
+        self.out.push_str(open);
+        let mut empty = true;
+        for item in items {
+            if empty {
+                self.out.push('\n');
+                empty = false;
+            }
+            self.push_indent(indent + 1);
+            self.write_value(item, indent + 1);
+            self.out.push_str(",\n");
+        }
+        if !empty {
+            self.push_indent(indent);
+        }
+        self.out.push_str(close);
+    }
+
+    fn push_indent(&mut self, indent: usize) {
+        for _ in 0..indent {
+            self.out.push_str("    ");
+        }
+    }
+}
+
+#[test]
+fn test_debug_roundtrip() {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    enum Kind {
+        Unit,
+        Tuple(u8, i64),
+        Struct { flag: bool },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Id(u32);
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Empty;
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Value {
+        id: Id,
+        name: String,
+        initial: char,
+        ratio: f64,
+        negative: i32,
+        kinds: Vec<Kind>,
+        empty: Empty,
+        nothing: Option<()>,
+        pair: (u8, &'static str),
+        map: BTreeMap<&'static str, Vec<u8>>,
+        set: BTreeSet<u8>,
+        empty_vec: Vec<u8>,
+    }
+
+    let value = Value {
+        id: Id(42),
+        name: "line\n\"quoted\" \\ \u{1b}".into(),
+        initial: '\'',
+        ratio: 0.25,
+        negative: -7,
+        kinds: vec![Kind::Unit, Kind::Tuple(1, -2), Kind::Struct { flag: true }],
+        empty: Empty,
+        nothing: None,
+        pair: (1, "a"),
+        map: vec![("a", vec![1]), ("b", vec![])].into_iter().collect(),
+        set: vec![1, 2].into_iter().collect(),
+        empty_vec: vec![],
+    };
+    let text = format!("{value:#?}");
+    let (content, names) = parse_str(&text).unwrap();
+    assert_eq!(to_string_pretty(&content, &names), text);
+
+    // fields are map keys of the struct
+    match content.resolve_inner() {
+        Content::Map(fields) => {
+            assert_eq!(fields[0].0.as_str(), Some("id"));
+            assert_eq!(fields[0].1.as_u64(), Some(42));
+            assert_eq!(fields[1].1.as_str(), Some("line\n\"quoted\" \\ \u{1b}"));
+        }
+        _ => panic!("unexpected content {content:?}"),
+    }
+    match content {
+        Content::NewtypeVariant(NAMED_MARKER, idx, STRUCT, _) => {
+            assert_eq!(names[idx as usize], "Value")
+        }
+        _ => panic!("unexpected content {content:?}"),
+    }
+
+    // values of other shapes are kept as they are
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Opaque {
+        elapsed: std::time::Duration,
+        addr: std::net::IpAddr,
+        low: f64,
+        nan: f64,
+        marker: std::marker::PhantomData<(u8, u16)>,
+        addrs: BTreeMap<std::net::IpAddr, std::time::Duration>,
+    }
+
+    let value = Opaque {
+        elapsed: std::time::Duration::from_millis(1500),
+        addr: "127.0.0.1".parse().unwrap(),
+        low: f64::NEG_INFINITY,
+        nan: f64::NAN,
+        marker: std::marker::PhantomData,
+        addrs: vec![("::1".parse().unwrap(), std::time::Duration::from_micros(5))]
+            .into_iter()
+            .collect(),
+    };
+    let text = format!("{value:#?}");
+    let (content, names) = parse_str(&text).unwrap();
+    assert_eq!(to_string_pretty(&content, &names), text);
+    match content.resolve_inner() {
+        Content::Map(fields) => {
+            assert_eq!(fields[0].1.as_str(), Some("1.5s"));
+            assert_eq!(fields[1].1.as_str(), Some("127.0.0.1"));
+            assert_eq!(fields[2].1.as_str(), Some("-inf"));
+        }
+        _ => panic!("unexpected content {content:?}"),
+    }
+
+    // output that is not understood is rejected
+    assert_eq!(parse_str("Foo {\n    a: 1\n    b: 2,\n}"), None);
+    assert_eq!(parse_str("Foo {\n    a: 1,\n    ..\n}"), None);
+}
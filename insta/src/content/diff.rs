@@ -0,0 +1,244 @@
+//! Structural comparison of two [`Content`] trees.
+//!
+//! Instead of comparing the serialized text line by line this walks both
+//! trees and reports the differences by their path.  Paths are rendered in
+//! the selector syntax used for redactions (`.users[3].email`).
+use std::fmt::Write;
+
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use crate::content::{json, Content};
+
+/// A single difference between two content trees.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A value that only exists in the new tree.
+    Added { path: String, value: Content },
+    /// A value that only exists in the old tree.
+    Removed { path: String, value: Content },
+    /// A value that exists in both trees but differs.
+    Changed {
+        path: String,
+        old: Content,
+        new: Content,
+    },
+}
+
+impl Change {
+    /// Returns the selector path of the change.
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => {
+                if path.is_empty() {
+                    "."
+                } else {
+                    path
+                }
+            }
+        }
+    }
+}
+
+/// Compares two content trees and returns the differences between them.
+///
+/// Maps are compared by key so that reordered keys are not reported.
+/// Sequences are aligned by their elements so that an inserted or removed
+/// item does not cause every following item to be reported as changed.
+pub fn diff(old: &Content, new: &Content) -> Vec<Change> {
+    let mut rv = Vec::new();
+    diff_at(old, new, &mut String::new(), &mut rv);
+    rv
+}
+
+/// Renders a value on a single line for display in a structural diff.
+pub fn render_value(value: &Content) -> String {
+    json::to_string(value)
+}
+
+fn diff_at(old: &Content, new: &Content, path: &mut String, rv: &mut Vec<Change>) {
+    let old = old.resolve_inner();
+    let new = new.resolve_inner();
+
+    if let (Some(old_entries), Some(new_entries)) = (map_entries(old), map_entries(new)) {
+        for (key, old_value) in &old_entries {
+            let len = path.len();
+            push_key(path, key);
+            match new_entries.iter().find(|(k, _)| k == key) {
+                Some((_, new_value)) => diff_at(old_value, new_value, path, rv),
+                None => rv.push(Change::Removed {
+                    path: path.clone(),
+                    value: (*old_value).clone(),
+                }),
+            }
+            path.truncate(len);
+        }
+        for (key, new_value) in &new_entries {
+            if !old_entries.iter().any(|(k, _)| k == key) {
+                let len = path.len();
+                push_key(path, key);
+                rv.push(Change::Added {
+                    path: path.clone(),
+                    value: (*new_value).clone(),
+                });
+                path.truncate(len);
+            }
+        }
+    } else if let (Some(old_items), Some(new_items)) = (old.as_slice(), new.as_slice()) {
+        diff_seq(old_items, new_items, path, rv);
+    } else if old != new {
+        rv.push(Change::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        });
+    }
+}
+
+fn diff_seq(old: &[Content], new: &[Content], path: &mut String, rv: &mut Vec<Change>) {
+    let old_keys = old.iter().map(render_value).collect::<Vec<_>>();
+    let new_keys = new.iter().map(render_value).collect::<Vec<_>>();
+
+    for op in capture_diff_slices(Algorithm::Patience, &old_keys, &new_keys) {
+        let (_, old_range, new_range) = op.as_tag_tuple();
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+        // pair up replaced items so that changes within them are reported
+        // at the nested path
+        let paired = old_range.len().min(new_range.len());
+        for offset in 0..paired {
+            with_index(path, new_range.start + offset, |path| {
+                diff_at(
+                    &old[old_range.start + offset],
+                    &new[new_range.start + offset],
+                    path,
+                    rv,
+                )
+            });
+        }
+        let start = old_range.start + paired;
+        for (idx, value) in old[start..old_range.end].iter().enumerate() {
+            with_index(path, start + idx, |path| {
+                rv.push(Change::Removed {
+                    path: path.clone(),
+                    value: value.clone(),
+                })
+            });
+        }
+        let start = new_range.start + paired;
+        for (idx, value) in new[start..new_range.end].iter().enumerate() {
+            with_index(path, start + idx, |path| {
+                rv.push(Change::Added {
+                    path: path.clone(),
+                    value: value.clone(),
+                })
+            });
+        }
+    }
+}
+
+/// Appends a sequence index to the path for the duration of `f`.
+fn with_index(path: &mut String, idx: usize, mut f: impl FnMut(&mut String)) {
+    let len = path.len();
+    write!(path, "[{idx}]").unwrap();
+    f(path);
+    path.truncate(len);
+}
+
+/// Returns the entries of map like content with the keys as content.
+fn map_entries(content: &Content) -> Option<Vec<(Content, &Content)>> {
+    match content {
+        Content::Map(map) => Some(
+            map.iter()
+                .map(|(k, v)| (k.resolve_inner().clone(), v))
+                .collect(),
+        ),
+        Content::Struct(_, fields) | Content::StructVariant(_, _, _, fields) => {
+            Some(fields.iter().map(|(k, v)| (Content::from(*k), v)).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Appends a map key to a selector path.
+fn push_key(path: &mut String, key: &Content) {
+    match key.as_str() {
+        Some(key) if is_ident(key) => write!(path, ".{key}").unwrap(),
+        Some(key) => write!(path, "[{key:?}]").unwrap(),
+        None => write!(path, "[{}]", render_value(key)).unwrap(),
+    }
+}
+
+fn is_ident(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c == '$' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
+
+#[test]
+fn test_diff_maps() {
+    use crate::content::yaml::parse_str;
+    use std::path::Path;
+
+    let old = parse_str(
+        "users:\n  - name: a\n    email: a@example.com\n  - name: b\n    email: b@example.com\ncount: 2\n",
+        Path::new("old"),
+    )
+    .unwrap();
+    let new = parse_str(
+        "count: 3\nusers:\n  - name: a\n    email: a@example.com\n  - name: b\n    email: c@example.com\n  - name: d\n    email: d@example.com\n\"x-id\": 1\n",
+        Path::new("new"),
+    )
+    .unwrap();
+
+    let changes = diff(&old, &new)
+        .iter()
+        .map(|change| match change {
+            Change::Added { value, .. } => format!("+{}: {}", change.path(), render_value(value)),
+            Change::Removed { value, .. } => {
+                format!("-{}: {}", change.path(), render_value(value))
+            }
+            Change::Changed { old, new, .. } => format!(
+                "~{}: {} -> {}",
+                change.path(),
+                render_value(old),
+                render_value(new)
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        changes,
+        vec![
+            r#"~.users[1].email: "b@example.com" -> "c@example.com""#,
+            r#"+.users[2]: {"name":"d","email":"d@example.com"}"#,
+            "~.count: 2 -> 3",
+            r#"+["x-id"]: 1"#,
+        ]
+    );
+}
+
+#[test]
+fn test_diff_reordered() {
+    let old = Content::Map(vec![
+        (Content::from("a"), Content::from(1)),
+        (Content::from("b"), Content::from(2)),
+    ]);
+    let new = Content::Map(vec![
+        (Content::from("b"), Content::from(2)),
+        (Content::from("a"), Content::from(1)),
+    ]);
+    assert_eq!(diff(&old, &new), vec![]);
+
+    let old = Content::Seq(vec![Content::from(1), Content::from(2)]);
+    let new = Content::Seq(vec![Content::from(0), Content::from(1), Content::from(2)]);
+    assert_eq!(
+        diff(&old, &new),
+        vec![Change::Added {
+            path: "[0]".into(),
+            value: Content::from(0),
+        }]
+    );
+}
@@ -1,6 +1,7 @@
 use std::fmt::{Display, Write};
+use std::path::Path;
 
-use crate::content::Content;
+use crate::content::{Content, Error};
 
 /// The maximum number of characters to print in a single line
 /// when [`to_string_pretty`] is used.
@@ -316,6 +317,206 @@ pub fn to_string_pretty(value: &Content) -> String {
     ser.into_result()
 }
 
+/// Parses a JSON document.
+///
+/// Integers are parsed like by the YAML parser: as `i64` if they fit, and as
+/// `u64` or `f64` otherwise.
+pub fn parse_str(s: &str, filename: &Path) -> Result<Content, Error> {
+    let mut parser = Parser { s, pos: 0 };
+    parser
+        .parse_document()
+        .ok_or_else(|| Error::FailedParsingJson(filename.to_path_buf()))
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse_document(&mut self) -> Option<Content> {
+        let rv = self.parse_value()?;
+        self.skip_whitespace();
+        if self.pos == self.s.len() {
+            Some(rv)
+        } else {
+            None
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str, value: Content) -> Option<Content> {
+        if self.s[self.pos..].starts_with(keyword) {
+            self.pos += keyword.len();
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn parse_value(&mut self) -> Option<Content> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                let mut map = vec![];
+                if self.eat(b'}') {
+                    return Some(Content::Map(map));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.parse_string()?;
+                    if !self.eat(b':') {
+                        return None;
+                    }
+                    map.push((Content::from(key), self.parse_value()?));
+                    if self.eat(b'}') {
+                        return Some(Content::Map(map));
+                    } else if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut seq = vec![];
+                if self.eat(b']') {
+                    return Some(Content::Seq(seq));
+                }
+                loop {
+                    seq.push(self.parse_value()?);
+                    if self.eat(b']') {
+                        return Some(Content::Seq(seq));
+                    } else if !self.eat(b',') {
+                        return None;
+                    }
+                }
+            }
+            b'"' => self.parse_string().map(Content::from),
+            b't' => self.eat_keyword("true", Content::Bool(true)),
+            b'f' => self.eat_keyword("false", Content::Bool(false)),
+            b'n' => self.eat_keyword("null", Content::None),
+            b'-' | b'0'..=b'9' => self.parse_number(),
+            _ => None,
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<Content> {
+        let start = self.pos;
+        let mut is_float = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' | b'-' | b'+' => {}
+                b'.' | b'e' | b'E' => is_float = true,
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        let num = &self.s[start..self.pos];
+        let digits = num.strip_prefix('-').unwrap_or(num);
+        // reject what rust parses but JSON does not allow
+        if !digits.starts_with(|c: char| c.is_ascii_digit())
+            || (digits.len() > 1
+                && digits.starts_with('0')
+                && !digits[1..].starts_with(['.', 'e', 'E']))
+            || digits.contains(".e")
+            || digits.contains(".E")
+            || digits.ends_with('.')
+        {
+            return None;
+        }
+        if !is_float {
+            if let Ok(num) = num.parse::<i64>() {
+                return Some(Content::from(num));
+            } else if let Ok(num) = num.parse::<u64>() {
+                return Some(Content::from(num));
+            }
+        }
+        num.parse::<f64>().ok().map(Content::from)
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        if self.peek() != Some(b'"') {
+            return None;
+        }
+        self.pos += 1;
+        let mut rv = String::new();
+        loop {
+            let rest = &self.s[self.pos..];
+            let end = rest.find(['"', '\\'])?;
+            if rest[..end].chars().any(|c| c < ' ') {
+                return None;
+            }
+            rv.push_str(&rest[..end]);
+            self.pos += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Some(rv);
+            }
+            let c = match self.peek()? {
+                b'"' => '"',
+                b'\\' => '\\',
+                b'/' => '/',
+                b'b' => '\x08',
+                b'f' => '\x0c',
+                b'n' => '\n',
+                b'r' => '\r',
+                b't' => '\t',
+                b'u' => {
+                    let high = self.parse_hex_escape()?;
+                    let code = if (0xd800..0xdc00).contains(&high) {
+                        // a surrogate pair
+                        if !self.s[self.pos + 1..].starts_with("\\u") {
+                            return None;
+                        }
+                        self.pos += 2;
+                        let low = self.parse_hex_escape()?;
+                        if !(0xdc00..0xe000).contains(&low) {
+                            return None;
+                        }
+                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                    } else {
+                        high
+                    };
+                    char::from_u32(code)?
+                }
+                _ => return None,
+            };
+            rv.push(c);
+            self.pos += 1;
+        }
+    }
+
+    /// Parses the four hex digits of a `\u` escape.  Expects the position to
+    /// be on the `u` and leaves it on the last digit.
+    fn parse_hex_escape(&mut self) -> Option<u32> {
+        let hex = self.s.get(self.pos + 1..self.pos + 5)?;
+        if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += 4;
+        u32::from_str_radix(hex, 16).ok()
+    }
+}
+
 #[test]
 fn test_to_string() {
     let json = to_string(&Content::Map(vec![
@@ -520,3 +721,56 @@ fn test_to_string_pretty_complex() {
     }
     "##);
 }
+
+#[test]
+fn test_parse_str() {
+    let parse = |s: &str| parse_str(s, Path::new("<test>")).ok();
+
+    let value = parse("{\"a\":\t[1, -2, 1.5, 18446744073709551615, true, null],\n \"b\": {}}");
+    assert_eq!(
+        value,
+        Some(Content::Map(vec![
+            (
+                Content::from("a"),
+                Content::Seq(vec![
+                    Content::from(1i64),
+                    Content::from(-2i64),
+                    Content::from(1.5),
+                    Content::from(u64::MAX),
+                    Content::from(true),
+                    Content::None,
+                ])
+            ),
+            (Content::from("b"), Content::Map(vec![])),
+        ]))
+    );
+
+    assert_eq!(
+        parse(r#""a\"b\\c\/\n\u00e9\ud83d\ude00""#),
+        Some(Content::from("a\"b\\c/\né😀"))
+    );
+
+    // everything that serializes can be parsed back
+    let value = Content::Map(vec![(
+        Content::from("crazy"),
+        Content::from((0u8..128).map(char::from).collect::<String>()),
+    )]);
+    assert_eq!(parse(&to_string_pretty(&value)), Some(value));
+
+    for invalid in [
+        "",
+        "{",
+        "[1,]",
+        "{\"a\" 1}",
+        "01",
+        "1.",
+        "-",
+        "\"\t\"",
+        "\"\\x\"",
+        "nul",
+        "[] []",
+        "{a: 1}",
+    ] {
+        assert_eq!(parse(invalid), None, "{invalid:?}");
+    }
+}
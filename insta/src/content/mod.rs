@@ -3,10 +3,13 @@
 //!
 //! It's modelled after serde's data format but it's in fact possible to use
 //! this independently of serde.  The `yaml` and `json` support implemented
-//! here works without serde.  Only `yaml` has an implemented parser but since
-//! YAML is a superset of JSON insta instead currently parses JSON via the
-//! YAML implementation.
+//! here works without serde.  Snapshot files are parsed via the YAML
+//! implementation, serialized snapshots can be parsed back with
+//! [`parse_serialized`].
 
+#[cfg(feature = "redactions")]
+pub mod debug;
+pub mod diff;
 pub mod json;
 #[cfg(feature = "serde")]
 mod serialization;
@@ -21,6 +24,7 @@ use std::fmt;
 #[derive(Debug)]
 pub enum Error {
     FailedParsingYaml(std::path::PathBuf),
+    FailedParsingJson(std::path::PathBuf),
     UnexpectedDataType,
     MissingField,
     FileIo(std::io::Error, std::path::PathBuf),
@@ -32,6 +36,9 @@ impl fmt::Display for Error {
             Error::FailedParsingYaml(p) => {
                 f.write_str(format!("Failed parsing the YAML from {:?}", p.display()).as_str())
             }
+            Error::FailedParsingJson(p) => {
+                f.write_str(format!("Failed parsing the JSON from {:?}", p.display()).as_str())
+            }
             Error::UnexpectedDataType => {
                 f.write_str("The present data type wasn't what was expected")
             }
@@ -45,6 +52,20 @@ impl fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// Parses a serialized snapshot back into content.
+///
+/// Snapshots starting with `{` or `[` are parsed as JSON since insta's YAML
+/// output never starts with a flow collection.  Everything else, as well as
+/// hand-written YAML flow collections, is parsed as YAML.
+pub(crate) fn parse_serialized(s: &str, filename: &std::path::Path) -> Result<Content, Error> {
+    if s.trim_start().starts_with(['{', '[']) {
+        if let Ok(rv) = json::parse_str(s, filename) {
+            return Ok(rv);
+        }
+    }
+    yaml::parse_str(s, filename)
+}
+
 /// Represents variable typed content.
 ///
 /// This is used for the serialization system to represent values
@@ -4,9 +4,12 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::{env, fmt, fs};
 
-use crate::utils::is_ci;
+use crate::comparator::{
+    Comparator, DefaultComparator, FloatToleranceComparator, SemanticComparator, TemplateComparator,
+};
+use crate::utils::{is_ci, Glob};
 use crate::{
-    content::{yaml, Content},
+    content::{json, yaml, Content},
     elog,
 };
 
@@ -72,6 +75,23 @@ pub enum OutputBehavior {
     Minimal,
     /// No output at all
     Nothing,
+    /// Diff plus GitHub Actions annotations for failed assertions
+    Github,
+}
+
+/// Controls how snapshot diffs are rendered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DiffStyle {
+    /// Removed and added lines below each other
+    #[default]
+    Unified,
+    /// Old and new snapshot in two columns next to each other
+    SideBySide,
+    /// Changes between serialized snapshots listed by their selector path
+    ///
+    /// This is only available for JSON and YAML snapshots.  Other snapshots,
+    /// including RON, are shown as a unified diff with a notice.
+    Structural,
 }
 
 /// Unreferenced snapshots flag
@@ -102,6 +122,7 @@ pub enum Error {
     Env(&'static str),
     #[allow(unused)]
     Config(&'static str),
+    Comparator(usize, String),
 }
 
 impl fmt::Display for Error {
@@ -110,6 +131,9 @@ impl fmt::Display for Error {
             Error::Deserialize(_) => write!(f, "failed to deserialize tool config"),
             Error::Env(var) => write!(f, "invalid value for env var '{var}'"),
             Error::Config(var) => write!(f, "invalid value for config '{var}'"),
+            Error::Comparator(idx, reason) => {
+                write!(f, "invalid value for config 'comparators[{idx}]': {reason}")
+            }
         }
     }
 }
@@ -129,7 +153,11 @@ pub struct ToolConfig {
     force_pass: bool,
     require_full_match: bool,
     output: OutputBehavior,
+    diff_style: DiffStyle,
     snapshot_update: SnapshotUpdate,
+    comparators: Vec<ComparatorRule>,
+    #[cfg(feature = "redactions")]
+    redaction_presets: Vec<crate::redaction::RedactionPreset>,
     #[cfg(feature = "glob")]
     glob_fail_fast: bool,
     #[cfg(feature = "_cargo_insta_internal")]
@@ -239,9 +267,25 @@ impl ToolConfig {
                     "summary" => OutputBehavior::Summary,
                     "minimal" => OutputBehavior::Minimal,
                     "none" => OutputBehavior::Nothing,
+                    "github" => OutputBehavior::Github,
                     _ => return Err(Error::Env("INSTA_OUTPUT")),
                 }
             },
+            diff_style: {
+                let env_var = env::var("INSTA_DIFF_STYLE");
+                let val = match env_var.as_deref() {
+                    Err(_) | Ok("") => resolve(&cfg, &["behavior", "diff_style"])
+                        .and_then(|x| x.as_str())
+                        .unwrap_or("unified"),
+                    Ok(val) => val,
+                };
+                match val {
+                    "unified" => DiffStyle::Unified,
+                    "side-by-side" => DiffStyle::SideBySide,
+                    "structural" => DiffStyle::Structural,
+                    _ => return Err(Error::Env("INSTA_DIFF_STYLE")),
+                }
+            },
             snapshot_update: {
                 let env_var = env::var("INSTA_UPDATE");
                 let val = match env_var.as_deref() {
@@ -268,6 +312,33 @@ impl ToolConfig {
                     _ => return Err(Error::Env("INSTA_UPDATE")),
                 }
             },
+            comparators: match resolve(&cfg, &["comparators"]) {
+                None => Vec::new(),
+                Some(rules) => rules
+                    .as_slice()
+                    .ok_or(Error::Config("comparators"))?
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, rule)| {
+                        ComparatorRule::from_config(rule)
+                            .map_err(|reason| Error::Comparator(idx, reason))
+                    })
+                    .collect::<Result<_, _>>()?,
+            },
+            #[cfg(feature = "redactions")]
+            redaction_presets: match resolve(&cfg, &["redactions", "presets"]) {
+                None => Vec::new(),
+                Some(presets) => presets
+                    .as_slice()
+                    .ok_or(Error::Config("redactions.presets"))?
+                    .iter()
+                    .map(|name| {
+                        name.as_str()
+                            .and_then(crate::redaction::RedactionPreset::from_name)
+                            .ok_or(Error::Config("redactions.presets"))
+                    })
+                    .collect::<Result<_, _>>()?,
+            },
             #[cfg(feature = "glob")]
             glob_fail_fast: match env::var("INSTA_GLOB_FAIL_FAST").as_deref() {
                 Err(_) | Ok("") => resolve(&cfg, &["behavior", "glob_fail_fast"])
@@ -350,6 +421,11 @@ impl ToolConfig {
         self.output
     }
 
+    /// Returns how diffs should be rendered.
+    pub fn diff_style(&self) -> DiffStyle {
+        self.diff_style
+    }
+
     /// Returns the intended snapshot update behavior.
     pub fn snapshot_update(&self) -> SnapshotUpdate {
         self.snapshot_update
@@ -360,6 +436,23 @@ impl ToolConfig {
     pub fn glob_fail_fast(&self) -> bool {
         self.glob_fail_fast
     }
+
+    /// Returns the redaction presets enabled for all snapshots.
+    #[cfg(feature = "redactions")]
+    pub(crate) fn redaction_presets(&self) -> &[crate::redaction::RedactionPreset] {
+        &self.redaction_presets
+    }
+
+    /// Returns the comparator configured for a snapshot path.
+    ///
+    /// The path is relative to the workspace and the first rule whose glob
+    /// matches wins.
+    pub fn comparator_for(&self, path: &str) -> Option<&dyn Comparator> {
+        self.comparators
+            .iter()
+            .find(|rule| rule.glob.is_match(path))
+            .map(|rule| rule.comparator.as_comparator())
+    }
 }
 
 #[cfg(feature = "_cargo_insta_internal")]
@@ -405,6 +498,140 @@ impl ToolConfig {
     }
 }
 
+/// An entry of `comparators` in the config which selects a built-in
+/// comparator for snapshots matching a glob.
+#[derive(Debug, Clone)]
+struct ComparatorRule {
+    glob: Glob,
+    comparator: ConfiguredComparator,
+}
+
+#[derive(Debug, Clone)]
+enum ConfiguredComparator {
+    Default(DefaultComparator),
+    Float(FloatToleranceComparator),
+    Semantic(SemanticComparator),
+    Template(TemplateComparator),
+    #[cfg(feature = "image")]
+    Image(crate::comparator::ImageComparator),
+    #[cfg(feature = "archive")]
+    Archive(crate::comparator::ArchiveComparator),
+}
+
+impl ConfiguredComparator {
+    fn as_comparator(&self) -> &dyn Comparator {
+        match self {
+            ConfiguredComparator::Default(comparator) => comparator,
+            ConfiguredComparator::Float(comparator) => comparator,
+            ConfiguredComparator::Semantic(comparator) => comparator,
+            ConfiguredComparator::Template(comparator) => comparator,
+            #[cfg(feature = "image")]
+            ConfiguredComparator::Image(comparator) => comparator,
+            #[cfg(feature = "archive")]
+            ConfiguredComparator::Archive(comparator) => comparator,
+        }
+    }
+}
+
+impl ComparatorRule {
+    /// Parses a rule of the `comparators` config.  The error describes what
+    /// is wrong with the rule.
+    fn from_config(rule: &Content) -> Result<ComparatorRule, String> {
+        let glob = resolve(rule, &["glob"])
+            .and_then(|x| x.as_str())
+            .ok_or("'glob' is missing or not a string")?;
+        let kind = resolve(rule, &["kind"])
+            .and_then(|x| x.as_str())
+            .ok_or("'kind' is missing or not a string")?;
+
+        // reject unknown options so that a typo does not silently fall back
+        // to a default such as a tolerance of zero
+        let options: &[&str] = match kind {
+            "float" => &["abs", "rel"],
+            "semantic" | "json-semantic" | "yaml-semantic" => &["unordered"],
+            "image" => &["threshold", "max_diff_ratio"],
+            _ => &[],
+        };
+        match rule.resolve_inner() {
+            Content::Map(fields) => {
+                for (key, _) in fields {
+                    let key = key.as_str().ok_or("keys must be strings")?;
+                    if !["glob", "kind"].contains(&key) && !options.contains(&key) {
+                        return Err(format!("unknown option '{key}' for kind '{kind}'"));
+                    }
+                }
+            }
+            _ => return Err("expected a map".into()),
+        }
+
+        let number = |key: &str| match resolve(rule, &[key]) {
+            None => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .or_else(|| value.as_i64().map(|x| x as f64))
+                .map(Some)
+                .ok_or(format!("'{key}' must be a number")),
+        };
+        let comparator = match kind {
+            "default" => ConfiguredComparator::Default(DefaultComparator),
+            "float" => match (number("abs")?, number("rel")?) {
+                (None, None) => return Err("kind 'float' needs 'abs' or 'rel'".into()),
+                (abs, rel) => ConfiguredComparator::Float(FloatToleranceComparator::new(
+                    abs.unwrap_or(0.0),
+                    rel.unwrap_or(0.0),
+                )),
+            },
+            #[cfg(not(feature = "redactions"))]
+            "semantic" | "json-semantic" | "yaml-semantic"
+                if resolve(rule, &["unordered"]).is_some() =>
+            {
+                return Err("'unordered' requires the redactions feature".into());
+            }
+            "semantic" | "json-semantic" | "yaml-semantic" => {
+                #[allow(unused_mut)]
+                let mut comparator = SemanticComparator::new();
+                #[cfg(feature = "redactions")]
+                for path in resolve(rule, &["unordered"])
+                    .map(|x| x.as_slice().ok_or("'unordered' must be a list"))
+                    .transpose()?
+                    .unwrap_or_default()
+                {
+                    let path = path
+                        .as_str()
+                        .ok_or("'unordered' must be a list of strings")?;
+                    comparator = comparator
+                        .try_unordered(path)
+                        .ok_or(format!("invalid selector '{path}' in 'unordered'"))?;
+                }
+                ConfiguredComparator::Semantic(comparator)
+            }
+            "template" => ConfiguredComparator::Template(TemplateComparator),
+            #[cfg(feature = "image")]
+            "image" => {
+                let threshold = number("threshold")?.unwrap_or(0.0);
+                if !(0.0..=255.0).contains(&threshold) {
+                    return Err("'threshold' must be between 0 and 255".into());
+                }
+                ConfiguredComparator::Image(crate::comparator::ImageComparator::new(
+                    threshold as u8,
+                    number("max_diff_ratio")?.unwrap_or(0.0),
+                ))
+            }
+            #[cfg(feature = "archive")]
+            "archive" => ConfiguredComparator::Archive(crate::comparator::ArchiveComparator),
+            #[cfg(not(feature = "image"))]
+            "image" => return Err("kind 'image' requires the image feature".into()),
+            #[cfg(not(feature = "archive"))]
+            "archive" => return Err("kind 'archive' requires the archive feature".into()),
+            _ => return Err(format!("unknown kind '{kind}'")),
+        };
+        Ok(ComparatorRule {
+            glob: Glob::new(glob, true),
+            comparator,
+        })
+    }
+}
+
 /// How snapshots are supposed to be updated
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SnapshotUpdateBehavior {
@@ -584,6 +811,24 @@ pub fn memoize_warning(message: &str) {
     }
 }
 
+/// Appends the record of an assertion to the assertions file for cargo-insta
+/// to build run reports from.
+/// Best-effort: does nothing if `INSTA_ASSERTIONS_FILE` is not set or IO fails.
+pub fn memoize_assertion(record: &Content) {
+    if let Ok(path) = env::var("INSTA_ASSERTIONS_FILE") {
+        if let Ok(mut f) = fs::OpenOptions::new().append(true).create(true).open(path) {
+            // a single write so that concurrently running tests do not
+            // interleave their records
+            let _ = f.write_all(format!("{}\n", json::to_string(record)).as_bytes());
+        }
+    }
+}
+
+/// Returns `true` if assertions are recorded for a run report.
+pub fn should_memoize_assertions() -> bool {
+    env::var_os("INSTA_ASSERTIONS_FILE").is_some()
+}
+
 /// Returns the pending directory if `INSTA_PENDING_DIR` is set and non-empty.
 pub fn get_pending_dir() -> Option<PathBuf> {
     env::var("INSTA_PENDING_DIR")
@@ -684,3 +929,50 @@ fn resolve<'a>(value: &'a Content, path: &[&str]) -> Option<&'a Content> {
             _ => None,
         })
 }
+
+#[test]
+fn test_comparator_rule_from_config() {
+    let parse = |s: &str| {
+        let rule = yaml::parse_str(s, Path::new("insta.yaml")).unwrap();
+        ComparatorRule::from_config(&rule)
+    };
+
+    let rule = parse("glob: \"**/*__float_*.snap\"\nkind: float\nabs: 0.01\n").unwrap();
+    assert!(rule.glob.is_match("src/snapshots/foo__float_sum.snap"));
+    assert!(parse("glob: \"*.snap\"\nkind: float\nrel: 1\n").is_ok());
+
+    // a misspelled tolerance must not silently become an exact comparison
+    let err = |s: &str| parse(s).err().unwrap();
+    assert_eq!(
+        err("glob: \"*.snap\"\nkind: float\nabsolute: 0.01\n"),
+        "unknown option 'absolute' for kind 'float'"
+    );
+    assert_eq!(
+        err("glob: \"*.snap\"\nkind: float\n"),
+        "kind 'float' needs 'abs' or 'rel'"
+    );
+    assert_eq!(
+        err("glob: \"*.snap\"\nkind: default\nabs: 0.01\n"),
+        "unknown option 'abs' for kind 'default'"
+    );
+    assert_eq!(err("kind: default\n"), "'glob' is missing or not a string");
+    assert_eq!(
+        err("glob: \"*.snap\"\nkind: fuzzy\n"),
+        "unknown kind 'fuzzy'"
+    );
+    #[cfg(not(feature = "image"))]
+    assert_eq!(
+        err("glob: \"*.snap\"\nkind: image\n"),
+        "kind 'image' requires the image feature"
+    );
+    #[cfg(feature = "redactions")]
+    assert_eq!(
+        err("glob: \"*.snap\"\nkind: semantic\nunordered: [users]\n"),
+        "invalid selector 'users' in 'unordered'"
+    );
+
+    assert_eq!(
+        Error::Comparator(1, "unknown kind 'fuzzy'".into()).to_string(),
+        "invalid value for config 'comparators[1]': unknown kind 'fuzzy'"
+    );
+}
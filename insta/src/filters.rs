@@ -1,14 +1,84 @@
 use std::borrow::Cow;
+use std::fmt;
 use std::iter::FromIterator;
 use std::iter::IntoIterator;
+use std::sync::Arc;
 
-use regex::Regex;
+use regex::{Captures, Regex};
+
+/// The replacement of a filter rule.
+#[derive(Clone)]
+enum Replacement {
+    /// A replacement string as accepted by [`Regex::replace`].
+    Text(String),
+    /// A function computing the replacement from the captures.
+    Func(Arc<dyn Fn(&Captures<'_>) -> String + Sync + Send>),
+}
+
+impl fmt::Debug for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Replacement::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            Replacement::Func(_) => f.write_str("Func(..)"),
+        }
+    }
+}
+
+/// A single filter rule.
+#[derive(Debug, Clone)]
+struct Rule {
+    /// If set, the rule is only applied to lines matching this regex.
+    guard: Option<Regex>,
+    regex: Regex,
+    replacement: Replacement,
+}
+
+impl Rule {
+    fn replace<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        match self.replacement {
+            Replacement::Text(ref text) => self.regex.replace_all(s, text.as_str()),
+            Replacement::Func(ref func) => {
+                self.regex.replace_all(s, |caps: &Captures<'_>| func(caps))
+            }
+        }
+    }
+
+    fn apply_to<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        let guard = match self.guard {
+            Some(ref guard) => guard,
+            None => return self.replace(s),
+        };
+
+        let mut rv = String::new();
+        let mut changed = false;
+        for line in s.split_inclusive('\n') {
+            let (line, newline) = match line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (line, ""),
+            };
+            if guard.is_match(line) {
+                let replaced = self.replace(line);
+                changed |= matches!(replaced, Cow::Owned(_));
+                rv.push_str(&replaced);
+            } else {
+                rv.push_str(line);
+            }
+            rv.push_str(newline);
+        }
+
+        if changed {
+            Cow::Owned(rv)
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+}
 
 /// Represents stored filters.
 #[derive(Debug, Default, Clone)]
 #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
 pub struct Filters {
-    rules: Vec<(Regex, String)>,
+    rules: Vec<Rule>,
 }
 
 impl<'a, I> From<I> for Filters
@@ -33,10 +103,30 @@ impl<'a> FromIterator<(&'a str, &'a str)> for Filters {
 impl Filters {
     /// Adds a simple regex with a replacement.
     pub(crate) fn add<S: Into<String>>(&mut self, regex: &str, replacement: S) {
-        self.rules.push((
-            Regex::new(regex).expect("invalid regex for snapshot filter rule"),
-            replacement.into(),
-        ));
+        self.push(None, regex, Replacement::Text(replacement.into()));
+    }
+
+    /// Adds a regex with a function computing the replacement.
+    pub(crate) fn add_fn<F>(&mut self, regex: &str, func: F)
+    where
+        F: Fn(&Captures<'_>) -> String + Sync + Send + 'static,
+    {
+        self.push(None, regex, Replacement::Func(Arc::new(func)));
+    }
+
+    /// Adds a regex with a replacement that only applies to lines matching
+    /// the guard regex.
+    pub(crate) fn add_line<S: Into<String>>(&mut self, guard: &str, regex: &str, replacement: S) {
+        self.push(Some(guard), regex, Replacement::Text(replacement.into()));
+    }
+
+    fn push(&mut self, guard: Option<&str>, regex: &str, replacement: Replacement) {
+        self.rules.push(Rule {
+            guard: guard
+                .map(|guard| Regex::new(guard).expect("invalid regex for snapshot filter guard")),
+            regex: Regex::new(regex).expect("invalid regex for snapshot filter rule"),
+            replacement,
+        });
     }
 
     /// Clears all filters.
@@ -48,8 +138,8 @@ impl Filters {
     pub(crate) fn apply_to<'s>(&self, s: &'s str) -> Cow<'s, str> {
         let mut rv = Cow::Borrowed(s);
 
-        for (regex, replacement) in &self.rules {
-            match regex.replace_all(&rv, replacement) {
+        for rule in &self.rules {
+            match rule.apply_to(&rv) {
                 Cow::Borrowed(_) => continue,
                 Cow::Owned(value) => rv = Cow::Owned(value),
             };
@@ -79,6 +169,34 @@ fn test_filters() {
     );
 }
 
+#[test]
+fn test_filter_fn() {
+    let mut filters = Filters::default();
+    filters.add_fn(r"line (\d+)", |caps| {
+        let line: u32 = caps[1].parse().unwrap();
+        format!("line {}", line - 10)
+    });
+    filters.add_fn(r"[A-Z]:\\[^ ]*", |caps| caps[0].replace('\\', "/"));
+    assert_eq!(
+        filters.apply_to(r"error in C:\src\lib.rs at line 42"),
+        "error in C:/src/lib.rs at line 32"
+    );
+}
+
+#[test]
+fn test_line_filter() {
+    let mut filters = Filters::default();
+    filters.add_line("^Date:", r"\d+", "[N]");
+    assert_eq!(
+        filters.apply_to("Date: 2024-01-02\nCount: 3\nDate: 5"),
+        "Date: [N]-[N]-[N]\nCount: 3\nDate: [N]"
+    );
+
+    // nothing to replace keeps the input borrowed
+    let result = filters.apply_to("Count: 3\nDate: now\n");
+    assert!(matches!(result, Cow::Borrowed(_)));
+}
+
 #[test]
 fn test_static_str_array_conversion() {
     let arr: [(&'static str, &'static str); 2] = [("a1", "b1"), ("a2", "b2")];
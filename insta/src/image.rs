@@ -0,0 +1,496 @@
+//! Pixel based comparison of image snapshots.
+//!
+//! PNG images are decoded with the `png` crate while BMP and PPM images are
+//! simple enough to be decoded here.  All images are converted to 8 bit RGBA
+//! before they are compared.
+use crate::comparator::{Comparator, DefaultComparator};
+use crate::snapshot::{Snapshot, SnapshotContents};
+
+/// Compares image snapshots pixel by pixel.
+///
+/// Binary snapshots with a `png`, `bmp` or `ppm` extension are decoded and
+/// two pixels are considered equal if none of their channels (including
+/// alpha) differ by more than `threshold`. Two images match if they have the
+/// same size and the fraction of differing pixels is at most
+/// `max_diff_ratio`. This is useful for rendered images which vary slightly
+/// between platforms because of anti-aliasing. Snapshots which cannot be
+/// decoded are compared like with the [`DefaultComparator`].
+///
+/// When an assertion fails, an image in which the differing pixels are
+/// highlighted is written next to the pending snapshot as
+/// `<name>.snap.new.diff.png`.
+///
+/// This requires the `image` feature.
+///
+/// ```no_run
+/// use insta::comparator::ImageComparator;
+///
+/// # let rendered_png: Vec<u8> = vec![];
+/// let mut settings = insta::Settings::clone_current();
+/// settings.set_comparator(Box::new(ImageComparator::new(2, 0.001)));
+/// settings.bind(|| {
+///     insta::assert_binary_snapshot!("chart.png", rendered_png);
+/// });
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+#[derive(Clone, Debug, Default)]
+pub struct ImageComparator {
+    /// The difference a channel may have before a pixel counts as differing.
+    pub threshold: u8,
+    /// The fraction of pixels (between `0.0` and `1.0`) that may differ.
+    pub max_diff_ratio: f64,
+}
+
+impl ImageComparator {
+    /// Creates a comparator with the given per-channel threshold and ratio of
+    /// pixels that may differ.
+    pub fn new(threshold: u8, max_diff_ratio: f64) -> ImageComparator {
+        ImageComparator {
+            threshold,
+            max_diff_ratio,
+        }
+    }
+
+    fn pixel_matches(&self, a: [u8; 4], b: [u8; 4]) -> bool {
+        a.iter()
+            .zip(b.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= self.threshold)
+    }
+
+    /// Counts the pixels which differ between two images of the same size.
+    fn differing_pixels(&self, reference: &Image, test: &Image) -> usize {
+        reference
+            .pixels
+            .iter()
+            .zip(test.pixels.iter())
+            .filter(|(a, b)| !self.pixel_matches(**a, **b))
+            .count()
+    }
+
+    fn images_match(&self, reference: &Image, test: &Image) -> bool {
+        if reference.size() != test.size() {
+            return false;
+        }
+        let total = reference.pixels.len();
+        total == 0
+            || self.differing_pixels(reference, test) as f64 / total as f64 <= self.max_diff_ratio
+    }
+
+    /// Renders the differing pixels in red on top of a faded grayscale
+    /// version of the reference.  Pixels that only exist in one of the images
+    /// count as differing.
+    fn render_diff(&self, reference: &Image, test: &Image) -> Image {
+        let width = reference.width.max(test.width);
+        let height = reference.height.max(test.height);
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(match (reference.pixel(x, y), test.pixel(x, y)) {
+                    (Some(a), Some(b)) if self.pixel_matches(a, b) => {
+                        let luma = (a[0] as u32 * 299 + a[1] as u32 * 587 + a[2] as u32 * 114)
+                            / 1000
+                            * a[3] as u32
+                            / 255;
+                        let faded = (255 - (255 - luma) / 4) as u8;
+                        [faded, faded, faded, 255]
+                    }
+                    _ => [255, 0, 0, 255],
+                });
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+impl Comparator for ImageComparator {
+    fn matches(&self, reference: &Snapshot, test: &Snapshot) -> bool {
+        match decode_snapshots(reference, test) {
+            Some((a, b)) => self.images_match(&a, &b),
+            None => DefaultComparator.matches(reference, test),
+        }
+    }
+
+    fn explain(&self, reference: &Snapshot, test: &Snapshot) -> Option<String> {
+        let (a, b) = decode_snapshots(reference, test)?;
+        if a.size() != b.size() {
+            return Some(format!(
+                "image size changed from {}x{} to {}x{}",
+                a.width, a.height, b.width, b.height
+            ));
+        }
+        let differing = self.differing_pixels(&a, &b);
+        Some(format!(
+            "{differing} of {} pixels ({:.2}%) differ by more than {} per channel \
+             (at most {:.2}% may differ)",
+            a.pixels.len(),
+            differing as f64 * 100.0 / a.pixels.len() as f64,
+            self.threshold,
+            self.max_diff_ratio * 100.0
+        ))
+    }
+
+    fn diff_image(&self, reference: &Snapshot, test: &Snapshot) -> Option<Vec<u8>> {
+        let (a, b) = decode_snapshots(reference, test)?;
+        encode_png(&self.render_diff(&a, &b)).ok()
+    }
+
+    fn dyn_clone(&self) -> Box<dyn Comparator> {
+        Box::new(self.clone())
+    }
+}
+
+/// A decoded image with 8 bit RGBA pixels in row-major order.
+#[derive(Debug, PartialEq)]
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[y as usize * self.width as usize + x as usize])
+        } else {
+            None
+        }
+    }
+}
+
+/// Decodes both snapshots if they are images of the same format.
+fn decode_snapshots(reference: &Snapshot, test: &Snapshot) -> Option<(Image, Image)> {
+    let extension = reference.metadata().binary_extension()?;
+    if test.metadata().binary_extension() != Some(extension) {
+        return None;
+    }
+    let decode = |snapshot: &Snapshot| match snapshot.contents() {
+        SnapshotContents::Binary(Some(data)) => decode_image(extension, data),
+        _ => None,
+    };
+    Some((decode(reference)?, decode(test)?))
+}
+
+/// Decodes an image by the extension of its snapshot.
+fn decode_image(extension: &str, data: &[u8]) -> Option<Image> {
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => decode_png(data),
+        "bmp" => decode_bmp(data),
+        "ppm" => decode_ppm(data),
+        _ => None,
+    }
+}
+
+fn decode_png(data: &[u8]) -> Option<Image> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).ok()?;
+    let channels = info.color_type.samples();
+    // don't trust the header for the allocation below
+    let row_size = (info.width as usize).checked_mul(channels)?;
+    if row_size > info.line_size || info.line_size.checked_mul(info.height as usize)? > buf.len() {
+        return None;
+    }
+    let mut pixels = Vec::with_capacity(info.width as usize * info.height as usize);
+    for line in buf.chunks(info.line_size).take(info.height as usize) {
+        for px in line[..info.width as usize * channels].chunks(channels) {
+            pixels.push(match *px {
+                [l] => [l, l, l, 255],
+                [l, a] => [l, l, l, a],
+                [r, g, b] => [r, g, b, 255],
+                [r, g, b, a] => [r, g, b, a],
+                _ => return None,
+            });
+        }
+    }
+    Some(Image {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+/// Decodes uncompressed 24 and 32 bit BMP images.
+fn decode_bmp(data: &[u8]) -> Option<Image> {
+    let u16_at = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+    };
+    let u32_at = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+    };
+    if data.get(..2)? != b"BM" {
+        return None;
+    }
+    let pixel_offset = u32_at(10)? as usize;
+    let width = u32_at(18)? as i32;
+    let height = u32_at(22)? as i32;
+    let bits = u16_at(28)?;
+    let compression = u32_at(30)?;
+    if width < 0 || !matches!(bits, 24 | 32) {
+        return None;
+    }
+    // 32 bit images may describe their layout with bit masks, otherwise the
+    // pixels are stored as BGR(X)
+    let masks = match compression {
+        0 => [0xff0000, 0xff00, 0xff, 0],
+        3 if bits == 32 => [
+            u32_at(54)?,
+            u32_at(58)?,
+            u32_at(62)?,
+            u32_at(66).unwrap_or(0),
+        ],
+        _ => return None,
+    };
+    let (width, top_down) = (width as u32, height < 0);
+    let height = height.unsigned_abs();
+    let bytes_per_pixel = bits as usize / 8;
+    let stride = (width as usize)
+        .checked_mul(bytes_per_pixel)?
+        .checked_add(3)?
+        / 4
+        * 4;
+    // don't trust the header for the allocation below
+    if stride
+        .checked_mul(height as usize)?
+        .checked_add(pixel_offset)?
+        > data.len()
+    {
+        return None;
+    }
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height as usize {
+        let row = if top_down { y } else { height as usize - 1 - y };
+        let start = pixel_offset + row * stride;
+        let line = data.get(start..start + width as usize * bytes_per_pixel)?;
+        for px in line.chunks(bytes_per_pixel) {
+            let value = px
+                .iter()
+                .rev()
+                .fold(0u32, |acc, byte| acc << 8 | *byte as u32);
+            let channel = |mask: u32| {
+                if mask == 0 {
+                    return None;
+                }
+                let shift = mask.trailing_zeros();
+                let max = (mask >> shift) as u64;
+                Some(((((value & mask) >> shift) as u64) * 255 / max) as u8)
+            };
+            pixels.push([
+                channel(masks[0])?,
+                channel(masks[1])?,
+                channel(masks[2])?,
+                channel(masks[3]).unwrap_or(255),
+            ]);
+        }
+    }
+    Some(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Decodes binary (`P6`) and plain (`P3`) PPM images.
+fn decode_ppm(data: &[u8]) -> Option<Image> {
+    let mut pos = 0;
+    let magic = next_token(data, &mut pos)?;
+    let width: u32 = next_token(data, &mut pos)?.parse().ok()?;
+    let height: u32 = next_token(data, &mut pos)?.parse().ok()?;
+    let max: u32 = next_token(data, &mut pos)?.parse().ok()?;
+    if max == 0 || max > 65535 {
+        return None;
+    }
+    let count = (width as usize)
+        .checked_mul(height as usize)?
+        .checked_mul(3)?;
+    let samples = match magic {
+        "P3" => (0..count)
+            .map(|_| next_token(data, &mut pos)?.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()?,
+        "P6" => {
+            // a single whitespace character separates the header from the pixels
+            let raster = data.get(pos + 1..)?;
+            if max < 256 {
+                raster.get(..count)?.iter().map(|x| *x as u32).collect()
+            } else {
+                raster
+                    .get(..count.checked_mul(2)?)?
+                    .chunks(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]) as u32)
+                    .collect()
+            }
+        }
+        _ => return None,
+    };
+    let scale = |sample: u32| (sample.min(max) * 255 / max) as u8;
+    Some(Image {
+        width,
+        height,
+        pixels: samples
+            .chunks(3)
+            .map(|px| [scale(px[0]), scale(px[1]), scale(px[2]), 255])
+            .collect(),
+    })
+}
+
+/// Reads the next whitespace separated token of a PPM header, skipping
+/// comments.
+fn next_token<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a str> {
+    loop {
+        match data.get(*pos)? {
+            b'#' => {
+                while *data.get(*pos)? != b'\n' {
+                    *pos += 1;
+                }
+            }
+            c if c.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while data.get(*pos).map_or(false, |c| !c.is_ascii_whitespace()) {
+        *pos += 1;
+    }
+    std::str::from_utf8(&data[start..*pos]).ok()
+}
+
+fn encode_png(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
+    let mut rv = Vec::new();
+    let mut encoder = png::Encoder::new(&mut rv, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.pixels.concat())?;
+    writer.finish()?;
+    Ok(rv)
+}
+
+#[cfg(test)]
+fn image_snapshot(extension: &str, data: Vec<u8>) -> Snapshot {
+    use crate::snapshot::{MetaData, SnapshotKind};
+    use std::rc::Rc;
+
+    Snapshot::from_components(
+        "test".into(),
+        None,
+        MetaData {
+            snapshot_kind: SnapshotKind::Binary {
+                extension: extension.into(),
+            },
+            ..MetaData::default()
+        },
+        SnapshotContents::Binary(Some(Rc::new(data))),
+    )
+}
+
+#[cfg(test)]
+fn checkerboard(size: u32, dark: [u8; 4]) -> Image {
+    Image {
+        width: size,
+        height: size,
+        pixels: (0..size * size)
+            .map(|idx| {
+                if (idx % size + idx / size) % 2 == 0 {
+                    dark
+                } else {
+                    [255, 255, 255, 255]
+                }
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn test_decode_images() {
+    let expected = Image {
+        width: 2,
+        height: 2,
+        pixels: vec![
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255, 255, 255, 255],
+        ],
+    };
+
+    let ppm = b"P6\n# two by two\n2 2\n255\n\xff\0\0\0\xff\0\0\0\xff\xff\xff\xff";
+    assert_eq!(decode_image("ppm", ppm).as_ref(), Some(&expected));
+    let ppm = b"P3 2 2 15\n15 0 0  0 15 0\n0 0 15  15 15 15\n";
+    assert_eq!(decode_image("ppm", ppm).unwrap().pixels, expected.pixels);
+
+    // bottom-up rows of BGR pixels, padded to four bytes
+    let mut bmp = b"BM".to_vec();
+    bmp.extend_from_slice(&[0; 8]);
+    bmp.extend_from_slice(&54u32.to_le_bytes());
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&2u32.to_le_bytes());
+    bmp.extend_from_slice(&2u32.to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    bmp.extend_from_slice(&[0; 24]);
+    bmp.extend_from_slice(&[0xff, 0, 0, 0xff, 0xff, 0xff, 0, 0]);
+    bmp.extend_from_slice(&[0, 0, 0xff, 0, 0xff, 0, 0, 0]);
+    assert_eq!(decode_image("bmp", &bmp).unwrap().pixels, expected.pixels);
+    // dimensions that don't fit the data are rejected before allocating
+    let mut huge_bmp = bmp.clone();
+    huge_bmp[18..26].copy_from_slice(&[0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff, 0x7f]);
+    assert_eq!(decode_image("bmp", &huge_bmp), None);
+    assert_eq!(decode_image("ppm", b"P6 4294967295 4294967295 255 "), None);
+
+    let png = encode_png(&expected).unwrap();
+    assert_eq!(decode_image("PNG", &png).unwrap().pixels, expected.pixels);
+
+    assert_eq!(decode_image("png", b"not an image"), None);
+    assert_eq!(decode_image("gif", &png), None);
+}
+
+#[test]
+fn test_image_comparator() {
+    let reference = checkerboard(10, [0, 0, 0, 255]);
+    let mut test = checkerboard(10, [3, 3, 3, 255]);
+    test.pixels[0] = [255, 0, 0, 255];
+    let a = image_snapshot("png", encode_png(&reference).unwrap());
+    let b = image_snapshot("png", encode_png(&test).unwrap());
+
+    // every dark pixel is off by three and one is red
+    assert!(!ImageComparator::new(0, 0.0).matches(&a, &b));
+    assert!(ImageComparator::new(0, 0.5).matches(&a, &b));
+    assert!(!ImageComparator::new(3, 0.0).matches(&a, &b));
+    assert!(ImageComparator::new(3, 0.01).matches(&a, &b));
+    assert_eq!(
+        ImageComparator::new(3, 0.0).explain(&a, &b).as_deref(),
+        Some(
+            "1 of 100 pixels (1.00%) differ by more than 3 per channel (at most 0.00% may differ)"
+        )
+    );
+
+    let diff = decode_png(&ImageComparator::new(3, 0.0).diff_image(&a, &b).unwrap()).unwrap();
+    assert_eq!(diff.pixels[0], [255, 0, 0, 255]);
+    assert_eq!(diff.pixels[1], [255, 255, 255, 255]);
+    assert_eq!(diff.pixels[2], [192, 192, 192, 255]);
+
+    // size changes never match
+    let c = image_snapshot("png", encode_png(&checkerboard(9, [0, 0, 0, 255])).unwrap());
+    assert!(!ImageComparator::new(255, 1.0).matches(&a, &c));
+    assert_eq!(
+        ImageComparator::default().explain(&a, &c).as_deref(),
+        Some("image size changed from 10x10 to 9x9")
+    );
+
+    // other binary snapshots are compared by their bytes
+    let d = image_snapshot("bin", vec![1, 2, 3]);
+    assert!(ImageComparator::default().matches(&d, &d.clone()));
+    assert!(!ImageComparator::default().matches(&d, &image_snapshot("bin", vec![1, 2])));
+    assert_eq!(ImageComparator::default().explain(&d, &d), None);
+}
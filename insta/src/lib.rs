@@ -185,6 +185,10 @@
 //! * `filters`: enables support for filters
 //! * `glob`: enables support for globbing ([`glob!`])
 //! * `colors`: enables color output (enabled by default)
+//! * `image`: enables the [`ImageComparator`](comparator::ImageComparator) for
+//!   PNG, BMP and PPM snapshots
+//! * `archive`: enables the [`ArchiveComparator`](comparator::ArchiveComparator)
+//!   and diffs of the entries of zip and tar snapshots
 //!
 //! For legacy reasons the `json` and `yaml` features are enabled by default in
 //! limited capacity.  You will receive a deprecation warning if you are not
@@ -220,11 +224,13 @@
 //!   # also set by INSTA_FORCE_PASS
 //!   force_pass: true/false
 //!   # also set by INSTA_OUTPUT
-//!   output: "diff" | "summary" | "minimal" | "none"
+//!   output: "diff" | "summary" | "minimal" | "none" | "github"
 //!   # also set by INSTA_UPDATE
 //!   update: "auto" | "new" | "always" | "no" | "unseen" | "force"
 //!   # also set by INSTA_GLOB_FAIL_FAST
 //!   glob_fail_fast: true/false
+//!   # also set by INSTA_DIFF_STYLE
+//!   diff_style: "unified" | "side-by-side" | "structural"
 //!
 //! # these are used by cargo insta test
 //! test:
@@ -250,6 +256,36 @@
 //!   # show a warning if undiscovered (ignored or hidden) snapshots are found.
 //!   # defaults to true but creates a performance hit.
 //!   warn_undiscovered: true / false
+//!
+//! # redactions applied to all serialized snapshots (requires the redactions
+//! # feature)
+//! redactions:
+//!   # built-in presets, see `RedactionPreset`
+//!   presets: ["uuid", "timestamp", "temp-path", "address", "duration"]
+//!
+//! # built-in comparators selected by the path of the snapshot file (or the
+//! # source file for inline snapshots), relative to the workspace.  The first
+//! # matching glob wins and comparators set in code take precedence.
+//! comparators:
+//!   - glob: "tests/snapshots/**/*.json.snap"
+//!     # "default" | "float" | "semantic" (alias "json-semantic",
+//!     # "yaml-semantic") | "template" | "image" (requires the image feature)
+//!     # | "archive" (requires the archive feature)
+//!     kind: json-semantic
+//!     # sequences compared regardless of order, as redaction selectors
+//!     # (semantic only, requires the redactions feature)
+//!     unordered: [".users[].roles"]
+//!   - glob: "**/render__*.snap"
+//!     kind: float
+//!     # absolute and relative tolerance (float only)
+//!     abs: 1e-9
+//!     rel: 0
+//!   - glob: "tests/snapshots/*.png"
+//!     kind: image
+//!     # per-channel threshold and fraction of pixels that may differ
+//!     # (image only)
+//!     threshold: 2
+//!     max_diff_ratio: 0.001
 //! ```
 //!
 //! # External Diff Tools
@@ -298,6 +334,7 @@
 
 #[macro_use]
 mod macros;
+mod binary;
 pub mod comparator;
 mod content;
 mod env;
@@ -319,6 +356,12 @@ mod filters;
 #[cfg(feature = "glob")]
 mod glob;
 
+#[cfg(feature = "image")]
+mod image;
+
+#[cfg(feature = "archive")]
+mod archive;
+
 #[cfg(test)]
 mod test;
 
@@ -354,13 +397,14 @@ pub mod _cargo_insta_support {
     pub use crate::{
         content::Error as ContentError,
         env::{
-            get_pending_dir, Error as ToolConfigError, OutputBehavior, SnapshotUpdate, TestRunner,
-            ToolConfig, UnreferencedSnapshots,
+            get_pending_dir, DiffStyle, Error as ToolConfigError, OutputBehavior, SnapshotUpdate,
+            TestRunner, ToolConfig, UnreferencedSnapshots,
         },
         output::SnapshotPrinter,
         snapshot::PendingInlineSnapshot,
         snapshot::SnapshotContents,
         snapshot::TextSnapshotContents,
+        utils::diff_image_path,
         utils::get_cargo,
         utils::is_ci,
         utils::path_to_storage,
@@ -369,7 +413,10 @@ pub mod _cargo_insta_support {
 
 // useful for redactions
 #[cfg(feature = "redactions")]
-pub use crate::redaction::{dynamic_redaction, rounded_redaction, sorted_redaction};
+pub use crate::redaction::{
+    dynamic_redaction, map_key_redaction, rounded_redaction, sorted_redaction, stable_id_redaction,
+    RedactionPreset,
+};
 
 // these are here to make the macros work
 #[doc(hidden)]
@@ -384,13 +431,17 @@ pub mod _macro_support {
     pub use std::{any, env, format, option_env, path, vec};
 
     #[cfg(feature = "serde")]
-    pub use crate::serialization::{serialize_value, SerializationFormat, SnapshotLocation};
+    pub use crate::serialization::{
+        serialize_value, serialize_value_for_workspace, SerializationFormat, SnapshotLocation,
+    };
 
     #[cfg(feature = "glob")]
     pub use crate::glob::glob_exec;
 
     #[cfg(feature = "redactions")]
     pub use crate::{
-        redaction::Redaction, redaction::Selector, serialization::serialize_value_redacted,
+        redaction::Redaction,
+        redaction::Selector,
+        serialization::{format_debug_redacted, serialize_value_redacted},
     };
 }
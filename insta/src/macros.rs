@@ -247,31 +247,32 @@ macro_rules! _assert_serialized_snapshot {
     // If there are redaction expressions, capture the redactions expressions
     // and pass to `_assert_snapshot_base`
     (format=$format:ident, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
-        let transform = |value| {
-            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, $format)
+        let transform = |value, workspace| {
+            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, $format, workspace)
         };
         $crate::_assert_snapshot_base!(transform=transform, $value $($arg)*);
     }};
     // If there's a name, redaction expressions, and debug_expr, capture and pass all to `_assert_snapshot_base`
     (format=$format:ident, $name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?}, $debug_expr:expr $(,)?) => {{
-        let transform = |value| {
-            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, $format)
+        let transform = |value, workspace| {
+            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, $format, workspace)
         };
         $crate::_assert_snapshot_base!(transform=transform, $name, $value, $debug_expr);
     }};
     // If there's a name and redaction expressions, capture and pass to `_assert_snapshot_base`
     (format=$format:ident, $name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $(,)?) => {{
-        let transform = |value| {
-            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, $format)
+        let transform = |value, workspace| {
+            $crate::_prepare_snapshot_for_redaction!(value, {$($k => $v),*}, $format, workspace)
         };
         $crate::_assert_snapshot_base!(transform=transform, $name, $value);
     }};
     // Capture serialization function and pass to `_assert_snapshot_base`
     //
     (format=$format:ident, $($arg:tt)*) => {{
-        let transform = |value| {$crate::_macro_support::serialize_value(
+        let transform = |value, workspace| {$crate::_macro_support::serialize_value_for_workspace(
             &value,
             $crate::_macro_support::SerializationFormat::$format,
+            workspace,
         )};
         $crate::_assert_snapshot_base!(transform=transform, $($arg)*);
     }};
@@ -281,7 +282,7 @@ macro_rules! _assert_serialized_snapshot {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _prepare_snapshot_for_redaction {
-    ($value:expr, {$($k:expr => $v:expr),*}, $format:ident) => {
+    ($value:expr, {$($k:expr => $v:expr),*}, $format:ident, $workspace:expr) => {
         {
             let vec = $crate::_macro_support::vec![
                 $((
@@ -293,6 +294,7 @@ macro_rules! _prepare_snapshot_for_redaction {
                 &$value,
                 &vec,
                 $crate::_macro_support::SerializationFormat::$format,
+                $workspace,
             )
         }
     }
@@ -302,7 +304,7 @@ macro_rules! _prepare_snapshot_for_redaction {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _prepare_snapshot_for_redaction {
-    ($value:expr, {$($k:expr => $v:expr),*}, $format:ident) => {
+    ($value:expr, {$($k:expr => $v:expr),*}, $format:ident, $workspace:expr) => {
         compile_error!(
             "insta was compiled without redactions support. Enable the `redactions` feature."
         )
@@ -312,14 +314,80 @@ macro_rules! _prepare_snapshot_for_redaction {
 /// Asserts a [`Debug`] snapshot.
 ///
 /// The value needs to implement the [`Debug`] trait.  This is useful for
-/// simple values that do not implement the [`serde::Serialize`] trait, but does not
-/// permit redactions.
+/// simple values that do not implement the [`serde::Serialize`] trait.
 ///
 /// Debug is called with `"{:#?}"`, which means this uses pretty-print.
+///
+/// With the `redactions` feature redactions can be passed as second argument
+/// in the form `{ selector => replacement }`.  The output is then parsed back
+/// into a tree of structs, sequences and maps so that redactions apply to it
+/// like to serialized snapshots, and the ones from the
+/// [`Settings`](crate::Settings) and `insta.yaml` apply as well.  To only
+/// apply those pass `{}`.  Without redactions the output is used as is.
+/// Values that are not understood (like `1.5s` or `127.0.0.1`) are kept as
+/// they are, but output that does not print back the same way (eg: from
+/// some custom [`Debug`] implementations) panics when redacting.
+///
+#[cfg_attr(feature = "redactions", doc = " ```no_run")]
+#[cfg_attr(not(feature = "redactions"), doc = " ```ignore")]
+/// # use insta::*;
+/// #[derive(Debug)]
+/// struct User {
+///     id: u64,
+///     name: &'static str,
+/// }
+///
+/// assert_debug_snapshot!(User { id: 42, name: "john" }, {
+///     ".id" => "[id]",
+/// });
+/// ```
 #[macro_export]
 macro_rules! assert_debug_snapshot {
+    ($value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $($arg:tt)*) => {{
+        let transform = |value, workspace| $crate::_prepare_debug_snapshot!(value, {$($k => $v),*}, workspace);
+        $crate::_assert_snapshot_base!(transform=transform, $value $($arg)*);
+    }};
+    ($name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?}, $debug_expr:expr $(,)?) => {{
+        let transform = |value, workspace| $crate::_prepare_debug_snapshot!(value, {$($k => $v),*}, workspace);
+        $crate::_assert_snapshot_base!(transform=transform, $name, $value, $debug_expr);
+    }};
+    ($name:expr, $value:expr, $(match ..)? {$($k:expr => $v:expr),* $(,)?} $(,)?) => {{
+        let transform = |value, workspace| $crate::_prepare_debug_snapshot!(value, {$($k => $v),*}, workspace);
+        $crate::_assert_snapshot_base!(transform=transform, $name, $value);
+    }};
     ($($arg:tt)*) => {
-        $crate::_assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{:#?}", v), $($arg)*)
+        $crate::_assert_snapshot_base!(transform=|v, _| $crate::_macro_support::format!("{:#?}", v), $($arg)*)
+    };
+}
+
+#[cfg(feature = "redactions")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _prepare_debug_snapshot {
+    ($value:expr, {$($k:expr => $v:expr),*}, $workspace:expr) => {
+        {
+            let vec = $crate::_macro_support::vec![
+                $((
+                    $crate::_macro_support::Selector::parse($k).unwrap(),
+                    $crate::_macro_support::Redaction::from($v)
+                ),)*
+            ];
+            $crate::_macro_support::format_debug_redacted(&$value, &vec, $workspace)
+        }
+    }
+}
+
+#[cfg(not(feature = "redactions"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _prepare_debug_snapshot {
+    ($value:expr, {}, $workspace:expr) => {
+        $crate::_macro_support::format!("{:#?}", $value)
+    };
+    ($value:expr, {$($k:expr => $v:expr),*}, $workspace:expr) => {
+        compile_error!(
+            "insta was compiled without redactions support. Enable the `redactions` feature."
+        )
     };
 }
 
@@ -333,12 +401,12 @@ macro_rules! assert_debug_snapshot {
 #[macro_export]
 macro_rules! assert_compact_debug_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{:?}", v), $($arg)*)
+        $crate::_assert_snapshot_base!(transform=|v, _| $crate::_macro_support::format!("{:?}", v), $($arg)*)
     };
 }
 
 // A helper macro which takes a closure as `transform`, and runs the closure on
-// the value. This allows us to implement other macros with a small wrapper. All
+// the value and the workspace (as its config can affect the serialization). This allows us to implement other macros with a small wrapper. All
 // snapshot macros eventually call this macro.
 //
 // This macro handles optional trailing commas.
@@ -369,14 +437,15 @@ macro_rules! _assert_snapshot_base {
         )
     };
     // The main macro body — every call to this macro should end up here.
-    (transform=$transform:expr, $name:expr, $value:expr, $debug_expr:expr $(,)?) => {
+    (transform=$transform:expr, $name:expr, $value:expr, $debug_expr:expr $(,)?) => {{
+        let workspace = $crate::_get_workspace_root!();
         $crate::_macro_support::assert_snapshot(
             (
                 $name,
                 #[allow(clippy::redundant_closure_call)]
-                $transform(&$value).as_str(),
+                $transform(&$value, workspace.as_path()).as_str(),
             ).into(),
-            $crate::_get_workspace_root!().as_path(),
+            workspace.as_path(),
             $crate::_function_name!(),
             $crate::_macro_support::module_path!(),
             $crate::_macro_support::file!(),
@@ -384,7 +453,7 @@ macro_rules! _assert_snapshot_base {
             $debug_expr,
         )
         .unwrap()
-    };
+    }};
 }
 
 /// (Experimental)
@@ -462,7 +531,7 @@ macro_rules! assert_display_snapshot {
 #[macro_export]
 macro_rules! assert_snapshot {
     ($($arg:tt)*) => {
-        $crate::_assert_snapshot_base!(transform=|v| $crate::_macro_support::format!("{}", v), $($arg)*)
+        $crate::_assert_snapshot_base!(transform=|v, _| $crate::_macro_support::format!("{}", v), $($arg)*)
     };
 }
 
@@ -1,12 +1,16 @@
 use std::borrow::Cow;
+use std::fmt::Write;
 use std::process::Command;
 use std::{env, path::Path, time::Duration};
 
-use similar::{Algorithm, ChangeTag, TextDiff};
+use similar::{Algorithm, ChangeTag, DiffTag, TextDiff};
 
-use crate::content::yaml;
+use crate::binary::{self, BinaryDiff};
+use crate::content::diff::{self, Change};
+use crate::content::{self, yaml, Content};
+use crate::env::DiffStyle;
 use crate::snapshot::{MetaData, Snapshot, SnapshotContents};
-use crate::utils::{format_rust_expression, style, term_width};
+use crate::utils::{diff_image_path, format_rust_expression, style, term_width};
 
 /// Snapshot printer utility.
 pub struct SnapshotPrinter<'a> {
@@ -20,6 +24,8 @@ pub struct SnapshotPrinter<'a> {
     title: Option<&'a str>,
     line: Option<u32>,
     snapshot_file: Option<&'a Path>,
+    width: Option<usize>,
+    diff_style: DiffStyle,
 }
 
 impl<'a> SnapshotPrinter<'a> {
@@ -39,6 +45,8 @@ impl<'a> SnapshotPrinter<'a> {
             title: None,
             line: None,
             snapshot_file: None,
+            width: None,
+            diff_style: DiffStyle::Unified,
         }
     }
 
@@ -67,29 +75,64 @@ impl<'a> SnapshotPrinter<'a> {
         self.snapshot_file = file;
     }
 
+    /// Overrides the width used for separator lines.
+    ///
+    /// By default the width of the terminal is used.
+    pub fn set_width(&mut self, width: Option<usize>) {
+        self.width = width;
+    }
+
+    /// Sets how the changeset is rendered.
+    pub fn set_diff_style(&mut self, diff_style: DiffStyle) {
+        self.diff_style = diff_style;
+    }
+
     pub fn print(&self) {
+        print!("{}", self.render());
+    }
+
+    /// Renders the snapshot into a string instead of printing it.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
         if let Some(title) = self.title {
-            let width = term_width();
-            println!(
+            let width = self.width();
+            writeln!(
+                out,
                 "{title:━^width$}",
                 title = style(format!(" {title} ")).bold(),
                 width = width
-            );
+            )
+            .unwrap();
         }
-        self.print_snapshot_diff();
+        self.write_snapshot_diff(&mut out);
+        out
     }
 
-    fn print_snapshot_diff(&self) {
-        self.print_snapshot_summary();
+    fn width(&self) -> usize {
+        self.width.unwrap_or_else(term_width)
+    }
+
+    fn write_snapshot_diff(&self, out: &mut String) {
+        self.write_snapshot_summary(out);
         if self.show_diff {
-            self.print_changeset();
+            self.write_changeset(out);
         } else {
-            self.print_snapshot();
+            self.write_snapshot(out);
+        }
+        if let Some(explanation) = self.new_snapshot.metadata().explanation() {
+            writeln!(
+                out,
+                "{}: {}",
+                style("Mismatch").yellow().bold(),
+                explanation
+            )
+            .unwrap();
         }
     }
 
-    fn print_snapshot_summary(&self) {
-        print_snapshot_summary(
+    fn write_snapshot_summary(&self, out: &mut String) {
+        write_snapshot_summary(
+            out,
             self.workspace_root,
             self.new_snapshot,
             self.snapshot_file,
@@ -97,31 +140,32 @@ impl<'a> SnapshotPrinter<'a> {
         );
     }
 
-    fn print_info(&self) {
-        print_info(self.new_snapshot.metadata());
+    fn write_info(&self, out: &mut String) {
+        write_info(out, self.new_snapshot.metadata(), self.width());
     }
 
-    fn print_snapshot(&self) {
-        print_line(term_width());
+    fn write_snapshot(&self, out: &mut String) {
+        let width = self.width();
+        write_line(out, width);
 
-        let width = term_width();
         if self.show_info {
-            self.print_info();
+            self.write_info(out);
         }
-        println!("Snapshot Contents:");
+        writeln!(out, "Snapshot Contents:").unwrap();
 
         match self.new_snapshot.contents() {
             SnapshotContents::Text(new_contents) => {
                 let new_contents = new_contents.to_string();
 
-                println!("──────┬{:─^1$}", "", width.saturating_sub(7));
+                writeln!(out, "──────┬{:─^1$}", "", width.saturating_sub(7)).unwrap();
                 for (idx, line) in new_contents.lines().enumerate() {
-                    println!("{:>5} │ {}", style(idx + 1).cyan().dim().bold(), line);
+                    writeln!(out, "{:>5} │ {}", style(idx + 1).cyan().dim().bold(), line).unwrap();
                 }
-                println!("──────┴{:─^1$}", "", width.saturating_sub(7));
+                writeln!(out, "──────┴{:─^1$}", "", width.saturating_sub(7)).unwrap();
             }
             SnapshotContents::Binary(_) => {
-                println!(
+                writeln!(
+                    out,
                     "{}",
                     encode_file_link_escape(
                         &self
@@ -131,22 +175,24 @@ impl<'a> SnapshotPrinter<'a> {
                             )
                             .unwrap()
                     )
-                );
+                )
+                .unwrap();
             }
         }
     }
 
-    fn print_changeset(&self) {
-        let width = term_width();
-        print_line(width);
+    fn write_changeset(&self, out: &mut String) {
+        let width = self.width();
+        write_line(out, width);
 
         if self.show_info {
-            self.print_info();
+            self.write_info(out);
         }
 
         if let Some(old_snapshot) = self.old_snapshot {
             if old_snapshot.contents().is_binary() {
-                println!(
+                writeln!(
+                    out,
                     "{}",
                     style(format_args!(
                         "-{}: {}",
@@ -158,12 +204,14 @@ impl<'a> SnapshotPrinter<'a> {
                         ),
                     ))
                     .red()
-                );
+                )
+                .unwrap();
             }
         }
 
         if self.new_snapshot.contents().is_binary() {
-            println!(
+            writeln!(
+                out,
                 "{}",
                 style(format_args!(
                     "+{}: {}",
@@ -178,13 +226,41 @@ impl<'a> SnapshotPrinter<'a> {
                     ),
                 ))
                 .green()
-            );
+            )
+            .unwrap();
+
+            // comparators can store an image of the differences for review
+            if let Some(diff_image) = self
+                .snapshot_file
+                .map(diff_image_path)
+                .filter(|x| x.is_file())
+            {
+                writeln!(
+                    out,
+                    "{}",
+                    style(format_args!(
+                        "~diff: {}",
+                        encode_file_link_escape(&diff_image)
+                    ))
+                    .yellow()
+                )
+                .unwrap();
+            }
         }
 
+        // binary snapshots are diffed through a textual rendering, which is
+        // a hexdump for formats without a dedicated viewer
+        let binary_diff = self.binary_diff();
+        if let Some(summary) = binary_diff.as_ref().and_then(|x| x.summary.as_ref()) {
+            writeln!(out, "{}", style(summary).dim()).unwrap();
+        }
+        let is_binary_diff = binary_diff.is_some();
+
         if let Some((old, new)) = match (
             self.old_snapshot.as_ref().map(|o| o.contents()),
             self.new_snapshot.contents(),
         ) {
+            _ if is_binary_diff => binary_diff.map(|x| (x.old, Some(x.new))),
             (Some(SnapshotContents::Binary(_)) | None, SnapshotContents::Text(new)) => {
                 Some((None, Some(new.to_string())))
             }
@@ -201,11 +277,14 @@ impl<'a> SnapshotPrinter<'a> {
 
             // Check for external diff tool
             if let Ok(tool) = env::var("INSTA_DIFF_TOOL") {
-                if !tool.is_empty()
-                    && invoke_external_diff_tool(&tool, old_text, new_text, self.snapshot_file)
-                {
-                    println!(); // Add spacing after external tool output
-                    return;
+                if !tool.is_empty() {
+                    if let Some(output) =
+                        run_external_diff_tool(&tool, old_text, new_text, self.snapshot_file)
+                    {
+                        out.push_str(&output);
+                        writeln!(out).unwrap(); // Add spacing after external tool output
+                        return;
+                    }
                 }
             }
 
@@ -215,98 +294,468 @@ impl<'a> SnapshotPrinter<'a> {
                 .timeout(Duration::from_millis(500))
                 .diff_lines(old_text, new_text);
 
-            if old.is_some() {
-                println!(
+            // binary snapshots already printed their file names
+            if old.is_some() && !is_binary_diff {
+                writeln!(
+                    out,
                     "{}",
                     style(format_args!("-{}", self.old_snapshot_hint)).red()
-                );
+                )
+                .unwrap();
             }
 
-            if new.is_some() {
-                println!(
+            if new.is_some() && !is_binary_diff {
+                writeln!(
+                    out,
                     "{}",
                     style(format_args!("+{}", self.new_snapshot_hint)).green()
-                );
+                )
+                .unwrap();
             }
 
-            println!("────────────┬{:─^1$}", "", width.saturating_sub(13));
-
             // This is to make sure that binary and text snapshots are never reported as being
             // equal (that would otherwise happen if the text snapshot is an empty string).
             let mut has_changes = old.is_none() || new.is_none();
 
+            if self.diff_style == DiffStyle::Structural {
+                if let Some(changes) = old
+                    .as_deref()
+                    .zip(new.as_deref())
+                    .and_then(|(old, new)| structural_changes(old, new))
+                {
+                    write_structural(out, &changes, width);
+                    return;
+                }
+                writeln!(out, "{}", style(STRUCTURAL_UNAVAILABLE).dim()).unwrap();
+            }
+
+            if self.diff_style == DiffStyle::SideBySide {
+                write_side_by_side(out, &diff, has_changes, newlines_matter, width);
+                return;
+            }
+
+            writeln!(out, "────────────┬{:─^1$}", "", width.saturating_sub(13)).unwrap();
+
             for (idx, group) in diff.grouped_ops(4).iter().enumerate() {
                 if idx > 0 {
-                    println!("┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}", "", width.saturating_sub(13));
+                    writeln!(out, "┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}", "", width.saturating_sub(13)).unwrap();
                 }
                 for op in group {
                     for change in diff.iter_inline_changes(op) {
                         match change.tag() {
                             ChangeTag::Insert => {
                                 has_changes = true;
-                                print!(
+                                write!(
+                                    out,
                                     "{:>5} {:>5} │{}",
                                     "",
                                     style(change.new_index().unwrap() + 1).cyan().dim().bold(),
                                     style("+").green(),
-                                );
+                                )
+                                .unwrap();
                                 for &(emphasized, change) in change.values() {
                                     let change = render_invisible(change, newlines_matter);
                                     if emphasized {
-                                        print!("{}", style(change).green().underlined());
+                                        write!(out, "{}", style(change).green().underlined())
+                                            .unwrap();
                                     } else {
-                                        print!("{}", style(change).green());
+                                        write!(out, "{}", style(change).green()).unwrap();
                                     }
                                 }
                             }
                             ChangeTag::Delete => {
                                 has_changes = true;
-                                print!(
+                                write!(
+                                    out,
                                     "{:>5} {:>5} │{}",
                                     style(change.old_index().unwrap() + 1).cyan().dim(),
                                     "",
                                     style("-").red(),
-                                );
+                                )
+                                .unwrap();
                                 for &(emphasized, change) in change.values() {
                                     let change = render_invisible(change, newlines_matter);
                                     if emphasized {
-                                        print!("{}", style(change).red().underlined());
+                                        write!(out, "{}", style(change).red().underlined())
+                                            .unwrap();
                                     } else {
-                                        print!("{}", style(change).red());
+                                        write!(out, "{}", style(change).red()).unwrap();
                                     }
                                 }
                             }
                             ChangeTag::Equal => {
-                                print!(
+                                write!(
+                                    out,
                                     "{:>5} {:>5} │ ",
                                     style(change.old_index().unwrap() + 1).cyan().dim(),
                                     style(change.new_index().unwrap() + 1).cyan().dim().bold(),
-                                );
+                                )
+                                .unwrap();
                                 for &(_, change) in change.values() {
                                     let change = render_invisible(change, newlines_matter);
-                                    print!("{}", style(change).dim());
+                                    write!(out, "{}", style(change).dim()).unwrap();
                                 }
                             }
                         }
                         if change.missing_newline() {
-                            println!();
+                            writeln!(out).unwrap();
                         }
                     }
                 }
             }
 
             if !has_changes {
-                println!(
+                writeln!(
+                    out,
                     "{:>5} {:>5} │{}",
                     "",
                     style("-").dim(),
                     style(" snapshots are matching").cyan(),
-                );
+                )
+                .unwrap();
+            }
+
+            writeln!(out, "────────────┴{:─^1$}", "", width.saturating_sub(13)).unwrap();
+        }
+    }
+}
+
+impl SnapshotPrinter<'_> {
+    /// Renders the contents of binary snapshots for a diff.
+    fn binary_diff(&self) -> Option<BinaryDiff> {
+        let new = match self.new_snapshot.contents() {
+            SnapshotContents::Binary(Some(data)) => {
+                (self.new_snapshot.metadata().binary_extension()?, &data[..])
+            }
+            _ => return None,
+        };
+        let old = match self.old_snapshot.map(|x| (x, x.contents())) {
+            Some((old, SnapshotContents::Binary(Some(data)))) => {
+                Some((old.metadata().binary_extension()?, &data[..]))
+            }
+            // an absent data file is diffed like a new snapshot
+            Some((_, SnapshotContents::Binary(None))) | None => None,
+            Some((_, SnapshotContents::Text(_))) => return None,
+        };
+        binary::render_diff(old, new)
+    }
+}
+
+/// Shown instead of the structural diff if the snapshots cannot be parsed.
+const STRUCTURAL_UNAVAILABLE: &str =
+    "structural diff is only available for JSON and YAML snapshots, showing a line diff";
+
+/// Parses two serialized snapshots and returns their structural changes.
+///
+/// Returns `None` unless both sides are YAML or JSON maps or sequences, in
+/// which case the caller falls back to a line based diff.  Other formats
+/// such as RON are not parsed: their struct and enum variant names have no
+/// representation in the parsed tree, so changes to them would go unnoticed.
+fn structural_changes(old: &str, new: &str) -> Option<Vec<Change>> {
+    let parse = |text: &str| {
+        content::parse_serialized(text, Path::new("<snapshot>"))
+            .ok()
+            .filter(|content| matches!(content.resolve_inner(), Content::Map(_) | Content::Seq(_)))
+    };
+    Some(diff::diff(&parse(old)?, &parse(new)?))
+}
+
+/// Renders structural changes as one line per changed path.
+fn write_structural(out: &mut String, changes: &[Change], width: usize) {
+    let max_value = (width / 2).max(20);
+    let value = |content: &Content| {
+        let rendered = diff::render_value(content);
+        if rendered.chars().count() > max_value {
+            let mut rv = rendered.chars().take(max_value - 1).collect::<String>();
+            rv.push('…');
+            rv
+        } else {
+            rendered
+        }
+    };
+
+    write_line(out, width);
+    for change in changes {
+        match change {
+            Change::Added { value: new, .. } => writeln!(
+                out,
+                "{} {}: {}",
+                style("+").green(),
+                style(change.path()).cyan(),
+                style(value(new)).green()
+            ),
+            Change::Removed { value: old, .. } => writeln!(
+                out,
+                "{} {}: {}",
+                style("-").red(),
+                style(change.path()).cyan(),
+                style(value(old)).red()
+            ),
+            Change::Changed { old, new, .. } => writeln!(
+                out,
+                "{} {}: {} -> {}",
+                style("~").yellow(),
+                style(change.path()).cyan(),
+                style(value(old)).red(),
+                style(value(new)).green()
+            ),
+        }
+        .unwrap();
+    }
+    if changes.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            style("  snapshots are structurally matching").cyan()
+        )
+        .unwrap();
+    }
+    write_line(out, width);
+}
+
+/// Renders a changeset as two columns, the old lines on the left and the new
+/// lines on the right.
+fn write_side_by_side(
+    out: &mut String,
+    diff: &TextDiff<'_, '_, '_, str>,
+    mut has_changes: bool,
+    newlines_matter: bool,
+    width: usize,
+) {
+    // every column is a line number, a marker and the text
+    let text_width = ((width.saturating_sub(1)) / 2).saturating_sub(7).max(10);
+    let column_width = text_width + 7;
+    let old_lines = diff.old_slices();
+    let new_lines = diff.new_slices();
+
+    // splits a line into chunks that fit the column
+    let wrap = |line: &str| -> Vec<String> {
+        let line = render_invisible(line, newlines_matter);
+        let chars = line
+            .trim_end_matches(['\r', '\n'])
+            .replace('\t', "    ")
+            .chars()
+            .collect::<Vec<_>>();
+        if chars.is_empty() {
+            return vec![String::new()];
+        }
+        chars
+            .chunks(text_width)
+            .map(|x| x.iter().collect())
+            .collect()
+    };
+
+    writeln!(
+        out,
+        "{:─^2$}┬{:─^3$}",
+        "",
+        "",
+        column_width,
+        width.saturating_sub(column_width + 1)
+    )
+    .unwrap();
+
+    for (idx, group) in diff.grouped_ops(4).iter().enumerate() {
+        if idx > 0 {
+            writeln!(
+                out,
+                "{:┈^2$}┼{:┈^3$}",
+                "",
+                "",
+                column_width,
+                width.saturating_sub(column_width + 1)
+            )
+            .unwrap();
+        }
+        for op in group {
+            let tag = op.tag();
+            if tag != DiffTag::Equal {
+                has_changes = true;
+            }
+            let old_range = op.old_range();
+            let new_range = op.new_range();
+            for row in 0..old_range.len().max(new_range.len()) {
+                let old_idx = Some(old_range.start + row).filter(|x| old_range.contains(x));
+                let new_idx = Some(new_range.start + row).filter(|x| new_range.contains(x));
+                let old_chunks = old_idx.map(|x| wrap(old_lines[x])).unwrap_or_default();
+                let new_chunks = new_idx.map(|x| wrap(new_lines[x])).unwrap_or_default();
+
+                for chunk in 0..old_chunks.len().max(new_chunks.len()) {
+                    // only the first chunk of a line carries the line number
+                    let old_no = old_idx.filter(|_| chunk == 0).map(|x| x + 1);
+                    let new_no = new_idx.filter(|_| chunk == 0).map(|x| x + 1);
+                    let old_text = format!(
+                        "{:<1$}",
+                        old_chunks.get(chunk).map(|x| x.as_str()).unwrap_or(""),
+                        text_width
+                    );
+                    let new_text = new_chunks.get(chunk).map(|x| x.as_str()).unwrap_or("");
+
+                    let (old_text, old_marker) = match (tag, old_idx) {
+                        (DiffTag::Equal, _) => (style(old_text).dim(), style(" ")),
+                        (_, Some(_)) => (style(old_text).red(), style("-").red()),
+                        (_, None) => (style(old_text), style(" ")),
+                    };
+                    let (new_text, new_marker) = match (tag, new_idx) {
+                        (DiffTag::Equal, _) => (style(new_text.to_string()).dim(), style(" ")),
+                        (_, Some(_)) => (style(new_text.to_string()).green(), style("+").green()),
+                        (_, None) => (style(new_text.to_string()), style(" ")),
+                    };
+
+                    writeln!(
+                        out,
+                        "{:>5} {}{}│{:>5} {}{}",
+                        style(old_no.map(|x| x.to_string()).unwrap_or_default())
+                            .cyan()
+                            .dim(),
+                        old_marker,
+                        old_text,
+                        style(new_no.map(|x| x.to_string()).unwrap_or_default())
+                            .cyan()
+                            .dim()
+                            .bold(),
+                        new_marker,
+                        new_text,
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    if !has_changes {
+        writeln!(
+            out,
+            "{:>5}  {}│{}",
+            "",
+            format_args!("{:<1$}", "", text_width),
+            style(" snapshots are matching").cyan(),
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        out,
+        "{:─^2$}┴{:─^3$}",
+        "",
+        "",
+        column_width,
+        width.saturating_sub(column_width + 1)
+    )
+    .unwrap();
+}
+
+/// The maximum number of diff lines included in a compact diff.
+const COMPACT_DIFF_MAX_LINES: usize = 40;
+
+/// Renders an uncolored, abbreviated diff between two snapshots.
+///
+/// This is used where the regular diff does not fit, such as the message of
+/// a CI annotation.
+pub(crate) fn compact_diff(
+    old: Option<&Snapshot>,
+    new: &Snapshot,
+    diff_style: DiffStyle,
+) -> String {
+    let old_text = match old.map(|x| x.contents()) {
+        Some(SnapshotContents::Text(old)) => Some(old.to_string()),
+        Some(SnapshotContents::Binary(_)) => None,
+        None => Some(String::new()),
+    };
+    let new_text = match new.contents() {
+        SnapshotContents::Text(new) => Some(new.to_string()),
+        SnapshotContents::Binary(_) => None,
+    };
+    let (old_text, new_text) = match (old_text, new_text) {
+        (Some(old_text), Some(new_text)) => (old_text, new_text),
+        _ => {
+            return match new.metadata().binary_extension() {
+                Some(ext) => format!("binary snapshot changed (.{ext})"),
+                None => "snapshot changed from binary to text".to_string(),
             }
+        }
+    };
 
-            println!("────────────┴{:─^1$}", "", width.saturating_sub(13));
+    let mut lines = Vec::new();
+    let structural = if diff_style == DiffStyle::Structural && old.is_some() {
+        structural_changes(&old_text, &new_text)
+    } else {
+        None
+    };
+    if let Some(changes) = structural {
+        for change in &changes {
+            lines.push(match change {
+                Change::Added { value, .. } => {
+                    format!("+ {}: {}", change.path(), diff::render_value(value))
+                }
+                Change::Removed { value, .. } => {
+                    format!("- {}: {}", change.path(), diff::render_value(value))
+                }
+                Change::Changed { old, new, .. } => format!(
+                    "~ {}: {} -> {}",
+                    change.path(),
+                    diff::render_value(old),
+                    diff::render_value(new)
+                ),
+            });
+        }
+    } else {
+        if diff_style == DiffStyle::Structural && old.is_some() {
+            lines.push(STRUCTURAL_UNAVAILABLE.to_string());
         }
+        let diff = TextDiff::configure()
+            .algorithm(Algorithm::Patience)
+            .timeout(Duration::from_millis(500))
+            .diff_lines(&old_text, &new_text);
+        let unified = diff
+            .unified_diff()
+            .context_radius(2)
+            .missing_newline_hint(false)
+            .header("old snapshot", "new results")
+            .to_string();
+        lines.extend(unified.lines().map(|x| x.to_string()));
+    }
+
+    if lines.len() > COMPACT_DIFF_MAX_LINES {
+        let omitted = lines.len() - COMPACT_DIFF_MAX_LINES;
+        lines.truncate(COMPACT_DIFF_MAX_LINES);
+        lines.push(format!("... ({omitted} more lines)"));
     }
+    lines.join("\n")
+}
+
+/// Formats a GitHub Actions workflow command that annotates a file with an
+/// error.
+pub(crate) fn github_annotation(
+    file: &Path,
+    line: Option<u32>,
+    title: &str,
+    message: &str,
+) -> String {
+    fn escape_data(s: &str) -> String {
+        s.replace('%', "%25")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    }
+    fn escape_property(s: &str) -> String {
+        escape_data(s).replace(':', "%3A").replace(',', "%2C")
+    }
+
+    let mut rv = format!(
+        "::error file={}",
+        escape_property(&file.to_string_lossy().replace('\\', "/"))
+    );
+    if let Some(line) = line {
+        write!(rv, ",line={line}").unwrap();
+    }
+    write!(
+        rv,
+        ",title={}::{}",
+        escape_property(title),
+        escape_data(message)
+    )
+    .unwrap();
+    rv
 }
 
 /// Prints the summary of a snapshot
@@ -315,6 +764,18 @@ pub fn print_snapshot_summary(
     snapshot: &Snapshot,
     snapshot_file: Option<&Path>,
     line: Option<u32>,
+) {
+    let mut out = String::new();
+    write_snapshot_summary(&mut out, workspace_root, snapshot, snapshot_file, line);
+    print!("{out}");
+}
+
+fn write_snapshot_summary(
+    out: &mut String,
+    workspace_root: &Path,
+    snapshot: &Snapshot,
+    snapshot_file: Option<&Path>,
+    line: Option<u32>,
 ) {
     if let Some(snapshot_file) = snapshot_file {
         let snapshot_file = workspace_root
@@ -323,19 +784,22 @@ pub fn print_snapshot_summary(
             .ok()
             .map(|x| x.to_path_buf())
             .unwrap_or_else(|| snapshot_file.to_path_buf());
-        println!(
+        writeln!(
+            out,
             "Snapshot file: {}",
             style(snapshot_file.display()).cyan().underlined()
-        );
+        )
+        .unwrap();
     }
     if let Some(name) = snapshot.snapshot_name() {
-        println!("Snapshot: {}", style(name).yellow());
+        writeln!(out, "Snapshot: {}", style(name).yellow()).unwrap();
     } else {
-        println!("Snapshot: {}", style("<inline>").dim());
+        writeln!(out, "Snapshot: {}", style("<inline>").dim()).unwrap();
     }
 
     if let Some(ref value) = snapshot.metadata().get_relative_source(workspace_root) {
-        println!(
+        writeln!(
+            out,
             "Source: {}{}",
             style(value.display()).cyan(),
             line.or(
@@ -344,16 +808,17 @@ pub fn print_snapshot_summary(
             )
             .map(|line| format!(":{}", style(line).bold()))
             .unwrap_or_default()
-        );
+        )
+        .unwrap();
     }
 
     if let Some(ref value) = snapshot.metadata().input_file() {
-        println!("Input file: {}", style(value).cyan());
+        writeln!(out, "Input file: {}", style(value).cyan()).unwrap();
     }
 }
 
-fn print_line(width: usize) {
-    println!("{:─^1$}", "", width);
+fn write_line(out: &mut String, width: usize) {
+    writeln!(out, "{:─^1$}", "", width).unwrap();
 }
 
 fn trailing_newline(s: &str) -> &str {
@@ -424,21 +889,25 @@ fn render_invisible(s: &str, newlines_matter: bool) -> Cow<'_, str> {
     }
 }
 
-fn print_info(metadata: &MetaData) {
-    let width = term_width();
+fn write_info(out: &mut String, metadata: &MetaData, width: usize) {
     if let Some(expr) = metadata.expression() {
-        println!("Expression: {}", style(format_rust_expression(expr)));
-        print_line(width);
+        writeln!(out, "Expression: {}", style(format_rust_expression(expr))).unwrap();
+        write_line(out, width);
     }
     if let Some(descr) = metadata.description() {
-        println!("{descr}");
-        print_line(width);
+        writeln!(out, "{descr}").unwrap();
+        write_line(out, width);
     }
     if let Some(info) = metadata.private_info() {
-        let out = yaml::to_string(info);
+        let yaml = yaml::to_string(info);
         // TODO: does the yaml output always start with '---'?
-        println!("{}", out.trim().strip_prefix("---").unwrap().trim_start());
-        print_line(width);
+        writeln!(
+            out,
+            "{}",
+            yaml.trim().strip_prefix("---").unwrap().trim_start()
+        )
+        .unwrap();
+        write_line(out, width);
     }
 }
 
@@ -466,11 +935,27 @@ pub fn invoke_external_diff_tool(
     new_content: &str,
     snapshot_file: Option<&Path>,
 ) -> bool {
+    match run_external_diff_tool(tool, old_content, new_content, snapshot_file) {
+        Some(output) => {
+            print!("{output}");
+            true
+        }
+        None => false,
+    }
+}
+
+/// Runs an external diff tool and returns its captured stdout.
+fn run_external_diff_tool(
+    tool: &str,
+    old_content: &str,
+    new_content: &str,
+    snapshot_file: Option<&Path>,
+) -> Option<String> {
     let dir = match tempfile::tempdir() {
         Ok(dir) => dir,
         Err(err) => {
             eprintln!("warning: failed to create temp dir for diff tool: {err}");
-            return false;
+            return None;
         }
     };
 
@@ -486,13 +971,13 @@ pub fn invoke_external_diff_tool(
     // Write old content
     if let Err(err) = std::fs::write(&old_path, old_content) {
         eprintln!("warning: failed to write old snapshot to temp file: {err}");
-        return false;
+        return None;
     }
 
     // Write new content
     if let Err(err) = std::fs::write(&new_path, new_content) {
         eprintln!("warning: failed to write new snapshot to temp file: {err}");
-        return false;
+        return None;
     }
 
     // Invoke the diff tool from the temp directory so paths are relative/clean.
@@ -503,10 +988,7 @@ pub fn invoke_external_diff_tool(
 
     // Split tool string to support arguments (e.g., "delta --side-by-side")
     let mut parts = tool.split_whitespace();
-    let cmd = match parts.next() {
-        Some(cmd) => cmd,
-        None => return false,
-    };
+    let cmd = parts.next()?;
     let mut command = Command::new(cmd);
     command.args(parts);
     command.current_dir(dir.path());
@@ -515,20 +997,17 @@ pub fn invoke_external_diff_tool(
 
     match command.output() {
         Ok(output) => {
-            // Print captured output through normal channels so it gets captured
-            // by cargo test when appropriate (just like the built-in diff)
-            if !output.stdout.is_empty() {
-                print!("{}", String::from_utf8_lossy(&output.stdout));
-            }
+            // Stdout is handed back to the caller so it goes through the same
+            // channel as the built-in diff (important for cargo test capture)
             if !output.stderr.is_empty() {
                 eprint!("{}", String::from_utf8_lossy(&output.stderr));
             }
             // Non-zero exit is normal for diff tools when files differ
-            true
+            Some(String::from_utf8_lossy(&output.stdout).into_owned())
         }
         Err(err) => {
             eprintln!("warning: failed to invoke diff tool `{tool}`: {err}");
-            false
+            None
         }
     }
     // Temp dir is cleaned up when `dir` goes out of scope
@@ -541,3 +1020,110 @@ fn test_invisible() {
         "␍␊\r\n␛␍\r␇␈␡␊\n"
     );
 }
+
+#[test]
+fn test_side_by_side() {
+    let old = "a\nb\nc\n";
+    let new = "a\nB\nc\nthis line is long enough to be wrapped into three rows\n";
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = String::new();
+    write_side_by_side(&mut out, &diff, false, false, 60);
+
+    let lines = out.lines().collect::<Vec<_>>();
+    // the changed line is shown next to its replacement
+    assert!(lines.iter().any(|x| x.contains("-b") && x.contains("+B")));
+    // a new line only shows up in the right column
+    let added = lines.iter().position(|x| x.contains("this line")).unwrap();
+    assert!(!lines[added].contains('-'));
+    assert!(lines[added + 2].contains("rows"));
+    assert!(!lines.iter().any(|x| x.contains("snapshots are matching")));
+}
+
+#[test]
+fn test_structural() {
+    let old = r#"{"users": [{"name": "a", "email": "a"}], "count": 1}"#;
+    let new = "count: 1\nusers:\n  - name: a\n    email: b\n";
+    let changes = structural_changes(old, new).unwrap();
+    let mut out = String::new();
+    write_structural(&mut out, &changes, 60);
+    assert!(out.contains(r#"~ .users[0].email: "a" -> "b""#));
+    assert_eq!(out.lines().count(), 3);
+
+    // plain text is left to the line based diff
+    assert!(structural_changes("hello\n", "world\n").is_none());
+
+    // and so is RON, with a notice
+    use crate::snapshot::{TextSnapshotContents, TextSnapshotKind};
+    let snapshot = |text: &str| {
+        Snapshot::from_components(
+            "test".into(),
+            None,
+            MetaData::default(),
+            TextSnapshotContents::new(text.into(), TextSnapshotKind::File).into(),
+        )
+    };
+    let old = snapshot("User(\n  id: 1,\n  role: Admin,\n)");
+    let new = snapshot("User(\n  id: 1,\n  role: Guest,\n)");
+    let mut printer = SnapshotPrinter::new(Path::new("."), Some(&old), &new);
+    printer.set_diff_style(DiffStyle::Structural);
+    let mut out = String::new();
+    printer.write_changeset(&mut out);
+    assert!(out.contains(STRUCTURAL_UNAVAILABLE));
+    assert!(out.contains("role: Guest,"));
+    assert!(
+        compact_diff(Some(&old), &new, DiffStyle::Structural).starts_with(STRUCTURAL_UNAVAILABLE)
+    );
+}
+
+#[test]
+fn test_binary_changeset() {
+    use crate::snapshot::{MetaData, SnapshotKind};
+    use std::rc::Rc;
+
+    let snapshot = |extension: &str, data: &[u8]| {
+        Snapshot::from_components(
+            "test".into(),
+            None,
+            MetaData {
+                snapshot_kind: SnapshotKind::Binary {
+                    extension: extension.into(),
+                },
+                ..MetaData::default()
+            },
+            SnapshotContents::Binary(Some(Rc::new(data.to_vec()))),
+        )
+    };
+    let snapshot_file = Path::new(env!("CARGO_MANIFEST_DIR")).join("snapshots/test.snap");
+    let old = snapshot("bin", b"\x00\x01\x02 payload v1");
+    let new = snapshot("bin", b"\x00\x01\x02 payload v2");
+    let mut printer = SnapshotPrinter::new(Path::new("."), Some(&old), &new);
+    printer.set_snapshot_file(Some(&snapshot_file));
+    let mut out = String::new();
+    printer.write_changeset(&mut out);
+    assert!(out
+        .contains("-00000000  00 01 02 20 70 61 79 6c  6f 61 64 20 76 31        |... payload v1|"));
+    assert!(out
+        .contains("+00000000  00 01 02 20 70 61 79 6c  6f 61 64 20 76 32        |... payload v2|"));
+
+    // images are only linked
+    let old = snapshot("png", b"\x89PNG old");
+    let new = snapshot("png", b"\x89PNG new");
+    let mut printer = SnapshotPrinter::new(Path::new("."), Some(&old), &new);
+    printer.set_snapshot_file(Some(&snapshot_file));
+    let mut out = String::new();
+    printer.write_changeset(&mut out);
+    assert!(!out.contains("00000000"));
+}
+
+#[test]
+fn test_github_annotation() {
+    assert_eq!(
+        github_annotation(
+            Path::new("src/lib.rs"),
+            Some(4),
+            "snapshot 'a, b' failed",
+            "-old 100%\n+new"
+        ),
+        "::error file=src/lib.rs,line=4,title=snapshot 'a%2C b' failed::-old 100%25%0A+new"
+    );
+}
@@ -1,9 +1,12 @@
+use once_cell::sync::Lazy;
 use pest::Parser;
 use pest_derive::Parser;
+use regex::Regex;
 use std::borrow::Cow;
 use std::fmt;
 
 use crate::content::Content;
+use crate::utils::Glob;
 
 #[derive(Debug)]
 pub struct SelectorParseError(Box<pest::error::Error<Rule>>);
@@ -187,6 +190,299 @@ pub fn rounded_redaction(decimals: usize) -> Redaction {
     })
 }
 
+/// The name of the newtype that marks values for [`stable_id_redaction`]
+/// until the placeholders are assigned.
+const STABLE_ID_MARKER: &str = "$insta_stable_id";
+
+/// Creates a redaction that replaces values with stable numbered
+/// placeholders.
+///
+/// Every distinct value is replaced with `[id-1]`, `[id-2]` and so forth in
+/// the order the values appear in the snapshot.  Equal values get the same
+/// placeholder, even if they are matched by different selectors, so the
+/// relationships between redacted values can still be verified.  Null values
+/// are left as they are.  The numbering starts over for every snapshot.
+///
+/// ```rust
+/// # use insta::{Settings, stable_id_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".**.id", stable_id_redaction());
+/// settings.add_redaction(".**.owner_id", stable_id_redaction());
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+pub fn stable_id_redaction() -> Redaction {
+    dynamic_redaction(|value: Content, _path: ContentPath| -> Content {
+        Content::NewtypeStruct(STABLE_ID_MARKER, Box::new(value))
+    })
+}
+
+/// The name of the newtype that marks maps for [`map_key_redaction`] until
+/// they are sorted.
+const SORTED_KEYS_MARKER: &str = "$insta_sorted_keys";
+
+/// Creates a redaction that replaces the keys of a map.
+///
+/// The selector has to match the map itself.  Every key is replaced with the
+/// given redaction and the map is sorted afterwards, so that maps keyed by
+/// random identifiers (like a `HashMap<Uuid, Item>`) produce stable
+/// snapshots.  The sorting happens after all other redactions were applied
+/// and orders the entries by their redacted keys and then by their values.
+///
+/// Static replacements make all keys equal, so this is typically combined
+/// with [`stable_id_redaction`] which numbers the keys in their sorted order:
+///
+/// ```rust
+/// # use insta::{Settings, map_key_redaction, stable_id_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".items", map_key_redaction(stable_id_redaction()));
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+pub fn map_key_redaction<R: Into<Redaction>>(key: R) -> Redaction {
+    let key = key.into();
+    dynamic_redaction(move |mut value: Content, path: ContentPath| -> Content {
+        let inner = value.resolve_inner_mut();
+        if let Content::Map(ref mut map) = inner {
+            let mut key_path = path.0.to_vec();
+            key_path.push(PathItem::Field("$key"));
+            let map = std::mem::take(map)
+                .into_iter()
+                .map(|(k, v)| (key.redact(k, &key_path), v))
+                .collect();
+            *inner = Content::NewtypeStruct(SORTED_KEYS_MARKER, Box::new(Content::Map(map)));
+        }
+        value
+    })
+}
+
+/// Resolves the markers left by [`stable_id_redaction`] and
+/// [`map_key_redaction`] once all redactions were applied.
+pub(crate) fn finish_redactions(content: &mut Content) {
+    sort_redacted_maps(content);
+    assign_stable_ids(content);
+}
+
+/// Sorts the maps marked by [`map_key_redaction`], inner maps first.
+fn sort_redacted_maps(content: &mut Content) {
+    // stable ids are numbered after sorting so they must not affect the order
+    fn sort_key(content: &Content) -> Content {
+        let mut content = content.clone();
+        content.walk(&mut |content| match content {
+            Content::NewtypeStruct(STABLE_ID_MARKER, _) => {
+                *content = Content::Unit;
+                false
+            }
+            _ => true,
+        });
+        content
+    }
+
+    content.walk(&mut |content| match content {
+        Content::NewtypeStruct(SORTED_KEYS_MARKER, inner) => {
+            let mut inner = std::mem::replace(&mut **inner, Content::Unit);
+            if let Content::Map(ref mut map) = inner {
+                let mut entries = std::mem::take(map)
+                    .into_iter()
+                    .map(|(mut k, mut v)| {
+                        sort_redacted_maps(&mut k);
+                        sort_redacted_maps(&mut v);
+                        ((sort_key(&k), sort_key(&v)), (k, v))
+                    })
+                    .collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                *map = entries.into_iter().map(|(_, entry)| entry).collect();
+            }
+            *content = inner;
+            false
+        }
+        _ => true,
+    });
+}
+
+/// Replaces the values marked by [`stable_id_redaction`] with their
+/// numbered placeholders.
+fn assign_stable_ids(content: &mut Content) {
+    let mut seen: Vec<Content> = vec![];
+    content.walk(&mut |content| match content {
+        Content::NewtypeStruct(STABLE_ID_MARKER, value) => {
+            // a missing value does not refer to anything
+            if value.is_nil() {
+                *content = std::mem::replace(&mut **value, Content::Unit);
+                return false;
+            }
+            // `Some(id)` and `id` refer to the same entity
+            let value = value.resolve_inner();
+            let idx = match seen.iter().position(|x| x == value) {
+                Some(idx) => idx,
+                None => {
+                    seen.push(value.clone());
+                    seen.len() - 1
+                }
+            };
+            *content = Content::from(format!("[id-{}]", idx + 1));
+            false
+        }
+        _ => true,
+    });
+}
+
+/// A built-in redaction which replaces values of a common volatile shape
+/// anywhere in a snapshot.
+///
+/// Presets are enabled with
+/// [`Settings::add_redaction_preset`](crate::Settings::add_redaction_preset)
+/// or for all snapshots by their name in `redactions.presets` in
+/// `insta.yaml`.
+#[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RedactionPreset {
+    /// UUIDs like `67e55044-10b1-426f-9247-bb680e5fe0c8` become `[uuid]`
+    /// (`uuid`).
+    Uuid,
+    /// RFC 3339 timestamps like `2024-01-01T10:00:00Z` become `[timestamp]`
+    /// (`timestamp`).
+    Timestamp,
+    /// Paths in the temporary directory become `[temp-path]` followed by
+    /// everything after the first component, so `/tmp/.tmpA1b2C3/out.txt`
+    /// becomes `[temp-path]/out.txt` (`temp-path`).
+    TempPath,
+    /// Memory addresses like `0x7ffd5e8c1a2c` become `[address]`
+    /// (`address`).
+    Address,
+    /// Durations like `1.5ms`, `250µs` or `1m 30s` and serialized
+    /// [`std::time::Duration`] values become `[duration]` (`duration`).
+    ///
+    /// Whole seconds, minutes or hours like `3s` are not redacted as they
+    /// cannot be told apart from other text like `1990s`.
+    Duration,
+}
+
+static UUID_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b")
+        .unwrap()
+});
+
+static TIMESTAMP_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:[Zz]|[+-]\d{2}:\d{2})")
+        .unwrap()
+});
+
+static DURATION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    // plain numbers with `s`, `m` or `h` are too common in text (eg: `1990s`)
+    // so those need a fraction or a second unit like in `1m 30s`.
+    Regex::new(concat!(
+        r"\b(?:",
+        r"\d+(?:\.\d+)?[hms](?: ?\d+(?:\.\d+)?(?:ns|µs|us|ms|s|m|h))+",
+        r"|\d+\.\d+(?:ns|µs|us|ms|s|m|h)",
+        r"|\d+(?:ns|µs|us|ms)",
+        r")\b",
+    ))
+    .unwrap()
+});
+
+static TEMP_PATH_REGEX: Lazy<Regex> = Lazy::new(|| {
+    // the temporary directory is often a symlink (eg: on macOS), so both
+    // spellings are matched.
+    let temp_dir = std::env::temp_dir();
+    let mut dirs = vec![temp_dir.clone()];
+    if let Ok(canonical) = temp_dir.canonicalize() {
+        if canonical != temp_dir {
+            dirs.push(canonical);
+        }
+    }
+    let dirs = dirs
+        .iter()
+        .map(|dir| regex::escape(dir.to_string_lossy().trim_end_matches(['/', '\\'])))
+        .collect::<Vec<_>>();
+    Regex::new(&format!(r#"(?:{})[/\\][^/\\\s"']+"#, dirs.join("|"))).unwrap()
+});
+
+static ADDRESS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b0x[0-9a-fA-F]{6,16}\b").unwrap());
+
+impl RedactionPreset {
+    /// All presets in the order they are applied.  The broad duration
+    /// pattern comes last so it does not match within other values.
+    const ALL: [RedactionPreset; 5] = [
+        RedactionPreset::Uuid,
+        RedactionPreset::Timestamp,
+        RedactionPreset::TempPath,
+        RedactionPreset::Address,
+        RedactionPreset::Duration,
+    ];
+
+    /// Looks up a preset by the name used in settings and `insta.yaml`.
+    pub(crate) fn from_name(name: &str) -> Option<RedactionPreset> {
+        Some(match name {
+            "uuid" => RedactionPreset::Uuid,
+            "timestamp" => RedactionPreset::Timestamp,
+            "duration" => RedactionPreset::Duration,
+            "temp-path" => RedactionPreset::TempPath,
+            "address" => RedactionPreset::Address,
+            _ => return None,
+        })
+    }
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            RedactionPreset::Uuid => "[uuid]",
+            RedactionPreset::Timestamp => "[timestamp]",
+            RedactionPreset::Duration => "[duration]",
+            RedactionPreset::TempPath => "[temp-path]",
+            RedactionPreset::Address => "[address]",
+        }
+    }
+
+    fn regex(self) -> &'static Regex {
+        match self {
+            RedactionPreset::Uuid => &UUID_REGEX,
+            RedactionPreset::Timestamp => &TIMESTAMP_REGEX,
+            RedactionPreset::Duration => &DURATION_REGEX,
+            RedactionPreset::TempPath => &TEMP_PATH_REGEX,
+            RedactionPreset::Address => &ADDRESS_REGEX,
+        }
+    }
+}
+
+/// Applies redaction presets to every value in the content.
+///
+/// Map keys are left alone: redacting them would make keys collide and the
+/// order of the map unstable.  They can be redacted with
+/// [`map_key_redaction`] instead.
+pub(crate) fn apply_redaction_presets(content: &mut Content, presets: &[RedactionPreset]) {
+    if presets.is_empty() {
+        return;
+    }
+    content.walk(&mut |content| match content {
+        Content::Map(entries) => {
+            for (_, value) in entries {
+                apply_redaction_presets(value, presets);
+            }
+            false
+        }
+        // stable ids are numbered by their original value
+        Content::NewtypeStruct(STABLE_ID_MARKER, _) => false,
+        // a serialized `std::time::Duration`
+        Content::Struct("Duration", fields)
+            if presets.contains(&RedactionPreset::Duration)
+                && fields.len() == 2
+                && fields[0].0 == "secs"
+                && fields[1].0 == "nanos" =>
+        {
+            *content = Content::from(RedactionPreset::Duration.placeholder());
+            false
+        }
+        Content::String(s) => {
+            for preset in RedactionPreset::ALL.iter().filter(|x| presets.contains(x)) {
+                if let Cow::Owned(redacted) = preset.regex().replace_all(s, preset.placeholder()) {
+                    *s = redacted;
+                }
+            }
+            false
+        }
+        _ => true,
+    });
+}
+
 impl Redaction {
     /// Performs the redaction of the value at the given path.
     fn redact(&self, value: Content, path: &[PathItem]) -> Content {
@@ -201,7 +497,7 @@ impl Redaction {
 #[grammar = "select_grammar.pest"]
 pub struct SelectParser;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum PathItem {
     Content(Content),
     Field(&'static str),
@@ -255,6 +551,261 @@ pub enum Segment<'a> {
     Key(Cow<'a, str>),
     Index(u64),
     Range(Option<i64>, Option<i64>),
+    Filter(Filter<'a>),
+    KeyGlob(KeyPattern<'a>),
+    KeyRegex(KeyPattern<'a>),
+}
+
+impl Segment<'_> {
+    fn into_static(self) -> Segment<'static> {
+        match self {
+            Segment::Key(x) => Segment::Key(Cow::Owned(x.into_owned())),
+            Segment::Index(x) => Segment::Index(x),
+            Segment::Wildcard => Segment::Wildcard,
+            Segment::DeepWildcard => Segment::DeepWildcard,
+            Segment::Range(a, b) => Segment::Range(a, b),
+            Segment::KeyGlob(x) => Segment::KeyGlob(x.into_static()),
+            Segment::KeyRegex(x) => Segment::KeyRegex(x.into_static()),
+            Segment::Filter(filter) => Segment::Filter(Filter {
+                path: filter.path.into_iter().map(Segment::into_static).collect(),
+                condition: filter.condition.map(|(op, value)| {
+                    (
+                        op,
+                        match value {
+                            FilterValue::String(x) => {
+                                FilterValue::String(Cow::Owned(x.into_owned()))
+                            }
+                            FilterValue::Number(x) => {
+                                FilterValue::Number(Cow::Owned(x.into_owned()))
+                            }
+                            FilterValue::Bool(x) => FilterValue::Bool(x),
+                            FilterValue::Null => FilterValue::Null,
+                        },
+                    )
+                }),
+            }),
+        }
+    }
+}
+
+/// A key pattern like `*-id` or `~"(created|updated)_at"`.
+///
+/// Both globs and regular expressions have to match the entire key.
+#[derive(Clone)]
+pub struct KeyPattern<'a> {
+    source: Cow<'a, str>,
+    matcher: KeyMatcher,
+}
+
+#[derive(Clone)]
+enum KeyMatcher {
+    Glob(Glob),
+    Regex(regex::Regex),
+}
+
+impl<'a> KeyPattern<'a> {
+    fn glob(source: Cow<'a, str>) -> KeyPattern<'a> {
+        KeyPattern {
+            matcher: KeyMatcher::Glob(Glob::new(&source, false)),
+            source,
+        }
+    }
+
+    fn regex(source: Cow<'a, str>) -> Result<KeyPattern<'a>, regex::Error> {
+        Ok(KeyPattern {
+            matcher: KeyMatcher::Regex(regex::Regex::new(&format!("^(?:{source})$"))?),
+            source,
+        })
+    }
+
+    fn into_static(self) -> KeyPattern<'static> {
+        KeyPattern {
+            source: Cow::Owned(self.source.into_owned()),
+            matcher: self.matcher,
+        }
+    }
+
+    fn is_match(&self, key: &str) -> bool {
+        match self.matcher {
+            KeyMatcher::Glob(ref glob) => glob.is_match(key),
+            KeyMatcher::Regex(ref regex) => regex.is_match(key),
+        }
+    }
+}
+
+impl fmt::Debug for KeyPattern<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("KeyPattern").field(&self.source).finish()
+    }
+}
+
+impl PartialEq for KeyPattern<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for KeyPattern<'_> {}
+
+/// A predicate like `[?(.kind == "temp")]` which matches the elements whose
+/// value satisfies it.
+///
+/// The path is resolved relative to the element.  Without a condition the
+/// filter matches if the path exists and is not null.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter<'a> {
+    path: Vec<Segment<'a>>,
+    condition: Option<(FilterOp, FilterValue<'a>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterValue<'a> {
+    String(Cow<'a, str>),
+    /// Numbers are kept in their textual form so that segments stay `Eq`.
+    Number(Cow<'a, str>),
+    Bool(bool),
+    Null,
+}
+
+impl Filter<'_> {
+    fn is_match(&self, value: &Content) -> bool {
+        let mut value = value;
+        for segment in &self.path {
+            let child = match (value.resolve_inner(), segment) {
+                (Content::Struct(_, fields), Segment::Key(key))
+                | (Content::StructVariant(_, _, _, fields), Segment::Key(key)) => fields
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value),
+                (Content::Map(map), Segment::Key(key)) => map
+                    .iter()
+                    .find(|(name, _)| name.as_str() == Some(key))
+                    .map(|(_, value)| value),
+                (_, Segment::Index(idx)) => value.as_slice().and_then(|x| x.get(*idx as usize)),
+                _ => None,
+            };
+            value = match child {
+                Some(child) => child,
+                None => return false,
+            };
+        }
+
+        let (op, expected) = match self.condition {
+            Some((op, ref expected)) => (op, expected),
+            None => return !value.is_nil(),
+        };
+        let ordering = match *expected {
+            FilterValue::String(ref expected) => match value.resolve_inner() {
+                Content::String(s) => Some(s.as_str().cmp(expected)),
+                // unit enum variants are compared by their name
+                Content::UnitVariant(_, _, name) => Some((*name).cmp(expected)),
+                _ => None,
+            },
+            FilterValue::Number(ref expected) => {
+                let actual = value
+                    .as_f64()
+                    .or_else(|| value.as_i64().map(|x| x as f64))
+                    .or_else(|| value.as_u64().map(|x| x as f64));
+                actual.and_then(|x| x.partial_cmp(&expected.parse::<f64>().ok()?))
+            }
+            FilterValue::Bool(expected) => value.as_bool().map(|x| x.cmp(&expected)),
+            FilterValue::Null => Some(std::cmp::Ordering::Equal).filter(|_| value.is_nil()),
+        };
+
+        use std::cmp::Ordering::*;
+        match op {
+            FilterOp::Eq => ordering == Some(Equal),
+            FilterOp::Ne => ordering != Some(Equal),
+            FilterOp::Lt => ordering == Some(Less),
+            FilterOp::Le => matches!(ordering, Some(Less | Equal)),
+            FilterOp::Gt => ordering == Some(Greater),
+            FilterOp::Ge => matches!(ordering, Some(Greater | Equal)),
+        }
+    }
+}
+
+/// Returns the contents of a quoted string.
+fn parse_string(sq: &str) -> Cow<'_, str> {
+    let s = &sq[1..sq.len() - 1];
+    let mut was_backslash = false;
+    if s.bytes().any(|x| x == b'\\') {
+        Cow::Owned(
+            s.chars()
+                .filter_map(|c| {
+                    let rv = match c {
+                        '\\' if !was_backslash => {
+                            was_backslash = true;
+                            return None;
+                        }
+                        other => other,
+                    };
+                    was_backslash = false;
+                    Some(rv)
+                })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Parses the `key` and `subscript` rules into a segment.
+fn parse_key_segment(pair: pest::iterators::Pair<'_, Rule>) -> Segment<'_> {
+    match pair.as_rule() {
+        Rule::key => Segment::Key(Cow::Borrowed(&pair.as_str()[1..])),
+        Rule::subscript => {
+            let subscript_rule = pair.into_inner().next().unwrap();
+            match subscript_rule.as_rule() {
+                Rule::int => Segment::Index(subscript_rule.as_str().parse().unwrap()),
+                Rule::string => Segment::Key(parse_string(subscript_rule.as_str())),
+                _ => unreachable!(),
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn parse_filter(pair: pest::iterators::Pair<'_, Rule>) -> Filter<'_> {
+    let mut inner = pair.into_inner();
+    let path = inner
+        .next()
+        .unwrap()
+        .into_inner()
+        .filter(|x| x.as_rule() != Rule::identity)
+        .map(parse_key_segment)
+        .collect();
+    let condition = inner.next().map(|op| {
+        let op = match op.as_str() {
+            "==" => FilterOp::Eq,
+            "!=" => FilterOp::Ne,
+            "<" => FilterOp::Lt,
+            "<=" => FilterOp::Le,
+            ">" => FilterOp::Gt,
+            ">=" => FilterOp::Ge,
+            _ => unreachable!(),
+        };
+        let value = inner.next().unwrap();
+        let value = match value.as_rule() {
+            Rule::string => FilterValue::String(parse_string(value.as_str())),
+            Rule::number => FilterValue::Number(Cow::Borrowed(value.as_str())),
+            Rule::true_value => FilterValue::Bool(true),
+            Rule::false_value => FilterValue::Bool(false),
+            Rule::null_value => FilterValue::Null,
+            _ => unreachable!(),
+        };
+        (op, value)
+    });
+    Filter { path, condition }
 }
 
 #[derive(Debug, Clone)]
@@ -296,38 +847,27 @@ impl<'a> Selector<'a> {
                         have_deep_wildcard = true;
                         Segment::DeepWildcard
                     }
-                    Rule::key => Segment::Key(Cow::Borrowed(&segment_pair.as_str()[1..])),
-                    Rule::subscript => {
-                        let subscript_rule = segment_pair.into_inner().next().unwrap();
-                        match subscript_rule.as_rule() {
-                            Rule::int => Segment::Index(subscript_rule.as_str().parse().unwrap()),
-                            Rule::string => {
-                                let sq = subscript_rule.as_str();
-                                let s = &sq[1..sq.len() - 1];
-                                let mut was_backslash = false;
-                                Segment::Key(if s.bytes().any(|x| x == b'\\') {
-                                    Cow::Owned(
-                                        s.chars()
-                                            .filter_map(|c| {
-                                                let rv = match c {
-                                                    '\\' if !was_backslash => {
-                                                        was_backslash = true;
-                                                        return None;
-                                                    }
-                                                    other => other,
-                                                };
-                                                was_backslash = false;
-                                                Some(rv)
-                                            })
-                                            .collect(),
-                                    )
-                                } else {
-                                    Cow::Borrowed(s)
-                                })
+                    Rule::key | Rule::subscript => parse_key_segment(segment_pair),
+                    Rule::key_glob => Segment::KeyGlob(KeyPattern::glob(Cow::Borrowed(
+                        &segment_pair.as_str()[1..],
+                    ))),
+                    Rule::key_regex => {
+                        let sq = segment_pair.clone().into_inner().next().unwrap().as_str();
+                        match KeyPattern::regex(Cow::Borrowed(&sq[1..sq.len() - 1])) {
+                            Ok(pattern) => Segment::KeyRegex(pattern),
+                            Err(err) => {
+                                return Err(SelectorParseError(Box::new(
+                                    pest::error::Error::new_from_span(
+                                        pest::error::ErrorVariant::CustomError {
+                                            message: format!("invalid key pattern: {err}"),
+                                        },
+                                        segment_pair.as_span(),
+                                    ),
+                                )))
                             }
-                            _ => unreachable!(),
                         }
                     }
+                    Rule::filter => Segment::Filter(parse_filter(segment_pair)),
                     Rule::full_range => Segment::Range(None, None),
                     Rule::range => {
                         let mut int_rule = segment_pair
@@ -357,33 +897,42 @@ impl<'a> Selector<'a> {
             selectors: self
                 .selectors
                 .into_iter()
-                .map(|parts| {
-                    parts
-                        .into_iter()
-                        .map(|x| match x {
-                            Segment::Key(x) => Segment::Key(Cow::Owned(x.into_owned())),
-                            Segment::Index(x) => Segment::Index(x),
-                            Segment::Wildcard => Segment::Wildcard,
-                            Segment::DeepWildcard => Segment::DeepWildcard,
-                            Segment::Range(a, b) => Segment::Range(a, b),
-                        })
-                        .collect()
-                })
+                .map(|parts| parts.into_iter().map(Segment::into_static).collect())
                 .collect(),
         }
     }
 
-    fn segment_is_match(&self, segment: &Segment, element: &PathItem) -> bool {
+    fn segment_is_match(&self, segment: &Segment, element: &PathItem, filtered: bool) -> bool {
         match *segment {
             Segment::Wildcard => true,
             Segment::DeepWildcard => true,
             Segment::Key(ref k) => element.as_str() == Some(k),
             Segment::Index(i) => element.as_u64() == Some(i),
             Segment::Range(start, end) => element.range_check(start, end),
+            Segment::KeyGlob(ref pattern) | Segment::KeyRegex(ref pattern) => {
+                element.as_str().map_or(false, |key| pattern.is_match(key))
+            }
+            Segment::Filter(..) => filtered,
         }
     }
 
-    fn selector_is_match(&self, selector: &[Segment], path: &[PathItem]) -> bool {
+    /// Checks a single selector.  `offset` is the index of the selector's
+    /// first segment in [`Selector::filter_matches`].
+    fn selector_is_match(
+        &self,
+        selector: &[Segment],
+        offset: usize,
+        path: &[PathItem],
+        filters: &[Vec<bool>],
+    ) -> bool {
+        let filtered = |segment_idx: usize, pos: usize| {
+            filters
+                .get(pos)
+                .and_then(|x| x.get(offset + segment_idx))
+                .copied()
+                .unwrap_or(false)
+        };
+
         if let Some(idx) = selector.iter().position(|x| *x == Segment::DeepWildcard) {
             let forward_sel = &selector[..idx];
             let backward_sel = &selector[idx + 1..];
@@ -392,14 +941,29 @@ impl<'a> Selector<'a> {
                 return false;
             }
 
-            for (segment, element) in forward_sel.iter().zip(path.iter()) {
-                if !self.segment_is_match(segment, element) {
+            // a filter needs an element to test
+            if path.len() < forward_sel.len() + backward_sel.len()
+                && backward_sel
+                    .iter()
+                    .any(|x| matches!(x, Segment::Filter(..)))
+            {
+                return false;
+            }
+
+            for (pos, (segment, element)) in forward_sel.iter().zip(path.iter()).enumerate() {
+                if !self.segment_is_match(segment, element, filtered(pos, pos)) {
                     return false;
                 }
             }
 
-            for (segment, element) in backward_sel.iter().rev().zip(path.iter().rev()) {
-                if !self.segment_is_match(segment, element) {
+            for ((segment_idx, segment), (pos, element)) in backward_sel
+                .iter()
+                .enumerate()
+                .rev()
+                .zip(path.iter().enumerate().rev())
+            {
+                let filtered = filtered(idx + 1 + segment_idx, pos);
+                if !self.segment_is_match(segment, element, filtered) {
                     return false;
                 }
             }
@@ -409,8 +973,8 @@ impl<'a> Selector<'a> {
             if selector.len() != path.len() {
                 return false;
             }
-            for (segment, element) in selector.iter().zip(path.iter()) {
-                if !self.segment_is_match(segment, element) {
+            for (pos, (segment, element)) in selector.iter().zip(path.iter()).enumerate() {
+                if !self.segment_is_match(segment, element, filtered(pos, pos)) {
                     return false;
                 }
             }
@@ -418,30 +982,67 @@ impl<'a> Selector<'a> {
         }
     }
 
+    /// Checks if the selector matches the given path.
+    ///
+    /// As the values along the path are not known here, filters like
+    /// `[?(.kind == "temp")]` never match.
     pub fn is_match(&self, path: &[PathItem]) -> bool {
+        self.is_match_filtered(path, &[])
+    }
+
+    fn is_match_filtered(&self, path: &[PathItem], filters: &[Vec<bool>]) -> bool {
+        let mut offset = 0;
         for selector in &self.selectors {
-            if self.selector_is_match(selector, path) {
+            if self.selector_is_match(selector, offset, path, filters) {
                 return true;
             }
+            offset += selector.len();
         }
         false
     }
 
+    /// Returns true if any selector has a filter.  Only then the values along
+    /// the path need to be tested.
+    fn has_filters(&self) -> bool {
+        self.selectors
+            .iter()
+            .flatten()
+            .any(|x| matches!(x, Segment::Filter(..)))
+    }
+
+    /// Tests a value against all filters.  The result is indexed by the
+    /// position of the segment in the flattened selectors.
+    fn filter_matches(&self, value: &Content) -> Vec<bool> {
+        self.selectors
+            .iter()
+            .flatten()
+            .map(|segment| match *segment {
+                Segment::Filter(ref filter) => filter.is_match(value),
+                _ => false,
+            })
+            .collect()
+    }
+
     pub fn redact(&self, value: Content, redaction: &Redaction) -> Content {
-        self.redact_impl(value, redaction, &mut vec![])
+        let mut path = RedactionPath {
+            items: vec![],
+            filters: vec![],
+            has_filters: self.has_filters(),
+        };
+        self.redact_impl(value, redaction, &mut path)
     }
 
     fn redact_seq(
         &self,
         seq: Vec<Content>,
         redaction: &Redaction,
-        path: &mut Vec<PathItem>,
+        path: &mut RedactionPath,
     ) -> Vec<Content> {
         let len = seq.len();
         seq.into_iter()
             .enumerate()
             .map(|(idx, value)| {
-                path.push(PathItem::Index(idx as u64, len as u64));
+                path.push(self, PathItem::Index(idx as u64, len as u64), &value);
                 let new_value = self.redact_impl(value, redaction, path);
                 path.pop();
                 new_value
@@ -453,11 +1054,11 @@ impl<'a> Selector<'a> {
         &self,
         seq: Vec<(&'static str, Content)>,
         redaction: &Redaction,
-        path: &mut Vec<PathItem>,
+        path: &mut RedactionPath,
     ) -> Vec<(&'static str, Content)> {
         seq.into_iter()
             .map(|(key, value)| {
-                path.push(PathItem::Field(key));
+                path.push(self, PathItem::Field(key), &value);
                 let new_value = self.redact_impl(value, redaction, path);
                 path.pop();
                 (key, new_value)
@@ -469,20 +1070,20 @@ impl<'a> Selector<'a> {
         &self,
         value: Content,
         redaction: &Redaction,
-        path: &mut Vec<PathItem>,
+        path: &mut RedactionPath,
     ) -> Content {
-        if self.is_match(path) {
-            redaction.redact(value, path)
+        if self.is_match_filtered(&path.items, &path.filters) {
+            redaction.redact(value, &path.items)
         } else {
             match value {
                 Content::Map(map) => Content::Map(
                     map.into_iter()
                         .map(|(key, value)| {
-                            path.push(PathItem::Field("$key"));
+                            path.push(self, PathItem::Field("$key"), &key);
                             let new_key = self.redact_impl(key.clone(), redaction, path);
                             path.pop();
 
-                            path.push(PathItem::Content(key));
+                            path.push(self, PathItem::Content(key), &value);
                             let new_value = self.redact_impl(value, redaction, path);
                             path.pop();
 
@@ -533,6 +1134,29 @@ impl<'a> Selector<'a> {
     }
 }
 
+/// The path to the value that is being redacted.
+struct RedactionPath {
+    items: Vec<PathItem>,
+    /// Which filters matched the values along the path.  The values
+    /// themselves are not retained as they are moved while redacting.
+    filters: Vec<Vec<bool>>,
+    has_filters: bool,
+}
+
+impl RedactionPath {
+    fn push(&mut self, selector: &Selector, item: PathItem, value: &Content) {
+        self.items.push(item);
+        if self.has_filters {
+            self.filters.push(selector.filter_matches(value));
+        }
+    }
+
+    fn pop(&mut self) {
+        self.items.pop();
+        self.filters.pop();
+    }
+}
+
 #[test]
 fn test_range_checks() {
     use similar_asserts::assert_eq;
@@ -545,3 +1169,112 @@ fn test_range_checks() {
     assert_eq!(PathItem::Index(1, 10).range_check(Some(1), None), true);
     assert_eq!(PathItem::Index(9, 10).range_check(Some(1), None), true);
 }
+
+#[test]
+fn test_selector_filters() {
+    let selector = Selector::parse(".other, .items[?(.kind == \"temp\")].id").unwrap();
+    let path = [
+        PathItem::Field("items"),
+        PathItem::Index(0, 2),
+        PathItem::Field("id"),
+    ];
+    // without values a filter cannot match
+    assert!(!selector.is_match(&path));
+    assert!(selector.is_match(&[PathItem::Field("other")]));
+
+    let item = Content::Struct("Item", vec![("kind", Content::from("temp"))]);
+    let other = Content::Struct("Item", vec![("kind", Content::from("stored"))]);
+    let filters = |value: &Content| {
+        vec![
+            vec![false; 2],
+            selector.filter_matches(value),
+            vec![false; 2],
+        ]
+    };
+    assert!(selector.is_match_filtered(&path, &filters(&item)));
+    assert!(!selector.is_match_filtered(&path, &filters(&other)));
+}
+
+#[test]
+fn test_redaction_presets() {
+    use similar_asserts::assert_eq;
+
+    let redact = |value: &str| {
+        let mut content = Content::from(value);
+        apply_redaction_presets(&mut content, &RedactionPreset::ALL);
+        content.as_str().unwrap().to_string()
+    };
+
+    assert_eq!(
+        redact("user 67e55044-10b1-426f-9247-BB680E5FE0C8 logged in"),
+        "user [uuid] logged in"
+    );
+    assert_eq!(
+        redact("2024-01-01T10:00:00.123+02:00 / 2024-01-01 10:00:00Z / 2024-01-01"),
+        "[timestamp] / [timestamp] / 2024-01-01"
+    );
+    assert_eq!(
+        redact("took 1.5ms, then 1m 30s"),
+        "took [duration], then [duration]"
+    );
+    assert_eq!(
+        redact("waited 250µs, 40ns and 2h5m"),
+        "waited [duration], [duration] and [duration]"
+    );
+    assert_eq!(redact("1st of 10 items"), "1st of 10 items");
+    assert_eq!(
+        redact("the 1990s, 100m sprint, 3h, 5 ms"),
+        "the 1990s, 100m sprint, 3h, 5 ms"
+    );
+    assert_eq!(redact("at 0x7ffd5e8c1a2c (0x10)"), "at [address] (0x10)");
+    let temp_path = std::env::temp_dir().join(".tmpA1b2C3").join("out.txt");
+    assert_eq!(
+        redact(&format!("wrote {}", temp_path.display())),
+        format!("wrote [temp-path]{}out.txt", std::path::MAIN_SEPARATOR)
+    );
+
+    // serialized durations are redacted as a whole
+    let mut content = Content::Struct(
+        "Duration",
+        vec![("secs", Content::U64(1)), ("nanos", Content::U32(5))],
+    );
+    apply_redaction_presets(&mut content, &[RedactionPreset::Duration]);
+    assert_eq!(content.as_str(), Some("[duration]"));
+
+    // map keys are not redacted so that they stay distinct
+    let mut content = Content::Map(vec![
+        (
+            Content::from("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+            Content::from("2024-01-01T10:00:00Z"),
+        ),
+        (
+            Content::from("a8098c1a-f86e-11da-bd1a-00112444be1e"),
+            Content::from("a8098c1a-f86e-11da-bd1a-00112444be1e"),
+        ),
+    ]);
+    apply_redaction_presets(
+        &mut content,
+        &[RedactionPreset::Uuid, RedactionPreset::Timestamp],
+    );
+    assert_eq!(
+        content,
+        Content::Map(vec![
+            (
+                Content::from("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+                Content::from("[timestamp]"),
+            ),
+            (
+                Content::from("a8098c1a-f86e-11da-bd1a-00112444be1e"),
+                Content::from("[uuid]"),
+            ),
+        ])
+    );
+
+    // only the enabled presets apply
+    let mut content = Content::from("67e55044-10b1-426f-9247-bb680e5fe0c8 after 3.2s");
+    apply_redaction_presets(&mut content, &[RedactionPreset::Duration]);
+    assert_eq!(
+        content.as_str(),
+        Some("67e55044-10b1-426f-9247-bb680e5fe0c8 after [duration]")
+    );
+}
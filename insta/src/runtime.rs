@@ -7,18 +7,22 @@ use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{borrow::Cow, env};
 
+use crate::comparator::Comparator;
+use crate::content::Content;
+use crate::env::get_tool_config;
+use crate::output::{compact_diff, github_annotation, SnapshotPrinter};
 use crate::settings::Settings;
 use crate::snapshot::{
     MetaData, PendingInlineSnapshot, Snapshot, SnapshotContents, SnapshotKind, TextSnapshotContents,
 };
-use crate::utils::{path_to_storage, style};
-use crate::{env::get_tool_config, output::SnapshotPrinter};
+use crate::utils::{diff_image_path, path_to_storage, style};
 use crate::{
     env::{
-        memoize_snapshot_file, pending_snapshot_path, snapshot_update_behavior, OutputBehavior,
-        SnapshotUpdateBehavior, ToolConfig,
+        memoize_assertion, memoize_snapshot_file, pending_snapshot_path, should_memoize_assertions,
+        snapshot_update_behavior, OutputBehavior, SnapshotUpdateBehavior, ToolConfig,
     },
     snapshot::TextSnapshotKind,
 };
@@ -302,6 +306,7 @@ struct SnapshotAssertionContext<'a> {
     tool_config: Arc<ToolConfig>,
     workspace: &'a Path,
     module_path: &'a str,
+    function_name: &'a str,
     snapshot_name: Option<Cow<'a, str>>,
     snapshot_file: Option<PathBuf>,
     duplication_key: Option<String>,
@@ -419,6 +424,7 @@ impl<'a> SnapshotAssertionContext<'a> {
             tool_config,
             workspace,
             module_path,
+            function_name,
             snapshot_name,
             snapshot_file,
             old_snapshot,
@@ -482,6 +488,7 @@ impl<'a> SnapshotAssertionContext<'a> {
                     .input_file()
                     .and_then(|x| self.localize_path(x))
                     .map(|x| path_to_storage(&x)),
+                explanation: None,
                 snapshot_kind: self.snapshot_kind.clone(),
             }),
             contents,
@@ -631,6 +638,57 @@ impl<'a> SnapshotAssertionContext<'a> {
         Ok(snapshot_update)
     }
 
+    /// Stores the diff image rendered by the comparator next to the pending
+    /// snapshot file.
+    fn save_diff_image(&self, new_snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+        let (snapshot_file, old_snapshot) = match (&self.snapshot_file, &self.old_snapshot) {
+            (Some(snapshot_file), Some(old_snapshot)) => (snapshot_file, old_snapshot),
+            _ => return Ok(()),
+        };
+        if let Some(image) = self.with_comparator(new_snapshot, |comparator| {
+            comparator.diff_image(old_snapshot, new_snapshot)
+        }) {
+            let path = diff_image_path(&pending_snapshot_path(self.workspace, snapshot_file));
+            fs::write(&path, image)?;
+            if self.tool_config.output_behavior() != OutputBehavior::Nothing {
+                elog!(
+                    "{} {}",
+                    style("stored diff image").green(),
+                    style(path.display()).cyan().underlined(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with the comparator for this assertion.
+    ///
+    /// A comparator set in the settings takes precedence over the one that is
+    /// configured for the snapshot path in `insta.yaml`.  Inline snapshots
+    /// are matched by the path of their source file.
+    fn with_comparator<R>(
+        &self,
+        new_snapshot: &Snapshot,
+        f: impl FnOnce(&dyn Comparator) -> R,
+    ) -> R {
+        Settings::with(|settings| {
+            if let Some(comparator) = settings.explicit_comparator() {
+                return f(comparator);
+            }
+            let path = match self.snapshot_file {
+                Some(ref snapshot_file) => Some(path_to_storage(
+                    snapshot_file
+                        .strip_prefix(self.workspace)
+                        .unwrap_or(snapshot_file),
+                )),
+                None => new_snapshot.metadata().source().map(Into::into),
+            };
+            f(path
+                .and_then(|path| self.tool_config.comparator_for(&path))
+                .unwrap_or_else(|| settings.comparator()))
+        })
+    }
+
     /// This prints the information about the snapshot
     fn print_snapshot_info(&self, new_snapshot: &Snapshot) {
         let mut printer =
@@ -639,6 +697,7 @@ impl<'a> SnapshotAssertionContext<'a> {
         printer.set_snapshot_file(self.snapshot_file.as_deref());
         printer.set_title(Some("Snapshot Summary"));
         printer.set_show_info(true);
+        printer.set_diff_style(self.tool_config.diff_style());
         match self.tool_config.output_behavior() {
             OutputBehavior::Summary => {
                 printer.print();
@@ -647,13 +706,113 @@ impl<'a> SnapshotAssertionContext<'a> {
                 printer.set_show_diff(true);
                 printer.print();
             }
+            OutputBehavior::Github => {
+                printer.set_show_diff(true);
+                printer.print();
+                self.print_github_annotations(new_snapshot);
+            }
             _ => {}
         }
     }
 
+    /// Prints GitHub Actions workflow commands which annotate the assertion
+    /// and, for file snapshots, the snapshot file with the failure.
+    fn print_github_annotations(&self, new_snapshot: &Snapshot) {
+        // annotations need paths relative to the checkout of the repository
+        let base = env::var("GITHUB_WORKSPACE")
+            .ok()
+            .filter(|x| !x.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.workspace.to_path_buf());
+        let relative = |path: &Path| {
+            let path = self.workspace.join(path);
+            match (base.canonicalize(), path.canonicalize()) {
+                (Ok(base), Ok(canonicalized)) => {
+                    path_relative_from(&canonicalized, &base).unwrap_or(path)
+                }
+                _ => path,
+            }
+        };
+
+        let title = format!(
+            "snapshot assertion for '{}' failed",
+            self.snapshot_name.as_deref().unwrap_or("unnamed snapshot"),
+        );
+        let diff = compact_diff(
+            self.old_snapshot.as_ref(),
+            new_snapshot,
+            self.tool_config.diff_style(),
+        );
+
+        if let Some(source) = new_snapshot.metadata().source() {
+            println!(
+                "{}",
+                github_annotation(
+                    &relative(Path::new(source)),
+                    Some(self.assertion_line),
+                    &title,
+                    &diff
+                )
+            );
+        }
+        if let Some(ref snapshot_file) = self.snapshot_file {
+            println!(
+                "{}",
+                github_annotation(&relative(snapshot_file), None, &title, &diff)
+            );
+        }
+    }
+
+    /// Records the outcome of the assertion for `cargo insta test --report`.
+    fn record_assertion(&self, new_snapshot: &Snapshot, outcome: &str, duration: Duration) {
+        if !should_memoize_assertions() {
+            return;
+        }
+        let mut record = vec![
+            (Content::from("function"), Content::from(self.function_name)),
+            (Content::from("module"), Content::from(self.module_path)),
+            (
+                Content::from("name"),
+                Content::from(self.snapshot_name.as_deref().unwrap_or("unnamed snapshot")),
+            ),
+            (
+                Content::from("file"),
+                Content::from(
+                    new_snapshot
+                        .metadata()
+                        .source()
+                        .unwrap_or(self.assertion_file),
+                ),
+            ),
+            (Content::from("line"), Content::from(self.assertion_line)),
+            (Content::from("outcome"), Content::from(outcome)),
+            (
+                Content::from("duration"),
+                Content::from(duration.as_secs_f64()),
+            ),
+        ];
+        if let Some(ref snapshot_file) = self.snapshot_file {
+            record.push((
+                Content::from("snapshot"),
+                Content::from(snapshot_file.to_string_lossy().as_ref()),
+            ));
+        }
+        if outcome == "fail" || outcome == "new" {
+            record.push((
+                Content::from("diff"),
+                Content::from(compact_diff(
+                    self.old_snapshot.as_ref(),
+                    new_snapshot,
+                    self.tool_config.diff_style(),
+                )),
+            ));
+        }
+        memoize_assertion(&Content::Map(record));
+    }
+
     /// Finalizes the assertion when the snapshot comparison fails, potentially
     /// panicking to fail the test
-    fn finalize(&self, update_result: SnapshotUpdateBehavior) {
+    fn finalize(&self, update_result: SnapshotUpdateBehavior, explanation: Option<&str>) {
         // if we are in glob mode, we want to adjust the finalization
         // so that we do not show the hints immediately.
         let fail_fast = {
@@ -708,18 +867,20 @@ impl<'a> SnapshotAssertionContext<'a> {
 
                     print_or_panic!(
                         fail_fast,
-                        "snapshot assertion from glob for '{}' failed in line {}",
+                        "snapshot assertion from glob for '{}' failed in line {}{}",
                         self.snapshot_name.as_deref().unwrap_or("unnamed snapshot"),
-                        self.assertion_line
+                        self.assertion_line,
+                        explanation.map(|x| format!(": {x}")).unwrap_or_default()
                     );
                     return;
                 }
             }
 
             panic!(
-                "snapshot assertion for '{}' failed in line {}",
+                "snapshot assertion for '{}' failed in line {}{}",
                 self.snapshot_name.as_deref().unwrap_or("unnamed snapshot"),
-                self.assertion_line
+                self.assertion_line,
+                explanation.map(|x| format!(": {x}")).unwrap_or_default()
             );
         }
     }
@@ -801,7 +962,11 @@ fn record_snapshot_duplicate(
             printer.set_snapshot_file(ctx.snapshot_file.as_deref());
             printer.set_title(Some("Differences in Block"));
             printer.set_snapshot_hints("previous assertion", "current assertion");
-            if ctx.tool_config.output_behavior() == OutputBehavior::Diff {
+            printer.set_diff_style(ctx.tool_config.diff_style());
+            if matches!(
+                ctx.tool_config.output_behavior(),
+                OutputBehavior::Diff | OutputBehavior::Github
+            ) {
                 printer.set_show_diff(true);
             }
             printer.print();
@@ -852,6 +1017,7 @@ pub fn assert_snapshot(
     assertion_line: u32,
     expr: &str,
 ) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
     let ctx = SnapshotAssertionContext::prepare(
         &snapshot_value,
         workspace,
@@ -918,14 +1084,14 @@ pub fn assert_snapshot(
         }
     });
 
-    let pass = Settings::with(|settings| {
+    let pass = ctx.with_comparator(&new_snapshot, |comparator| {
         ctx.old_snapshot
             .as_ref()
             .map(|x| {
                 if ctx.tool_config.require_full_match() {
-                    settings.comparator().matches_fully(x, &new_snapshot)
+                    comparator.matches_fully(x, &new_snapshot)
                 } else {
-                    settings.comparator().matches(x, &new_snapshot)
+                    comparator.matches(x, &new_snapshot)
                 }
             })
             .unwrap_or(false)
@@ -938,13 +1104,59 @@ pub fn assert_snapshot(
             ctx.tool_config.snapshot_update(),
             crate::env::SnapshotUpdate::Force
         ) {
+            ctx.record_assertion(&new_snapshot, "updated", started.elapsed());
             ctx.update_snapshot(new_snapshot)?;
+        } else {
+            ctx.record_assertion(&new_snapshot, "pass", started.elapsed());
         }
     // otherwise print information and update snapshots.
     } else {
+        // comparators can explain what made the comparison fail
+        let mut new_snapshot = new_snapshot;
+        new_snapshot.metadata.explanation = ctx.old_snapshot.as_ref().and_then(|old_snapshot| {
+            ctx.with_comparator(&new_snapshot, |comparator| {
+                comparator.explain(old_snapshot, &new_snapshot)
+            })
+        });
         ctx.print_snapshot_info(&new_snapshot);
-        let update_result = ctx.update_snapshot(new_snapshot)?;
-        ctx.finalize(update_result);
+        // comparators can carry parts of the old snapshot over into the new one
+        let new_snapshot = match (ctx.old_snapshot.as_ref(), new_snapshot.contents()) {
+            (Some(old_snapshot), SnapshotContents::Text(contents)) => ctx
+                .with_comparator(&new_snapshot, |comparator| {
+                    comparator.merge(old_snapshot, &new_snapshot)
+                })
+                .map(|merged| {
+                    Snapshot::from_components(
+                        new_snapshot.module_name().to_string(),
+                        new_snapshot.snapshot_name().map(|x| x.to_string()),
+                        new_snapshot.metadata().clone(),
+                        SnapshotContents::Text(TextSnapshotContents::new(merged, contents.kind)),
+                    )
+                })
+                .unwrap_or(new_snapshot),
+            _ => new_snapshot,
+        };
+        let update_result = ctx.update_snapshot(new_snapshot.clone())?;
+        if update_result == SnapshotUpdateBehavior::NewFile {
+            ctx.save_diff_image(&new_snapshot)?;
+        }
+        let is_new = match ctx.old_snapshot.as_ref().map(|x| x.contents()) {
+            None => true,
+            // an empty inline snapshot was never recorded
+            Some(SnapshotContents::Text(old)) => {
+                old.kind == TextSnapshotKind::Inline && old.to_string().is_empty()
+            }
+            Some(SnapshotContents::Binary(_)) => false,
+        };
+        let outcome = if update_result == SnapshotUpdateBehavior::InPlace {
+            "updated"
+        } else if is_new {
+            "new"
+        } else {
+            "fail"
+        };
+        ctx.record_assertion(&new_snapshot, outcome, started.elapsed());
+        ctx.finalize(update_result, new_snapshot.metadata().explanation());
     }
 
     Ok(())
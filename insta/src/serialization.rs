@@ -1,6 +1,7 @@
 use serde::{de::value::Error as ValueError, Serialize};
 #[cfg(feature = "ron")]
 use std::borrow::Cow;
+use std::path::Path;
 #[cfg(feature = "toml")]
 use {
     core::str::FromStr,
@@ -10,6 +11,7 @@ use {
 
 use crate::{
     content::{json, yaml, Content, ContentSerializer},
+    env::{get_tool_config, ToolConfig},
     settings::Settings,
 };
 
@@ -31,14 +33,25 @@ pub enum SnapshotLocation {
     File,
 }
 
-pub fn serialize_content(mut content: Content, format: SerializationFormat) -> String {
+/// Serializes content into a snapshot.
+///
+/// The tool config, if given, contributes the redaction presets from
+/// `insta.yaml`.
+pub fn serialize_content(
+    mut content: Content,
+    format: SerializationFormat,
+    tool_config: Option<&ToolConfig>,
+) -> String {
+    #[cfg(not(feature = "redactions"))]
+    let _ = tool_config;
     content = Settings::with(|settings| {
         if settings.sort_maps() {
             content.sort_maps();
         }
         #[cfg(feature = "redactions")]
         {
-            content = settings.apply_redactions(content);
+            content = settings.apply_redactions(content, tool_config);
+            crate::redaction::finish_redactions(&mut content);
         }
         content
     });
@@ -193,7 +206,18 @@ pub fn serialize_content(mut content: Content, format: SerializationFormat) -> S
 pub fn serialize_value<S: Serialize>(s: &S, format: SerializationFormat) -> String {
     let serializer = ContentSerializer::<ValueError>::new();
     let content = Serialize::serialize(s, serializer).unwrap();
-    serialize_content(content, format)
+    serialize_content(content, format, None)
+}
+
+/// Like [`serialize_value`] but also applies the config of the workspace.
+pub fn serialize_value_for_workspace<S: Serialize>(
+    s: &S,
+    format: SerializationFormat,
+    workspace: &Path,
+) -> String {
+    let serializer = ContentSerializer::<ValueError>::new();
+    let content = Serialize::serialize(s, serializer).unwrap();
+    serialize_content(content, format, Some(&get_tool_config(workspace)))
 }
 
 #[cfg(feature = "redactions")]
@@ -201,13 +225,51 @@ pub fn serialize_value_redacted<S: Serialize>(
     s: &S,
     redactions: &[(crate::redaction::Selector, crate::redaction::Redaction)],
     format: SerializationFormat,
+    workspace: &Path,
 ) -> String {
     let serializer = ContentSerializer::<ValueError>::new();
     let mut content = Serialize::serialize(s, serializer).unwrap();
     for (selector, redaction) in redactions {
         content = selector.redact(content, redaction);
     }
-    serialize_content(content, format)
+    serialize_content(content, format, Some(&get_tool_config(workspace)))
+}
+
+/// Formats a value with `{:#?}` for a debug snapshot and applies redactions.
+///
+/// For this the output is parsed back into [`Content`].  As the redactions
+/// were asked for explicitly this panics if the output is not understood, or
+/// would not print the same way again.
+#[cfg(feature = "redactions")]
+pub fn format_debug_redacted<T: std::fmt::Debug + ?Sized>(
+    value: &T,
+    redactions: &[(crate::redaction::Selector, crate::redaction::Redaction)],
+    workspace: &Path,
+) -> String {
+    use crate::content::debug;
+
+    let text = format!("{value:#?}");
+    let tool_config = get_tool_config(workspace);
+    if redactions.is_empty()
+        && !Settings::with(|settings| settings.has_redactions(Some(&tool_config)))
+    {
+        return text;
+    }
+    let (mut content, names) = match debug::parse_str(&text) {
+        Some((content, names)) if debug::to_string_pretty(&content, &names) == text => {
+            (content, names)
+        }
+        _ => panic!(
+            "cannot apply redactions to a debug snapshot as the output of its Debug \
+             implementation is not understood:\n{text}"
+        ),
+    };
+    for (selector, redaction) in redactions {
+        content = selector.redact(content, redaction);
+    }
+    content = Settings::with(|settings| settings.apply_redactions(content, Some(&tool_config)));
+    crate::redaction::finish_redactions(&mut content);
+    debug::to_string_pretty(&content, &names)
 }
 
 #[test]
@@ -227,6 +289,7 @@ fn test_yaml_serialization() {
             ),
         ]),
         SerializationFormat::Yaml,
+        None,
     );
     crate::assert_snapshot!(&yaml, @"
     env:
@@ -252,6 +315,7 @@ fn test_yaml_serialization() {
             ),
         ]),
         SerializationFormat::Yaml,
+        None,
     );
     crate::assert_snapshot!(&inline_yaml, @"
     env:
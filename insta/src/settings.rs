@@ -8,14 +8,18 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use crate::comparator::Comparator;
+use crate::comparator::{Comparator, DefaultComparator};
 use crate::content::Content;
 #[cfg(feature = "serde")]
 use crate::content::ContentSerializer;
+#[cfg(feature = "redactions")]
+use crate::env::ToolConfig;
 #[cfg(feature = "filters")]
 use crate::filters::Filters;
 #[cfg(feature = "redactions")]
-use crate::redaction::{dynamic_redaction, sorted_redaction, ContentPath, Redaction, Selector};
+use crate::redaction::{
+    dynamic_redaction, sorted_redaction, ContentPath, Redaction, RedactionPreset, Selector,
+};
 
 thread_local!(static CURRENT_SETTINGS: RefCell<Settings> = RefCell::new(Settings::new()));
 
@@ -58,9 +62,11 @@ pub struct ActualSettings {
     pub info: Option<Content>,
     pub omit_expression: bool,
     pub prepend_module_to_snapshot: bool,
-    pub comparator: Box<dyn Comparator>,
+    pub comparator: Option<Box<dyn Comparator>>,
     #[cfg(feature = "redactions")]
     pub redactions: Redactions,
+    #[cfg(feature = "redactions")]
+    pub(crate) redaction_presets: Vec<RedactionPreset>,
     #[cfg(feature = "filters")]
     pub filters: Filters,
     #[cfg(feature = "filters")]
@@ -80,9 +86,11 @@ impl Clone for ActualSettings {
             info: self.info.clone(),
             omit_expression: self.omit_expression,
             prepend_module_to_snapshot: self.prepend_module_to_snapshot,
-            comparator: self.comparator.dyn_clone(),
+            comparator: self.comparator.as_ref().map(|x| x.dyn_clone()),
             #[cfg(feature = "redactions")]
             redactions: self.redactions.clone(),
+            #[cfg(feature = "redactions")]
+            redaction_presets: self.redaction_presets.clone(),
             #[cfg(feature = "filters")]
             filters: self.filters.clone(),
             #[cfg(feature = "filters")]
@@ -125,7 +133,11 @@ impl ActualSettings {
         // settings. The redacted content is then written to the snapshot file
         // as-is without further redaction.
         #[cfg(feature = "redactions")]
-        let content = self.redactions.apply_to_content(content);
+        let content = {
+            let mut content = self.redactions.apply_to_content(content);
+            crate::redaction::finish_redactions(&mut content);
+            content
+        };
 
         self.info = Some(content);
     }
@@ -143,7 +155,7 @@ impl ActualSettings {
     }
 
     pub fn comparator(&mut self, value: Box<dyn Comparator>) {
-        self.comparator = value;
+        self.comparator = Some(value);
     }
 
     #[cfg(feature = "redactions")]
@@ -216,9 +228,11 @@ impl Default for Settings {
                 info: None,
                 omit_expression: false,
                 prepend_module_to_snapshot: true,
-                comparator: Box::new(crate::comparator::DefaultComparator),
+                comparator: None,
                 #[cfg(feature = "redactions")]
                 redactions: Redactions::default(),
+                #[cfg(feature = "redactions")]
+                redaction_presets: Vec::new(),
                 #[cfg(feature = "filters")]
                 filters: Filters::default(),
                 #[cfg(feature = "filters")]
@@ -419,13 +433,26 @@ impl Settings {
     }
 
     /// Retrieves the [`Comparator`] that is currently active.
+    ///
+    /// Unless a comparator was set, this is the [`DefaultComparator`].
     pub fn comparator(&self) -> &dyn Comparator {
-        self.inner.comparator.as_ref()
+        self.inner
+            .comparator
+            .as_deref()
+            .unwrap_or(&DefaultComparator)
+    }
+
+    /// Returns the [`Comparator`] if one was set.
+    ///
+    /// Comparators configured in `insta.yaml` only apply if no comparator
+    /// was set in code.
+    pub(crate) fn explicit_comparator(&self) -> Option<&dyn Comparator> {
+        self.inner.comparator.as_deref()
     }
 
     /// Sets the currently active [`Comparator`] to `value`.
     pub fn set_comparator(&mut self, value: Box<dyn Comparator>) {
-        self._private_inner_mut().comparator = value;
+        self._private_inner_mut().comparator = Some(value);
     }
 
     /// Returns true if expressions are omitted from snapshots.
@@ -439,7 +466,9 @@ impl Settings {
     /// snapshots.
     ///
     /// Note that this only applies to snapshots that undergo serialization
-    /// (eg: does not work for [`assert_debug_snapshot!`](crate::assert_debug_snapshot!).)
+    /// and to [`assert_debug_snapshot!`](crate::assert_debug_snapshot!) when
+    /// redactions (or just `{}`) are passed to it (eg: does not work for
+    /// [`assert_snapshot!`](crate::assert_snapshot!).)
     #[cfg(feature = "redactions")]
     #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
     pub fn add_redaction<R: Into<Redaction>>(&mut self, selector: &str, replacement: R) {
@@ -471,6 +500,36 @@ impl Settings {
         self.add_redaction(selector, dynamic_redaction(func));
     }
 
+    /// Enables a built-in redaction preset.
+    ///
+    /// Presets replace values of a common volatile shape anywhere in a
+    /// snapshot, including within longer strings, without needing a
+    /// selector.  They are applied after the other redactions and leave map
+    /// keys alone, which can be redacted with
+    /// [`map_key_redaction`](crate::map_key_redaction).  See
+    /// [`RedactionPreset`] for the available presets.
+    ///
+    /// Presets can also be enabled for all snapshots with `redactions.presets`
+    /// in `insta.yaml`.
+    ///
+    /// Like all redactions this only applies to snapshots that undergo
+    /// serialization and to debug snapshots.
+    ///
+    /// ```
+    /// use insta::{RedactionPreset, Settings};
+    ///
+    /// let mut settings = Settings::clone_current();
+    /// settings.add_redaction_preset(RedactionPreset::Uuid);
+    /// ```
+    #[cfg(feature = "redactions")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
+    pub fn add_redaction_preset(&mut self, preset: RedactionPreset) {
+        let presets = &mut self._private_inner_mut().redaction_presets;
+        if !presets.contains(&preset) {
+            presets.push(preset);
+        }
+    }
+
     /// A special redaction that sorts a sequence or map.
     ///
     /// This is a shortcut to `add_redaction(selector, sorted_redaction())`.
@@ -493,14 +552,36 @@ impl Settings {
     #[cfg(feature = "redactions")]
     #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
     pub fn clear_redactions(&mut self) {
-        self._private_inner_mut().redactions.0.clear();
+        let inner = self._private_inner_mut();
+        inner.redactions.0.clear();
+        inner.redaction_presets.clear();
+    }
+
+    /// Returns true if any redactions apply, including the presets from the
+    /// tool config.
+    #[cfg(feature = "redactions")]
+    pub(crate) fn has_redactions(&self, tool_config: Option<&ToolConfig>) -> bool {
+        !self.inner.redactions.0.is_empty()
+            || !self.inner.redaction_presets.is_empty()
+            || tool_config.map_or(false, |config| !config.redaction_presets().is_empty())
     }
 
-    /// Apply redactions to content.
+    /// Apply redactions to content, including the presets from the tool
+    /// config.
     #[cfg(feature = "redactions")]
     #[cfg_attr(docsrs, doc(cfg(feature = "redactions")))]
-    pub(crate) fn apply_redactions(&self, content: Content) -> Content {
-        self.inner.redactions.apply_to_content(content)
+    pub(crate) fn apply_redactions(
+        &self,
+        content: Content,
+        tool_config: Option<&ToolConfig>,
+    ) -> Content {
+        let mut content = self.inner.redactions.apply_to_content(content);
+        let mut presets = self.inner.redaction_presets.clone();
+        if let Some(tool_config) = tool_config {
+            presets.extend_from_slice(tool_config.redaction_presets());
+        }
+        crate::redaction::apply_redaction_presets(&mut content, &presets);
+        content
     }
 
     /// Adds a new filter.
@@ -528,6 +609,48 @@ impl Settings {
         self._private_inner_mut().filters.add(regex, replacement);
     }
 
+    /// Adds a new filter with a function computing the replacement.
+    ///
+    /// This works like [`add_filter`](Self::add_filter) but instead of a
+    /// replacement string the function is invoked with the [`regex::Captures`]
+    /// of every match and returns the replacement.  This can be used when the
+    /// replacement depends on the matched value, for instance to normalize
+    /// captured paths or to renumber line numbers.
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// # let mut settings = Settings::new();
+    /// settings.add_filter_fn(r"(?:[A-Z]:)?\\[^\s]+", |caps| caps[0].replace('\\', "/"));
+    /// ```
+    #[cfg(feature = "filters")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
+    pub fn add_filter_fn<F>(&mut self, regex: &str, func: F)
+    where
+        F: Fn(&regex::Captures<'_>) -> String + Sync + Send + 'static,
+    {
+        self._private_inner_mut().filters.add_fn(regex, func);
+    }
+
+    /// Adds a new filter that only applies to lines matching a guard.
+    ///
+    /// The first argument is a [`regex`] that a line of the snapshot has to
+    /// match for the filter to apply, the other two are the same as for
+    /// [`add_filter`](Self::add_filter).  The regexes are matched against
+    /// single lines without their line ending.
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// # let mut settings = Settings::new();
+    /// settings.add_line_filter(r"^Date:", r"\d+", "[N]");
+    /// ```
+    #[cfg(feature = "filters")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "filters")))]
+    pub fn add_line_filter<S: Into<String>>(&mut self, guard: &str, regex: &str, replacement: S) {
+        self._private_inner_mut()
+            .filters
+            .add_line(guard, regex, replacement);
+    }
+
     /// Replaces the currently set filters.
     ///
     /// The default set is empty.
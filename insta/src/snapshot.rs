@@ -167,6 +167,9 @@ pub struct MetaData {
     pub(crate) info: Option<Content>,
     /// Reference to the input file.
     pub(crate) input_file: Option<String>,
+    /// Why the comparator rejected the snapshot. Like the assertion line this
+    /// is only kept for pending snapshots.
+    pub(crate) explanation: Option<String>,
     /// The type of the snapshot (string or binary).
     pub(crate) snapshot_kind: SnapshotKind,
 }
@@ -214,6 +217,19 @@ impl MetaData {
         self.input_file.as_deref()
     }
 
+    /// Returns why the comparator rejected the snapshot.
+    pub fn explanation(&self) -> Option<&str> {
+        self.explanation.as_deref()
+    }
+
+    /// Returns the file extension if this is a binary snapshot.
+    pub fn binary_extension(&self) -> Option<&str> {
+        match self.snapshot_kind {
+            SnapshotKind::Text => None,
+            SnapshotKind::Binary { ref extension } => Some(extension),
+        }
+    }
+
     fn from_content(content: Content) -> Result<MetaData, Box<dyn Error>> {
         if let Content::Map(map) = content {
             let mut source = None;
@@ -222,6 +238,7 @@ impl MetaData {
             let mut expression = None;
             let mut info = None;
             let mut input_file = None;
+            let mut explanation = None;
             let mut snapshot_type = TmpSnapshotKind::Text;
             let mut extension = None;
 
@@ -238,6 +255,7 @@ impl MetaData {
                     Some("expression") => expression = value.as_str().map(Into::into),
                     Some("info") if !value.is_nil() => info = Some(value),
                     Some("input_file") => input_file = value.as_str().map(Into::into),
+                    Some("explanation") => explanation = value.as_str().map(Into::into),
                     Some("snapshot_kind") => {
                         snapshot_type = match value.as_str() {
                             Some("binary") => TmpSnapshotKind::Binary,
@@ -258,6 +276,7 @@ impl MetaData {
                 expression,
                 info,
                 input_file,
+                explanation,
                 snapshot_kind: match snapshot_type {
                     TmpSnapshotKind::Text => SnapshotKind::Text,
                     TmpSnapshotKind::Binary => SnapshotKind::Binary {
@@ -290,6 +309,9 @@ impl MetaData {
         if let Some(input_file) = self.input_file.as_deref() {
             fields.push(("input_file", Content::from(input_file)));
         }
+        if let Some(explanation) = self.explanation.as_deref() {
+            fields.push(("explanation", Content::from(explanation)));
+        }
 
         match self.snapshot_kind {
             SnapshotKind::Text => {}
@@ -312,9 +334,10 @@ impl MetaData {
         // `--require-full-match` is experimental and we're working on making
         // inline & file snapshots more coherent, I'm leaving this as is for
         // now.
-        if self.assertion_line.is_some() {
+        if self.assertion_line.is_some() || self.explanation.is_some() {
             let mut rv = self.clone();
             rv.assertion_line = None;
+            rv.explanation = None;
             Cow::Owned(rv)
         } else {
             Cow::Borrowed(self)
@@ -522,6 +545,17 @@ impl Snapshot {
         self.snapshot.as_text()
     }
 
+    /// Returns a copy of the snapshot with different contents.
+    #[doc(hidden)]
+    pub fn with_contents(&self, contents: SnapshotContents) -> Snapshot {
+        Snapshot {
+            module_name: self.module_name.clone(),
+            snapshot_name: self.snapshot_name.clone(),
+            metadata: self.metadata.clone(),
+            snapshot: contents,
+        }
+    }
+
     fn serialize_snapshot(&self, md: &MetaData) -> String {
         let mut buf = yaml::to_string(&md.as_content());
         buf.push_str("---\n");
@@ -1539,6 +1573,40 @@ fn test_parse_yaml_error() {
     assert!(error.contains("bad.yaml"));
 }
 
+#[test]
+fn test_explanation_only_pending() {
+    let dir = std::env::temp_dir().join(format!("insta-explanation-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("explained.snap");
+    let snapshot = Snapshot::from_components(
+        "explained".into(),
+        None,
+        MetaData {
+            explanation: Some("value at .a differs".into()),
+            ..MetaData::default()
+        },
+        SnapshotContents::Text(TextSnapshotContents::new(
+            "a: 1".into(),
+            TextSnapshotKind::File,
+        )),
+    );
+
+    let pending = snapshot.save_new(&path).unwrap();
+    assert_eq!(
+        Snapshot::from_file(&pending)
+            .unwrap()
+            .metadata()
+            .explanation(),
+        Some("value at .a differs")
+    );
+    snapshot.save(&path).unwrap();
+    assert_eq!(
+        Snapshot::from_file(&path).unwrap().metadata().explanation(),
+        None
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
 /// Check that snapshots don't take ownership of the value
 #[test]
 fn test_ownership() {
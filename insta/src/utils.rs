@@ -2,7 +2,7 @@ use std::{
     borrow::Cow,
     env,
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
@@ -74,6 +74,124 @@ pub fn path_to_storage(path: &Path) -> String {
     }
 }
 
+/// Returns the path of the image a comparator stores the differences in.
+///
+/// This accepts both the path of the snapshot and of its pending `.snap.new`
+/// file and returns `<name>.snap.new.diff.png` for either.
+pub fn diff_image_path(snapshot_path: &Path) -> PathBuf {
+    let snapshot_path = if snapshot_path.extension().map_or(false, |x| x == "new") {
+        snapshot_path.with_extension("")
+    } else {
+        snapshot_path.to_path_buf()
+    };
+    snapshot_path.with_extension("snap.new.diff.png")
+}
+
+/// A compiled glob pattern.
+///
+/// `*` matches any sequence of characters and `?` a single character.  With
+/// a literal separator (for paths) neither matches `/`, `**` matches across
+/// path segments and `**/` also matches no directory at all.
+///
+/// Matching simulates all positions in the pattern at once, so it takes
+/// linear time in the length of the pattern and the text.
+#[derive(Debug, Clone)]
+pub(crate) struct Glob {
+    tokens: Vec<GlobToken>,
+    literal_separator: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GlobToken {
+    Char(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    Star,
+    /// `**`
+    RecursiveStar,
+    /// `**/`, matches nothing or anything ending in `/`
+    RecursivePrefix,
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str, literal_separator: bool) -> Glob {
+        let mut tokens = vec![];
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '*' if literal_separator && chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        GlobToken::RecursivePrefix
+                    } else {
+                        GlobToken::RecursiveStar
+                    }
+                }
+                '*' => GlobToken::Star,
+                '?' => GlobToken::AnyChar,
+                c => GlobToken::Char(c),
+            });
+        }
+        Glob {
+            tokens,
+            literal_separator,
+        }
+    }
+
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        let n = self.tokens.len();
+        // `states[i]` is set if the text so far can be matched by the first
+        // `i` tokens
+        let mut states = vec![false; n + 1];
+        states[0] = true;
+        self.skip_empty(&mut states);
+        for c in text.chars() {
+            let in_segment = !self.literal_separator || c != '/';
+            let mut next = vec![false; n + 1];
+            for (idx, token) in self.tokens.iter().enumerate() {
+                if !states[idx] {
+                    continue;
+                }
+                match *token {
+                    GlobToken::Char(x) if x == c => next[idx + 1] = true,
+                    GlobToken::AnyChar if in_segment => next[idx + 1] = true,
+                    GlobToken::Star if in_segment => next[idx] = true,
+                    GlobToken::RecursiveStar => next[idx] = true,
+                    GlobToken::RecursivePrefix => {
+                        next[idx] = true;
+                        if c == '/' {
+                            next[idx + 1] = true;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            self.skip_empty(&mut next);
+            if !next.contains(&true) {
+                return false;
+            }
+            states = next;
+        }
+        states[n]
+    }
+
+    /// Advances past tokens which can match the empty string.
+    fn skip_empty(&self, states: &mut [bool]) {
+        for (idx, token) in self.tokens.iter().enumerate() {
+            if states[idx]
+                && matches!(
+                    token,
+                    GlobToken::Star | GlobToken::RecursiveStar | GlobToken::RecursivePrefix
+                )
+            {
+                states[idx + 1] = true;
+            }
+        }
+    }
+}
+
 /// Tries to format a given rust expression with rustfmt
 pub fn format_rust_expression(value: &str) -> Cow<'_, str> {
     const PREFIX: &str = "const x:() = ";
@@ -125,3 +243,58 @@ fn test_format_rust_expression() {
     assert_snapshot!(format_rust_expression(r#"  "aoe😄""#), @r#""aoe😄""#);
     assert_snapshot!(format_rust_expression("😄😄😄😄😄"), @"😄😄😄😄😄")
 }
+
+#[test]
+fn test_diff_image_path() {
+    let expected = Path::new("snapshots/foo__bar.snap.new.diff.png");
+    assert_eq!(
+        diff_image_path(Path::new("snapshots/foo__bar.snap")),
+        expected
+    );
+    assert_eq!(
+        diff_image_path(Path::new("snapshots/foo__bar.snap.new")),
+        expected
+    );
+}
+
+#[test]
+fn test_glob() {
+    let glob_matches = |pattern: &str, path: &str| Glob::new(pattern, true).is_match(path);
+    assert!(glob_matches("**/*.snap", "src/snapshots/a__b.snap"));
+    assert!(glob_matches("**/*.snap", "a.snap"));
+    assert!(glob_matches(
+        "tests/snapshots/**/*.json.snap",
+        "tests/snapshots/x/y.json.snap"
+    ));
+    assert!(glob_matches(
+        "tests/snapshots/**/*.json.snap",
+        "tests/snapshots/y.json.snap"
+    ));
+    assert!(!glob_matches(
+        "tests/snapshots/*.snap",
+        "tests/snapshots/x/y.snap"
+    ));
+    assert!(glob_matches(
+        "**/render__*.snap",
+        "tests/snapshots/render__page.snap"
+    ));
+    assert!(!glob_matches(
+        "**/render__*.snap",
+        "tests/snapshots/other__render.snap"
+    ));
+    assert!(glob_matches("src/?.rs", "src/a.rs"));
+    assert!(!glob_matches("src/?.rs", "src/ab.rs"));
+    assert!(!glob_matches("src/?.rs", "src//.rs"));
+    assert!(glob_matches("src/**", "src/a/b.rs"));
+    assert!(!glob_matches("src/*", "src/a/b.rs"));
+    assert!(!glob_matches("*.snap", "a.snap.new"));
+
+    // without a literal separator `*` matches across `/`
+    assert!(Glob::new("*-id", false).is_match("user/group-id"));
+    assert!(!Glob::new("*-id", false).is_match("user-ids"));
+
+    // patterns that backtrack exponentially with a naive matcher
+    let text = format!("{}b", "a".repeat(200));
+    assert!(!glob_matches(&"*a".repeat(30), &text));
+    assert!(!glob_matches(&"**a".repeat(30), &text));
+}
@@ -49,6 +49,28 @@ fn test_strip_ansi_escape_codes_programmatic() {
     });
 }
 
+#[cfg(feature = "filters")]
+#[test]
+fn test_filter_fn_and_line_filter() {
+    use insta::assert_snapshot;
+    let mut settings = insta::Settings::clone_current();
+    settings.add_filter_fn(r"line (\d+)", |caps| {
+        let line: u32 = caps[1].parse().unwrap();
+        format!("line {}", line - 100)
+    });
+    settings.add_line_filter(r"^Elapsed:", r"\d+", "[N]");
+    settings.bind(|| {
+        assert_snapshot!(
+            "error at line 142\nElapsed: 15ms\nRetries: 3",
+            @r"
+        error at line 42
+        Elapsed: [N]ms
+        Retries: 3
+        "
+        );
+    });
+}
+
 #[cfg(feature = "json")]
 #[test]
 fn test_basic_suffixes() {
@@ -30,6 +30,12 @@ fn test_selector_parser() {
     assert_selector_snapshot!("foo_bar_range_from", ".foo.bar[10:]");
     assert_selector_snapshot!("foo_bar_range", ".foo.bar[10:20]");
     assert_selector_snapshot!("foo_bar_deep", ".foo.bar.**");
+    assert_selector_snapshot!("foo_filter", ".foo[?(.kind == \"temp\")].id");
+    assert_selector_snapshot!("foo_glob", ".foo.*-id");
+    assert_selector_snapshot!("foo_deep_glob", ".foo.**_id");
+    assert_selector_snapshot!("foo_deep_glob_dash", ".**-id, .foo.**");
+    assert_selector_snapshot!("foo_regex", ".**.~\"(created|updated)_at\"");
+    assert_eq!(Selector::parse(".foo.~\"(\"").unwrap_err().column(), 5);
 }
 
 #[derive(Serialize)]
@@ -598,6 +604,288 @@ fn test_rounded_redaction() {
     );
 }
 
+#[cfg(feature = "yaml")]
+#[test]
+fn test_filter_redaction() {
+    #[derive(Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Kind {
+        Temp,
+        Stored,
+    }
+
+    #[derive(Serialize)]
+    pub struct Item {
+        id: u32,
+        kind: Kind,
+        size: f64,
+        expires: Option<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Event {
+        r#type: &'static str,
+        value: &'static str,
+    }
+
+    let items = vec![
+        Item {
+            id: 1,
+            kind: Kind::Temp,
+            size: 0.5,
+            expires: Some("2024-06-01"),
+        },
+        Item {
+            id: 2,
+            kind: Kind::Stored,
+            size: 12.0,
+            expires: None,
+        },
+    ];
+
+    assert_yaml_snapshot!(items, {
+        "[?(.kind == \"temp\")].id" => "[temp-id]",
+        "[?(.size >= 10)].size" => "[large]",
+        "[?(.expires)].expires" => "[date]",
+    }, @r#"
+    - id: "[temp-id]"
+      kind: temp
+      size: 0.5
+      expires: "[date]"
+    - id: 2
+      kind: stored
+      size: "[large]"
+      expires: ~
+    "#);
+
+    let events = vec![
+        vec![Event {
+            r#type: "timestamp",
+            value: "12:00",
+        }],
+        vec![Event {
+            r#type: "name",
+            value: "john",
+        }],
+    ];
+    assert_yaml_snapshot!(events, {
+        ".**[?(.type == \"timestamp\")].value" => "[time]",
+    }, @r#"
+    - - type: timestamp
+        value: "[time]"
+    - - type: name
+        value: john
+    "#);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_map_key_redaction_sorted() {
+    use std::collections::HashMap;
+
+    #[derive(Serialize)]
+    pub struct Item {
+        id: &'static str,
+        name: &'static str,
+        parent: Option<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Tree {
+        items: HashMap<&'static str, Item>,
+        counts: HashMap<&'static str, u32>,
+    }
+
+    let mut items = HashMap::new();
+    for item in [
+        Item {
+            id: "e2a0c4f1",
+            name: "root",
+            parent: None,
+        },
+        Item {
+            id: "7b19d3aa",
+            name: "child",
+            parent: Some("e2a0c4f1"),
+        },
+    ] {
+        items.insert(item.id, item);
+    }
+    let tree = Tree {
+        items,
+        counts: vec![("user-a81f", 2), ("group-77", 5), ("user-03bc", 1)]
+            .into_iter()
+            .collect(),
+    };
+
+    assert_yaml_snapshot!(tree, {
+        ".items" => insta::map_key_redaction(insta::stable_id_redaction()),
+        ".items.*.id" => insta::stable_id_redaction(),
+        ".items.*.parent" => insta::stable_id_redaction(),
+        ".counts" => insta::map_key_redaction(insta::dynamic_redaction(|value, path| {
+            assert_eq!(path.to_string(), ".counts.$key");
+            format!("[{}]", value.as_str().unwrap().split('-').next().unwrap())
+        })),
+    }, @r#"
+    items:
+      "[id-1]":
+        id: "[id-1]"
+        name: child
+        parent: "[id-2]"
+      "[id-2]":
+        id: "[id-2]"
+        name: root
+        parent: ~
+    counts:
+      "[group]": 5
+      "[user]": 1
+      "[user]": 2
+    "#);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_redaction_presets() {
+    #[derive(Serialize)]
+    pub struct Job {
+        id: &'static str,
+        started: &'static str,
+        elapsed: std::time::Duration,
+        log: Vec<String>,
+    }
+
+    let job = Job {
+        id: "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        started: "2024-01-01T10:00:00Z",
+        elapsed: std::time::Duration::from_millis(1500),
+        log: vec![
+            "job 67e55044-10b1-426f-9247-bb680e5fe0c8 started".into(),
+            format!("took {:?}", std::time::Duration::from_micros(1250)),
+        ],
+    };
+
+    let mut settings = insta::Settings::new();
+    settings.add_redaction_preset(insta::RedactionPreset::Uuid);
+    settings.add_redaction_preset(insta::RedactionPreset::Timestamp);
+    settings.add_redaction_preset(insta::RedactionPreset::Duration);
+    settings.bind(|| {
+        assert_yaml_snapshot!(job, @r#"
+        id: "[uuid]"
+        started: "[timestamp]"
+        elapsed: "[duration]"
+        log:
+          - "job [uuid] started"
+          - "took [duration]"
+        "#);
+    });
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_key_pattern_redaction() {
+    #[derive(Serialize)]
+    pub struct Item {
+        name: &'static str,
+        updated_at: &'static str,
+        deleted_at: Option<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    pub struct Response {
+        created_at: &'static str,
+        headers: std::collections::BTreeMap<&'static str, &'static str>,
+        items: Vec<Item>,
+    }
+
+    let response = Response {
+        created_at: "2024-01-01T10:00:00Z",
+        headers: vec![
+            ("content-type", "application/json"),
+            ("x-request-id", "d1c2b3a4"),
+            ("x-trace-id", "a4b3c2d1"),
+        ]
+        .into_iter()
+        .collect(),
+        items: vec![Item {
+            name: "a",
+            updated_at: "2024-01-02T10:00:00Z",
+            deleted_at: None,
+        }],
+    };
+
+    assert_json_snapshot!(response, {
+        ".**.~\"(created|updated)_at\"" => "[timestamp]",
+        ".headers.*-id" => "[id]",
+    }, @r#"
+    {
+      "created_at": "[timestamp]",
+      "headers": {
+        "content-type": "application/json",
+        "x-request-id": "[id]",
+        "x-trace-id": "[id]"
+      },
+      "items": [
+        {
+          "name": "a",
+          "updated_at": "[timestamp]",
+          "deleted_at": null
+        }
+      ]
+    }
+    "#);
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_stable_id_redaction() {
+    #[derive(Serialize)]
+    pub struct Document {
+        id: String,
+        owner_id: String,
+        editor_ids: Vec<String>,
+    }
+
+    let docs = vec![
+        Document {
+            id: "0c6a1d5e".into(),
+            owner_id: "f3b2e7a4".into(),
+            editor_ids: vec!["f3b2e7a4".into(), "9d8c7b6a".into()],
+        },
+        Document {
+            id: "5a4b3c2d".into(),
+            owner_id: "9d8c7b6a".into(),
+            editor_ids: vec![],
+        },
+    ];
+
+    let mut settings = insta::Settings::new();
+    settings.add_redaction("[].owner_id", insta::stable_id_redaction());
+    settings.bind(|| {
+        assert_yaml_snapshot!(docs, {
+            "[].id" => insta::stable_id_redaction(),
+            "[].editor_ids[]" => insta::stable_id_redaction(),
+        }, @r#"
+        - id: "[id-1]"
+          owner_id: "[id-2]"
+          editor_ids:
+            - "[id-2]"
+            - "[id-3]"
+        - id: "[id-4]"
+          owner_id: "[id-3]"
+          editor_ids: []
+        "#);
+    });
+
+    // the numbering starts over for every snapshot
+    assert_yaml_snapshot!(&docs[1], {
+        ".owner_id" => insta::stable_id_redaction(),
+    }, @r#"
+    id: 5a4b3c2d
+    owner_id: "[id-1]"
+    editor_ids: []
+    "#);
+}
+
 #[cfg(feature = "yaml")]
 #[test]
 fn test_named_redacted_with_debug_expr() {
@@ -704,3 +992,124 @@ fn test_metadata_raw_info_no_redaction() {
         assert_yaml_snapshot!("metadata_raw_info_no_redaction", &vec![1, 2, 3]);
     });
 }
+
+#[test]
+fn test_debug_redaction() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Session {
+        id: u64,
+        user: &'static str,
+        tokens: Vec<(u32, &'static str)>,
+    }
+
+    let session = Session {
+        id: 42,
+        user: "john",
+        tokens: vec![(1, "secret"), (2, "other secret")],
+    };
+
+    assert_debug_snapshot!(session, {
+        ".id" => "[id]",
+        ".tokens[][1]" => "[token]",
+    }, @r#"
+    Session {
+        id: "[id]",
+        user: "john",
+        tokens: [
+            (
+                1,
+                "[token]",
+            ),
+            (
+                2,
+                "[token]",
+            ),
+        ],
+    }
+    "#);
+
+    // the redactions from the settings only apply when asked for
+    let mut settings = insta::Settings::new();
+    settings.add_redaction(".user", "[user]");
+    settings.bind(|| {
+        assert_debug_snapshot!(session, @r#"
+        Session {
+            id: 42,
+            user: "john",
+            tokens: [
+                (
+                    1,
+                    "secret",
+                ),
+                (
+                    2,
+                    "other secret",
+                ),
+            ],
+        }
+        "#);
+        assert_debug_snapshot!(session, {}, @r#"
+        Session {
+            id: 42,
+            user: "[user]",
+            tokens: [
+                (
+                    1,
+                    "secret",
+                ),
+                (
+                    2,
+                    "other secret",
+                ),
+            ],
+        }
+        "#);
+    });
+}
+
+#[test]
+fn test_debug_redaction_std_types() {
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Request {
+        peer: std::net::IpAddr,
+        elapsed: std::time::Duration,
+        min_score: f64,
+        retries: u32,
+    }
+
+    let request = Request {
+        peer: "10.0.0.7".parse().unwrap(),
+        elapsed: std::time::Duration::from_micros(1250),
+        min_score: f64::NEG_INFINITY,
+        retries: 2,
+    };
+
+    assert_debug_snapshot!(request, {
+        ".peer" => "[peer]",
+        ".elapsed" => "[elapsed]",
+    }, @r#"
+    Request {
+        peer: "[peer]",
+        elapsed: "[elapsed]",
+        min_score: -inf,
+        retries: 2,
+    }
+    "#);
+}
+
+#[test]
+#[should_panic = "cannot apply redactions to a debug snapshot"]
+fn test_debug_redaction_not_understood() {
+    struct Opaque;
+
+    impl std::fmt::Debug for Opaque {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            // unlike derived implementations this omits the trailing comma
+            f.write_str("Opaque {\n    id: 1\n}")
+        }
+    }
+
+    assert_debug_snapshot!(Opaque, { ".id" => "[id]" }, @"Opaque { id: 1 }");
+}